@@ -58,7 +58,14 @@ impl TraitInfo {
         let trait_name = &self.name;
         let vis = &self.visibility;
 
-        let (cache_fields, cache_field_default_assigns, mode_setters, api_method_wrappers): (
+        let (
+            cache_fields,
+            cache_field_default_assigns,
+            mode_setters,
+            method_mode_setters,
+            api_method_wrappers,
+        ): (
+            TokenStream,
             TokenStream,
             TokenStream,
             TokenStream,
@@ -68,6 +75,7 @@ impl TraitInfo {
                 method.generate_cache_fields(),
                 method.generate_cache_field_default_assign(),
                 method.generate_mode_setter(),
+                method.generate_method_mode_setter(),
                 method.generate_api_method_wrapper(),
             )
         }));
@@ -95,6 +103,8 @@ impl TraitInfo {
                     pub async fn set_all_modes(&mut self, mode_plan: ModePlan) {
                         #mode_setters
                     }
+
+                    #method_mode_setters
                 }
 
                 #[async_trait::async_trait]
@@ -170,6 +180,18 @@ impl TraitMethodInfo {
         }
     }
 
+    fn generate_method_mode_setter(&self) -> TokenStream {
+        let mode_field_name = make_mode_field_name(&self.name);
+        let setter_name = format_ident!("set_{}_mode", self.name);
+
+        quote! {
+            #[allow(dead_code)]
+            pub async fn #setter_name(&mut self, mode_plan: ModePlan) {
+                (*self. #mode_field_name .lock().await) = mode_plan.into_mode();
+            }
+        }
+    }
+
     fn generate_api_method_wrapper(&self) -> TokenStream {
         let name = &self.name;
         let mode_field_name = make_mode_field_name(&self.name);
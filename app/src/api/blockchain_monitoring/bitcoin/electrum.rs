@@ -0,0 +1,840 @@
+//! Backend talking to Electrum protocol server(e.g. electrs), see
+//! [specification](https://electrum-protocol.readthedocs.io/en/latest/protocol-methods.html).
+//! Server indexes history by script hashes, so addresses of the account are scanned one by one.
+
+use std::{fmt, str::FromStr};
+
+use ::bitcoin::{
+    Address, OutPoint, Transaction, TxOut, Txid,
+    block::Header,
+    consensus::encode::{deserialize, serialize},
+    hashes::Hash,
+    hashes::sha256,
+};
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{
+        TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::Mutex,
+};
+
+use super::{
+    super::{
+        Account, Network, NetworkApi, NetworkApiConfig, SignedTransaction, Token, TransactionInfo,
+        TransactionUid, UnsignedTransaction,
+    },
+    ListedTransaction, ListedTransactions, UsedAddress, list_transactions, listed_transaction,
+    scan_addresses, transaction_type,
+    transfer::{
+        FEE_TARGET_BLOCKS, MIN_FEE_RATE, SpendableOutput, build_transfer, change_account,
+        finalize_transaction,
+    },
+};
+
+const CLIENT_NAME: &str = "ledger-tui";
+const PROTOCOL_VERSION: &str = "1.4";
+
+pub struct Api {
+    network: Network,
+    client: Client,
+    gap_limit: u32,
+    transactions: ListedTransactions,
+}
+
+#[derive(Deserialize)]
+struct HistoryItem {
+    /// Zero or negative for mempool transactions.
+    height: i64,
+    tx_hash: String,
+}
+
+#[derive(Deserialize)]
+struct Utxo {
+    tx_hash: String,
+    tx_pos: u32,
+    value: u64,
+}
+
+#[derive(Deserialize)]
+struct Balance {
+    confirmed: u64,
+    /// Negative if unconfirmed transactions spend confirmed outputs.
+    unconfirmed: i64,
+}
+
+impl Api {
+    pub fn new(network: Network, config: NetworkApiConfig) -> Api {
+        Api {
+            network,
+            client: Client::new(&config.endpoint),
+            gap_limit: config.address_gap_limit,
+            transactions: Default::default(),
+        }
+    }
+
+    /// Used addresses of the account with their history.
+    async fn scan(&self, account: &Account) -> Option<Vec<UsedAddress<HistoryItem>>> {
+        scan_addresses(
+            self.network,
+            account,
+            self.gap_limit,
+            |address| async move {
+                self.client
+                    .call(
+                        "blockchain.scripthash.get_history",
+                        json!([script_hash(&address)]),
+                    )
+                    .await
+                    .inspect_err(|e| log::error!("Failed to fetch address history: {}", e))
+                    .ok()
+            },
+        )
+        .await
+    }
+
+    async fn get_raw_transaction(&self, txid: &Txid) -> Result<Transaction, Error> {
+        let tx: String = self
+            .client
+            .call("blockchain.transaction.get", json!([txid.to_string()]))
+            .await?;
+
+        decode_hex(&tx)
+    }
+
+    /// Fee rate in sat/vB, server estimates it in BTC/kB and responds with -1 if it can't.
+    async fn estimate_fee_rate(&self) -> u64 {
+        let fee_rate: f64 = self
+            .client
+            .call("blockchain.estimatefee", json!([FEE_TARGET_BLOCKS]))
+            .await
+            .inspect_err(|e| log::error!("Failed to estimate fee rate: {}", e))
+            .unwrap_or(-1.0);
+
+        if fee_rate <= 0.0 {
+            log::warn!("Fee rate is not estimated, using the minimal one");
+            return MIN_FEE_RATE;
+        }
+
+        // 1 BTC/kB is 10^8 sats per 1000 bytes.
+        ((fee_rate * 100_000.0).ceil() as u64).max(MIN_FEE_RATE)
+    }
+
+    async fn get_block_time(&self, height: i64) -> Result<DateTime<Utc>, Error> {
+        let header: String = self
+            .client
+            .call("blockchain.block.header", json!([height]))
+            .await?;
+        let header: Header = decode_hex(&header)?;
+
+        DateTime::from_timestamp(header.time as i64, 0)
+            .ok_or_else(|| Error::InvalidResponse("block time is out of range".to_string()))
+    }
+
+    async fn describe_transaction(
+        &self,
+        txid: &Txid,
+        context: &ListedTransaction,
+    ) -> Result<TransactionInfo, Error> {
+        let tx = self.get_raw_transaction(txid).await?;
+
+        let mut prevouts: Vec<TxOut> = vec![];
+        if !tx.is_coin_base() {
+            for input in &tx.input {
+                let previous_output = input.previous_output;
+                let previous_tx = self.get_raw_transaction(&previous_output.txid).await?;
+                let prevout = previous_tx
+                    .output
+                    .get(previous_output.vout as usize)
+                    .ok_or_else(|| {
+                        Error::InvalidResponse(format!("output {} not found", previous_output))
+                    })?;
+
+                prevouts.push(prevout.clone());
+            }
+        }
+
+        let timestamp = match context.height {
+            Some(height) => self.get_block_time(height as i64).await?,
+            None => Utc::now(),
+        };
+
+        Ok(TransactionInfo {
+            ty: transaction_type(self.network, &prevouts, &tx.output, &context.scripts),
+            timestamp,
+            token: None,
+        })
+    }
+}
+
+#[async_trait]
+impl NetworkApi for Api {
+    async fn get_balance(&self, account: &Account) -> BigDecimal {
+        let Some(addresses) = self.scan(account).await else {
+            return BigDecimal::zero();
+        };
+
+        let mut balance = 0;
+        for UsedAddress { address, .. } in addresses {
+            match self
+                .client
+                .call::<Balance>(
+                    "blockchain.scripthash.get_balance",
+                    json!([script_hash(&address)]),
+                )
+                .await
+            {
+                Ok(address_balance) => {
+                    balance += address_balance.confirmed as i64 + address_balance.unconfirmed
+                }
+                Err(e) => log::error!("Failed to fetch balance of {}: {}", address, e),
+            }
+        }
+
+        BigDecimal::new(balance.into(), self.network.get_info().decimals as i64)
+    }
+
+    async fn get_token_balance(&self, _account: &Account, _token: &Token) -> BigDecimal {
+        log::error!("Tokens are not supported on bitcoin network");
+        BigDecimal::zero()
+    }
+
+    /// Transactions of all the used addresses of the account, latest first.
+    async fn get_transactions(&self, account: &Account) -> Vec<TransactionUid> {
+        let Some(addresses) = self.scan(account).await else {
+            return vec![];
+        };
+
+        list_transactions(addresses, &self.transactions, |item| {
+            (
+                &item.tx_hash,
+                (item.height > 0).then_some(item.height as u64),
+            )
+        })
+        .await
+    }
+
+    async fn get_transaction_info(&self, tx_uid: &TransactionUid) -> Option<TransactionInfo> {
+        let (txid, context) = listed_transaction(&self.transactions, tx_uid).await?;
+
+        self.describe_transaction(&txid, &context)
+            .await
+            .inspect_err(|e| log::error!("Failed to fetch transaction {}: {}", txid, e))
+            .ok()
+    }
+
+    async fn is_account_used(&self, account: &Account) -> bool {
+        self.scan(account)
+            .await
+            .is_some_and(|addresses| !addresses.is_empty())
+    }
+
+    async fn prepare_transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let addresses = self.scan(from).await?;
+
+        let mut utxos = vec![];
+        for used in &addresses {
+            let script_pubkey = used.address.script_pubkey();
+            let address_utxos: Vec<Utxo> = self
+                .client
+                .call(
+                    "blockchain.scripthash.listunspent",
+                    json!([script_hash(&used.address)]),
+                )
+                .await
+                .inspect_err(|e| log::error!("Failed to fetch outputs of {}: {}", used.address, e))
+                .ok()?;
+
+            for utxo in address_utxos {
+                let Ok(txid) = Txid::from_str(&utxo.tx_hash) else {
+                    log::error!("Invalid transaction hash received: {}", utxo.tx_hash);
+                    return None;
+                };
+
+                utxos.push(SpendableOutput {
+                    outpoint: OutPoint::new(txid, utxo.tx_pos),
+                    txout: TxOut {
+                        value: utxo.value,
+                        script_pubkey: script_pubkey.clone(),
+                    },
+                    owner: used.account.clone(),
+                });
+            }
+        }
+
+        let change = change_account(from, addresses.iter().map(|used| &used.account))?;
+
+        build_transfer(
+            self.network,
+            utxos,
+            to,
+            amount,
+            self.estimate_fee_rate().await,
+            &change,
+            |txid| async move {
+                self.get_raw_transaction(&txid)
+                    .await
+                    .inspect_err(|e| log::error!("Failed to fetch transaction {}: {}", txid, e))
+                    .ok()
+            },
+        )
+        .await
+    }
+
+    async fn prepare_token_transfer(
+        &self,
+        _token: &Token,
+        _from: &Account,
+        _to: &Account,
+        _amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        log::error!("Tokens are not supported on bitcoin network");
+        None
+    }
+
+    /// Signature of the transaction is the signed PSBT.
+    async fn send_transaction(&self, tx: &SignedTransaction) -> Option<TransactionUid> {
+        let tx = finalize_transaction(&tx.signature)?;
+
+        let txid: String = self
+            .client
+            .call(
+                "blockchain.transaction.broadcast",
+                json!([hex::encode(serialize(&tx))]),
+            )
+            .await
+            .inspect_err(|e| log::error!("Failed to broadcast transaction {}: {}", tx.txid(), e))
+            .ok()?;
+
+        match Txid::from_str(&txid) {
+            Ok(txid) => Some(TransactionUid {
+                uid: txid.to_string(),
+            }),
+            Err(e) => {
+                log::error!("Invalid transaction hash received: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Electrum protocol identifies addresses by SHA256 of their script in reversed byte order.
+fn script_hash(address: &Address) -> String {
+    let mut hash = sha256::Hash::hash(address.script_pubkey().as_bytes()).to_byte_array();
+    hash.reverse();
+
+    hex::encode(hash)
+}
+
+fn decode_hex<T: ::bitcoin::consensus::Decodable>(data: &str) -> Result<T, Error> {
+    let data = hex::decode(data).map_err(|e| Error::InvalidResponse(e.to_string()))?;
+    deserialize(&data).map_err(|e| Error::InvalidResponse(e.to_string()))
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(std::io::Error),
+    /// Server responded with an error object.
+    Server(Value),
+    InvalidResponse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "connection error: {}", e),
+            Self::Server(e) => write!(f, "server error: {}", e),
+            Self::InvalidResponse(e) => write!(f, "invalid response: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// JSON-RPC client sending newline delimited requests over TCP. Requests are sent one at a
+/// time, connection is established on the first request and re-established after failures.
+struct Client {
+    /// `host:port` of the server, optionally prefixed with `tcp://`.
+    endpoint: String,
+    connection: Mutex<Option<Connection>>,
+}
+
+struct Connection {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    next_id: u64,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    id: Option<u64>,
+    result: Option<Value>,
+    error: Option<Value>,
+}
+
+impl Client {
+    fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint
+                .strip_prefix("tcp://")
+                .unwrap_or(endpoint)
+                .to_string(),
+            connection: Mutex::new(None),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let mut connection = self.connection.lock().await;
+
+        if connection.is_none() {
+            *connection = Some(self.connect().await?);
+        }
+
+        let result = connection
+            .as_mut()
+            .expect("Connection is established above")
+            .request(method, params)
+            .await;
+        // State of the stream is unknown after failure, so it's not reused.
+        if let Err(Error::Io(_) | Error::InvalidResponse(_)) = result {
+            *connection = None;
+        }
+
+        serde_json::from_value(result?).map_err(|e| Error::InvalidResponse(e.to_string()))
+    }
+
+    async fn connect(&self) -> Result<Connection, Error> {
+        let stream = TcpStream::connect(&self.endpoint).await?;
+        let (reader, writer) = stream.into_split();
+
+        let mut connection = Connection {
+            reader: BufReader::new(reader),
+            writer,
+            next_id: 0,
+        };
+        // Server expects version negotiation before any other request.
+        connection
+            .request("server.version", json!([CLIENT_NAME, PROTOCOL_VERSION]))
+            .await?;
+
+        Ok(connection)
+    }
+}
+
+impl Connection {
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, Error> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+        request.push('\n');
+        self.writer.write_all(request.as_bytes()).await?;
+
+        // Notifications of subscriptions can arrive between responses, they have no id.
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line).await? == 0 {
+                return Err(Error::InvalidResponse(
+                    "connection closed by server".to_string(),
+                ));
+            }
+
+            let response: Response =
+                serde_json::from_str(&line).map_err(|e| Error::InvalidResponse(e.to_string()))?;
+            if response.id != Some(id) {
+                continue;
+            }
+
+            return match (response.result, response.error) {
+                (_, Some(error)) => Err(Error::Server(error)),
+                (Some(result), None) => Ok(result),
+                (None, None) => Ok(Value::Null),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex as SyncMutex},
+    };
+
+    use ::bitcoin::{
+        ScriptBuf, Sequence, TxIn, WPubkeyHash, Witness, absolute::LockTime, block,
+        hash_types::TxMerkleNode, psbt::Psbt,
+    };
+    use rust_decimal::Decimal;
+    use tokio::net::TcpListener;
+
+    use super::{super::super::TransactionType, *};
+    use crate::api::{
+        common_types::{AddressChain, bip84_test_account},
+        ledger::SoftwareSigner,
+    };
+
+    const BLOCK_TIME: u32 = 1_700_000_000;
+
+    fn transaction(inputs: &[OutPoint], outputs: &[(u64, ScriptBuf)]) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: inputs
+                .iter()
+                .map(|previous_output| TxIn {
+                    previous_output: *previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: outputs
+                .iter()
+                .map(|(value, script_pubkey)| TxOut {
+                    value: *value,
+                    script_pubkey: script_pubkey.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Serves canned responses, `handler` maps method and params to the result.
+    async fn serve(handler: impl Fn(&str, &Value) -> Value + Send + Sync + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = listener.local_addr().unwrap().to_string();
+        let handler = Arc::new(handler);
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let handler = handler.clone();
+
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let request: Value = serde_json::from_str(&line).unwrap();
+                        let method = request["method"].as_str().unwrap();
+                        let result = match method {
+                            "server.version" => json!(["electrs", PROTOCOL_VERSION]),
+                            _ => handler(method, &request["params"]),
+                        };
+
+                        let response =
+                            json!({"jsonrpc": "2.0", "id": request["id"], "result": result});
+                        writer
+                            .write_all(format!("{}\n", response).as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_electrum_account_history() {
+        let network: Network = "bitcoin-regtest".parse().unwrap();
+        let account = bip84_test_account(1);
+        let address = |chain, index| {
+            let address_account = account.derive_address_account(chain, index).unwrap();
+            super::super::account_address(network, &address_account).unwrap()
+        };
+        let receive = address(AddressChain::Receive, 0);
+        let change = address(AddressChain::Change, 0);
+        let foreign = ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::all_zeros());
+        let foreign_address = Address::from_script(&foreign, network.bitcoin_network())
+            .unwrap()
+            .to_string();
+
+        let source = transaction(&[], &[(200_000, foreign.clone())]);
+        let funding = transaction(
+            &[OutPoint::new(source.txid(), 0)],
+            &[
+                (150_000, receive.script_pubkey()),
+                (49_000, foreign.clone()),
+            ],
+        );
+        // Spends the funding output, 1000 sats go to fee.
+        let spending = transaction(
+            &[OutPoint::new(funding.txid(), 0)],
+            &[(100_000, foreign.clone()), (49_000, change.script_pubkey())],
+        );
+        let header = block::Header {
+            version: block::Version::TWO,
+            prev_blockhash: Hash::all_zeros(),
+            merkle_root: TxMerkleNode::all_zeros(),
+            time: BLOCK_TIME,
+            bits: Default::default(),
+            nonce: 0,
+        };
+
+        let histories = HashMap::from([
+            (
+                script_hash(&receive),
+                json!([
+                    {"height": 101, "tx_hash": funding.txid().to_string()},
+                    {"height": 0, "tx_hash": spending.txid().to_string()},
+                ]),
+            ),
+            (
+                script_hash(&change),
+                json!([{"height": 0, "tx_hash": spending.txid().to_string()}]),
+            ),
+        ]);
+        let balances = HashMap::from([
+            (
+                script_hash(&receive),
+                json!({"confirmed": 150_000, "unconfirmed": -150_000}),
+            ),
+            (
+                script_hash(&change),
+                json!({"confirmed": 0, "unconfirmed": 49_000}),
+            ),
+        ]);
+        let transactions: HashMap<_, _> = [&source, &funding, &spending]
+            .into_iter()
+            .map(|tx| (tx.txid().to_string(), hex::encode(serialize(tx))))
+            .collect();
+        let header = hex::encode(serialize(&header));
+
+        let endpoint = serve(move |method, params| {
+            let param = params[0].as_str().unwrap_or_default();
+            match method {
+                "blockchain.scripthash.get_history" => {
+                    histories.get(param).cloned().unwrap_or(json!([]))
+                }
+                "blockchain.scripthash.get_balance" => balances[param].clone(),
+                "blockchain.transaction.get" => json!(transactions[param]),
+                "blockchain.block.header" => {
+                    assert_eq!(params[0], 101);
+                    json!(header)
+                }
+                _ => panic!("Unexpected method {}", method),
+            }
+        })
+        .await;
+
+        let api = Api::new(
+            network,
+            NetworkApiConfig {
+                endpoint: format!("tcp://{}", endpoint),
+                backend: None,
+                address_gap_limit: 2,
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+        );
+
+        assert!(api.is_account_used(&account).await);
+        assert_eq!(
+            api.get_balance(&account).await,
+            BigDecimal::from_str("0.00049").unwrap()
+        );
+
+        let txs = api.get_transactions(&account).await;
+        assert_eq!(
+            txs,
+            [spending.txid(), funding.txid()].map(|txid| TransactionUid {
+                uid: txid.to_string()
+            })
+        );
+
+        let spending_info = api.get_transaction_info(&txs[0]).await.unwrap();
+        let TransactionType::Withdraw { to, amount } = spending_info.ty else {
+            panic!("Spending transaction should be a withdrawal");
+        };
+        assert_eq!(to.public_key, foreign_address);
+        assert_eq!(amount, Decimal::new(101_000, 8));
+
+        let funding_info = api.get_transaction_info(&txs[1]).await.unwrap();
+        let TransactionType::Deposit { from, amount } = funding_info.ty else {
+            panic!("Funding transaction should be a deposit");
+        };
+        assert_eq!(from.public_key, foreign_address);
+        assert_eq!(amount, Decimal::new(150_000, 8));
+        assert_eq!(funding_info.timestamp.timestamp(), BLOCK_TIME as i64);
+    }
+
+    #[tokio::test]
+    async fn test_electrum_send_transfer() {
+        let network = Network::BITCOIN;
+        let account = bip84_test_account(0);
+        let address = |chain, index| {
+            let address_account = account.derive_address_account(chain, index).unwrap();
+            super::super::account_address(network, &address_account).unwrap()
+        };
+        let receive = address(AddressChain::Receive, 0);
+        let change = address(AddressChain::Change, 0);
+        let foreign = ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::all_zeros());
+        let receiver = Account::external(
+            Address::from_script(&foreign, network.bitcoin_network())
+                .unwrap()
+                .to_string(),
+        );
+
+        // Change address is used, so change goes to the next one.
+        let funding = transaction(
+            &[OutPoint::null()],
+            &[
+                (70_000, receive.script_pubkey()),
+                (20_000, change.script_pubkey()),
+            ],
+        );
+        let history = json!([{"height": 1, "tx_hash": funding.txid().to_string()}]);
+        let unspent = HashMap::from([
+            (
+                script_hash(&receive),
+                json!([{"tx_hash": funding.txid().to_string(), "tx_pos": 0, "height": 1, "value": 70_000}]),
+            ),
+            (
+                script_hash(&change),
+                json!([{"tx_hash": funding.txid().to_string(), "tx_pos": 1, "height": 1, "value": 20_000}]),
+            ),
+        ]);
+        let funding_txid = funding.txid().to_string();
+        let funding_hex = hex::encode(serialize(&funding));
+
+        let broadcast = Arc::new(SyncMutex::new(None));
+        let endpoint = {
+            let broadcast = broadcast.clone();
+
+            serve(move |method, params| {
+                let param = params[0].as_str().unwrap_or_default();
+                match method {
+                    "blockchain.scripthash.get_history" => {
+                        if unspent.contains_key(param) {
+                            history.clone()
+                        } else {
+                            json!([])
+                        }
+                    }
+                    "blockchain.scripthash.listunspent" => unspent[param].clone(),
+                    "blockchain.estimatefee" => {
+                        assert_eq!(params[0], 6);
+                        json!(0.00002)
+                    }
+                    "blockchain.transaction.get" => {
+                        assert_eq!(param, funding_txid);
+                        json!(funding_hex)
+                    }
+                    "blockchain.transaction.broadcast" => {
+                        let tx: Transaction = decode_hex(param).unwrap();
+                        let txid = tx.txid().to_string();
+                        *broadcast.lock().unwrap() = Some(tx);
+                        json!(txid)
+                    }
+                    _ => panic!("Unexpected method {}", method),
+                }
+            })
+            .await
+        };
+
+        let api = Api::new(
+            network,
+            NetworkApiConfig {
+                endpoint,
+                backend: None,
+                address_gap_limit: 2,
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+        );
+
+        let unsigned = api
+            .prepare_transfer(&account, &receiver, "0.0008".parse().unwrap())
+            .await
+            .unwrap();
+        let psbt = Psbt::deserialize(&unsigned.payload).unwrap();
+
+        // Both outputs are needed, fee of 2 sat/vB for 209 vB is 418 sats.
+        assert_eq!(psbt.unsigned_tx.input.len(), 2);
+        assert_eq!(
+            psbt.unsigned_tx.output,
+            [
+                TxOut {
+                    value: 80_000,
+                    script_pubkey: foreign,
+                },
+                TxOut {
+                    value: 90_000 - 80_000 - 356 - 62,
+                    script_pubkey: address(AddressChain::Change, 1).script_pubkey(),
+                },
+            ]
+        );
+
+        let signature = SoftwareSigner::new()
+            .sign_message(
+                &unsigned.payload,
+                network,
+                account.derivation_path.as_ref().unwrap(),
+            )
+            .unwrap();
+        let tx_uid = api
+            .send_transaction(&SignedTransaction {
+                unsigned,
+                signature,
+            })
+            .await
+            .unwrap();
+
+        let broadcast = broadcast.lock().unwrap().clone().unwrap();
+        assert_eq!(tx_uid.uid, psbt.unsigned_tx.txid().to_string());
+        assert_eq!(broadcast.txid(), psbt.unsigned_tx.txid());
+        assert!(broadcast.input.iter().all(|input| input.witness.len() == 2));
+    }
+
+    #[ignore = "requires local electrs over regtest bitcoind"]
+    #[tokio::test]
+    async fn test_electrum_regtest() {
+        let api = Api::new(
+            "bitcoin-regtest".parse().unwrap(),
+            NetworkApiConfig {
+                endpoint: "127.0.0.1:60401".to_string(),
+                backend: None,
+                address_gap_limit: 20,
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+        );
+
+        // First receive address of BIP84 test vector, `bcrt1qcr8te4kr609gcawutmrza0j4xv80jy8zeqchgx`.
+        let account = Account {
+            xpub: None,
+            ..bip84_test_account(1)
+        };
+
+        let txs = api.get_transactions(&account).await;
+        for tx in &txs {
+            assert!(api.get_transaction_info(tx).await.is_some());
+        }
+        assert_eq!(api.is_account_used(&account).await, !txs.is_empty());
+    }
+}
@@ -0,0 +1,631 @@
+//! Backend talking to Esplora REST API, see
+//! [specification](https://github.com/Blockstream/esplora/blob/master/API.md).
+//! History is indexed by addresses, so addresses of the account are scanned one by one.
+
+use std::{collections::HashMap, str::FromStr};
+
+use ::bitcoin::{
+    Address, OutPoint, ScriptBuf, TxOut, Txid,
+    consensus::encode::{deserialize, serialize},
+};
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, de::DeserializeOwned};
+
+use super::{
+    super::{
+        Account, Network, NetworkApi, NetworkApiConfig, SignedTransaction, Token, TransactionInfo,
+        TransactionUid, UnsignedTransaction,
+    },
+    ListedTransactions, UsedAddress, list_transactions, listed_transaction, scan_addresses,
+    transaction_type,
+    transfer::{
+        FEE_TARGET_BLOCKS, MIN_FEE_RATE, SpendableOutput, build_transfer, change_account,
+        finalize_transaction,
+    },
+};
+
+/// Number of confirmed transactions returned per page of address history.
+const CHAIN_PAGE_SIZE: usize = 25;
+
+pub struct Api {
+    network: Network,
+    client: reqwest::Client,
+    /// Base URL of the API without trailing slash, e.g. `https://blockstream.info/api`.
+    url: String,
+    gap_limit: u32,
+    transactions: ListedTransactions,
+}
+
+#[derive(Deserialize)]
+struct Transaction {
+    txid: String,
+    vin: Vec<Input>,
+    vout: Vec<Output>,
+    status: Status,
+}
+
+#[derive(Deserialize)]
+struct Input {
+    /// Missing for coinbase inputs.
+    prevout: Option<Output>,
+}
+
+#[derive(Deserialize)]
+struct Output {
+    /// Hex encoded script.
+    scriptpubkey: String,
+    value: u64,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    confirmed: bool,
+    block_height: Option<u64>,
+    block_time: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Utxo {
+    txid: String,
+    vout: u32,
+    value: u64,
+}
+
+impl Output {
+    fn to_tx_out(&self) -> Option<TxOut> {
+        let script_pubkey = ScriptBuf::from_hex(&self.scriptpubkey)
+            .inspect_err(|e| log::error!("Invalid output script received: {}", e))
+            .ok()?;
+
+        Some(TxOut {
+            value: self.value,
+            script_pubkey,
+        })
+    }
+}
+
+impl Api {
+    pub fn new(network: Network, config: NetworkApiConfig) -> Api {
+        Api {
+            network,
+            client: reqwest::Client::new(),
+            url: config.endpoint.trim_end_matches('/').to_string(),
+            gap_limit: config.address_gap_limit,
+            transactions: Default::default(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Option<T> {
+        let text = self.get_text(path).await?;
+
+        serde_json::from_str(&text)
+            .inspect_err(|e| log::error!("Invalid response received from {}: {}", path, e))
+            .ok()
+    }
+
+    /// Some of the endpoints respond with plain text, e.g. hex of the transaction.
+    async fn get_text(&self, path: &str) -> Option<String> {
+        let url = format!("{}{}", self.url, path);
+
+        self.send(self.client.get(&url), &url).await
+    }
+
+    async fn send(&self, request: RequestBuilder, url: &str) -> Option<String> {
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Request to {} failed: {}", url, e);
+                return None;
+            }
+        };
+
+        response
+            .text()
+            .await
+            .inspect_err(|e| log::error!("Invalid response received from {}: {}", url, e))
+            .ok()
+    }
+
+    /// Full history of the address. First page contains mempool transactions followed by the
+    /// latest confirmed ones, older confirmed transactions are paged by the last seen txid.
+    async fn get_address_history(&self, address: &Address) -> Option<Vec<Transaction>> {
+        let mut history: Vec<Transaction> = self.get(&format!("/address/{}/txs", address)).await?;
+
+        let mut page_size = history.iter().filter(|tx| tx.status.confirmed).count();
+        while page_size >= CHAIN_PAGE_SIZE {
+            let last_seen = &history.last()?.txid;
+            let page: Vec<Transaction> = self
+                .get(&format!("/address/{}/txs/chain/{}", address, last_seen))
+                .await?;
+
+            page_size = page.len();
+            history.extend(page);
+        }
+
+        Some(history)
+    }
+
+    /// Used addresses of the account with their history.
+    async fn scan(&self, account: &Account) -> Option<Vec<UsedAddress<Transaction>>> {
+        scan_addresses(
+            self.network,
+            account,
+            self.gap_limit,
+            |address| async move { self.get_address_history(&address).await },
+        )
+        .await
+    }
+
+    /// Unspent outputs of the address, unconfirmed ones included.
+    async fn get_utxos(&self, address: &Address) -> Option<Vec<Utxo>> {
+        self.get(&format!("/address/{}/utxo", address)).await
+    }
+
+    async fn get_raw_transaction(&self, txid: Txid) -> Option<::bitcoin::Transaction> {
+        let tx = self.get_text(&format!("/tx/{}/hex", txid)).await?;
+
+        hex::decode(tx.trim())
+            .ok()
+            .and_then(|tx| deserialize(&tx).ok())
+            .or_else(|| {
+                log::error!("Invalid transaction {} received", txid);
+                None
+            })
+    }
+
+    /// Fee rate in sat/vB, estimates are keyed by confirmation target in blocks.
+    async fn estimate_fee_rate(&self) -> u64 {
+        let estimates: HashMap<String, f64> = self.get("/fee-estimates").await.unwrap_or_default();
+
+        match estimates.get(&FEE_TARGET_BLOCKS.to_string()) {
+            Some(&fee_rate) if fee_rate > 0.0 => (fee_rate.ceil() as u64).max(MIN_FEE_RATE),
+            _ => {
+                log::warn!("Fee rate is not estimated, using the minimal one");
+                MIN_FEE_RATE
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl NetworkApi for Api {
+    /// Sum of unspent outputs of the used addresses, unconfirmed ones included.
+    async fn get_balance(&self, account: &Account) -> BigDecimal {
+        let Some(addresses) = self.scan(account).await else {
+            return BigDecimal::zero();
+        };
+
+        let mut balance = 0;
+        for UsedAddress { address, .. } in addresses {
+            let utxos = self.get_utxos(&address).await.unwrap_or_default();
+
+            balance += utxos.iter().map(|utxo| utxo.value).sum::<u64>();
+        }
+
+        BigDecimal::new(balance.into(), self.network.get_info().decimals as i64)
+    }
+
+    async fn get_token_balance(&self, _account: &Account, _token: &Token) -> BigDecimal {
+        log::error!("Tokens are not supported on bitcoin network");
+        BigDecimal::zero()
+    }
+
+    /// Transactions of all the used addresses of the account, mempool ones first followed
+    /// by the confirmed ones, latest first.
+    async fn get_transactions(&self, account: &Account) -> Vec<TransactionUid> {
+        let Some(addresses) = self.scan(account).await else {
+            return vec![];
+        };
+
+        list_transactions(addresses, &self.transactions, |tx| {
+            (&tx.txid, tx.status.block_height)
+        })
+        .await
+    }
+
+    async fn get_transaction_info(&self, tx_uid: &TransactionUid) -> Option<TransactionInfo> {
+        let (txid, listed) = listed_transaction(&self.transactions, tx_uid).await?;
+
+        let tx: Transaction = self.get(&format!("/tx/{}", txid)).await?;
+
+        let prevouts = tx
+            .vin
+            .iter()
+            .filter_map(|input| input.prevout.as_ref())
+            .map(Output::to_tx_out)
+            .collect::<Option<Vec<_>>>()?;
+        let outputs = tx
+            .vout
+            .iter()
+            .map(Output::to_tx_out)
+            .collect::<Option<Vec<_>>>()?;
+
+        let timestamp = match tx.status.block_time {
+            Some(block_time) => DateTime::from_timestamp(block_time, 0)?,
+            None => Utc::now(),
+        };
+
+        Some(TransactionInfo {
+            ty: transaction_type(self.network, &prevouts, &outputs, &listed.scripts),
+            timestamp,
+            token: None,
+        })
+    }
+
+    async fn is_account_used(&self, account: &Account) -> bool {
+        self.scan(account)
+            .await
+            .is_some_and(|addresses| !addresses.is_empty())
+    }
+
+    async fn prepare_transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let addresses = self.scan(from).await?;
+
+        let mut utxos = vec![];
+        for used in &addresses {
+            let script_pubkey = used.address.script_pubkey();
+
+            for utxo in self.get_utxos(&used.address).await? {
+                let Ok(txid) = Txid::from_str(&utxo.txid) else {
+                    log::error!("Invalid transaction hash received: {}", utxo.txid);
+                    return None;
+                };
+
+                utxos.push(SpendableOutput {
+                    outpoint: OutPoint::new(txid, utxo.vout),
+                    txout: TxOut {
+                        value: utxo.value,
+                        script_pubkey: script_pubkey.clone(),
+                    },
+                    owner: used.account.clone(),
+                });
+            }
+        }
+
+        let change = change_account(from, addresses.iter().map(|used| &used.account))?;
+
+        build_transfer(
+            self.network,
+            utxos,
+            to,
+            amount,
+            self.estimate_fee_rate().await,
+            &change,
+            |txid| self.get_raw_transaction(txid),
+        )
+        .await
+    }
+
+    async fn prepare_token_transfer(
+        &self,
+        _token: &Token,
+        _from: &Account,
+        _to: &Account,
+        _amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        log::error!("Tokens are not supported on bitcoin network");
+        None
+    }
+
+    /// Signature of the transaction is the signed PSBT.
+    async fn send_transaction(&self, tx: &SignedTransaction) -> Option<TransactionUid> {
+        let tx = finalize_transaction(&tx.signature)?;
+
+        let url = format!("{}/tx", self.url);
+        let request = self.client.post(&url).body(hex::encode(serialize(&tx)));
+        let txid = self.send(request, &url).await?;
+
+        match Txid::from_str(txid.trim()) {
+            Ok(txid) => Some(TransactionUid {
+                uid: txid.to_string(),
+            }),
+            Err(e) => {
+                log::error!("Invalid transaction hash received: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex as SyncMutex};
+
+    use ::bitcoin::{
+        Sequence, TxIn, WPubkeyHash, Witness, absolute::LockTime, hashes::Hash, psbt::Psbt,
+    };
+    use rust_decimal::Decimal;
+    use serde_json::{Value, json};
+
+    use super::{
+        super::{super::TransactionType, account_address},
+        *,
+    };
+    use crate::api::{
+        common_types::{AddressChain, bip84_test_account},
+        ledger::SoftwareSigner,
+        stand_in,
+    };
+
+    const BLOCK_TIME: i64 = 1_700_000_000;
+
+    fn txid(idx: u8) -> String {
+        hex::encode([idx; 32])
+    }
+
+    fn output(script: &ScriptBuf, value: u64) -> Value {
+        json!({"scriptpubkey": script.to_hex_string(), "value": value})
+    }
+
+    fn status(height: Option<u64>) -> Value {
+        match height {
+            Some(height) => json!({
+                "confirmed": true,
+                "block_height": height,
+                "block_time": BLOCK_TIME + height as i64,
+            }),
+            None => json!({"confirmed": false}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_esplora_account_history() {
+        let network: Network = "bitcoin-regtest".parse().unwrap();
+        let account = bip84_test_account(1);
+        let address = |chain, index| {
+            let address_account = account.derive_address_account(chain, index).unwrap();
+            account_address(network, &address_account).unwrap()
+        };
+        let receive = address(AddressChain::Receive, 0);
+        let change = address(AddressChain::Change, 0);
+        let foreign = ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::all_zeros());
+        let foreign_address = Address::from_script(&foreign, network.bitcoin_network())
+            .unwrap()
+            .to_string();
+
+        // Receive address is reused by 30 deposits of 10000 sats confirmed in blocks 1..=30,
+        // so its history spans two pages. The last deposit is spent by unconfirmed transaction
+        // returning change to the change address.
+        let deposits: Vec<Value> = (1..=30u8)
+            .rev()
+            .map(|idx| {
+                json!({
+                    "txid": txid(idx),
+                    "vin": [{"prevout": output(&foreign, 20_000)}],
+                    "vout": [output(&receive.script_pubkey(), 10_000), output(&foreign, 9_000)],
+                    "status": status(Some(idx as u64)),
+                })
+            })
+            .collect();
+        let spending = json!({
+            "txid": txid(100),
+            "vin": [{"prevout": output(&receive.script_pubkey(), 10_000)}],
+            "vout": [output(&foreign, 3_000), output(&change.script_pubkey(), 6_000)],
+            "status": status(None),
+        });
+
+        let mut transactions: HashMap<String, Value> = deposits
+            .iter()
+            .chain([&spending])
+            .map(|tx| (tx["txid"].as_str().unwrap().to_string(), tx.clone()))
+            .collect();
+        let mut routes = HashMap::from([
+            (
+                format!("/address/{}/txs", receive),
+                json!([vec![spending.clone()], deposits[..25].to_vec()].concat()),
+            ),
+            (
+                format!("/address/{}/txs/chain/{}", receive, txid(6)),
+                json!(deposits[25..]),
+            ),
+            (
+                format!("/address/{}/utxo", receive),
+                json!(
+                    (2..=30)
+                        .map(|idx| json!({"txid": txid(idx), "vout": 0, "value": 10_000}))
+                        .collect::<Vec<_>>()
+                ),
+            ),
+            (format!("/address/{}/txs", change), json!([spending])),
+            (
+                format!("/address/{}/utxo", change),
+                json!([{"txid": txid(100), "vout": 1, "value": 6_000}]),
+            ),
+        ]);
+        routes.extend(
+            transactions
+                .drain()
+                .map(|(txid, tx)| (format!("/tx/{}", txid), tx)),
+        );
+
+        let url = stand_in::serve(move |path, _| {
+            if let Some(tx) = routes.get(path) {
+                return Some(tx.clone());
+            }
+
+            // Unused addresses.
+            path.ends_with("/txs").then(|| json!([]))
+        })
+        .await;
+
+        let api = Api::new(
+            network,
+            NetworkApiConfig {
+                endpoint: format!("{}/", url),
+                backend: None,
+                address_gap_limit: 2,
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+        );
+
+        assert!(api.is_account_used(&account).await);
+        assert_eq!(
+            api.get_balance(&account).await,
+            BigDecimal::from_str("0.00296").unwrap()
+        );
+
+        let txs = api.get_transactions(&account).await;
+        let expected: Vec<_> = [100]
+            .into_iter()
+            .chain((1..=30).rev())
+            .map(|idx| TransactionUid { uid: txid(idx) })
+            .collect();
+        assert_eq!(txs, expected);
+
+        let spending_info = api.get_transaction_info(&txs[0]).await.unwrap();
+        let TransactionType::Withdraw { to, amount } = spending_info.ty else {
+            panic!("Spending transaction should be a withdrawal");
+        };
+        assert_eq!(to.public_key, foreign_address);
+        assert_eq!(amount, Decimal::new(4_000, 8));
+
+        let deposit_info = api.get_transaction_info(&txs[30]).await.unwrap();
+        let TransactionType::Deposit { from, amount } = deposit_info.ty else {
+            panic!("Deposit transaction should be a deposit");
+        };
+        assert_eq!(from.public_key, foreign_address);
+        assert_eq!(amount, Decimal::new(10_000, 8));
+        assert_eq!(deposit_info.timestamp.timestamp(), BLOCK_TIME + 1);
+    }
+
+    #[tokio::test]
+    async fn test_esplora_send_transfer() {
+        let network = Network::BITCOIN;
+        let account = bip84_test_account(0);
+        let receive = account
+            .derive_address_account(AddressChain::Receive, 0)
+            .unwrap();
+        let receive_address = account_address(network, &receive).unwrap();
+        let foreign = ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::all_zeros());
+        let receiver = Account::external(
+            Address::from_script(&foreign, network.bitcoin_network())
+                .unwrap()
+                .to_string(),
+        );
+
+        let funding = ::bitcoin::Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: 100_000,
+                script_pubkey: receive_address.script_pubkey(),
+            }],
+        };
+        let funding_txid = funding.txid().to_string();
+        let funding_hex = hex::encode(serialize(&funding));
+
+        let broadcast = Arc::new(SyncMutex::new(None));
+        let url = {
+            let broadcast = broadcast.clone();
+
+            stand_in::serve(move |path, body| {
+                let response = match path {
+                    "/fee-estimates" => json!({"2": 10.5, "6": 3.2}).to_string(),
+                    "/tx" => {
+                        let tx: ::bitcoin::Transaction =
+                            deserialize(&hex::decode(body).unwrap()).unwrap();
+                        let txid = tx.txid().to_string();
+                        *broadcast.lock().unwrap() = Some(tx);
+                        txid
+                    }
+                    _ if path == format!("/address/{}/txs", receive_address) => json!([{
+                        "txid": funding_txid,
+                        "vin": [],
+                        "vout": [output(&receive_address.script_pubkey(), 100_000)],
+                        "status": status(Some(1)),
+                    }])
+                    .to_string(),
+                    _ if path == format!("/address/{}/utxo", receive_address) => {
+                        json!([{"txid": funding_txid, "vout": 0, "value": 100_000}]).to_string()
+                    }
+                    _ if path == format!("/tx/{}/hex", funding_txid) => funding_hex.clone(),
+                    // Unused addresses.
+                    _ if path.ends_with("/txs") => json!([]).to_string(),
+                    _ => return None,
+                };
+
+                Some(response)
+            })
+            .await
+        };
+
+        let api = Api::new(
+            network,
+            NetworkApiConfig {
+                endpoint: url,
+                backend: None,
+                address_gap_limit: 2,
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+        );
+
+        let unsigned = api
+            .prepare_transfer(&account, &receiver, "0.0004".parse().unwrap())
+            .await
+            .unwrap();
+        let psbt = Psbt::deserialize(&unsigned.payload).unwrap();
+
+        // Estimate of 3.2 sat/vB is rounded up, so fee of 110 vB transaction is 440 sats.
+        let change = account
+            .derive_address_account(AddressChain::Change, 0)
+            .unwrap();
+        assert_eq!(
+            psbt.unsigned_tx.output,
+            [
+                TxOut {
+                    value: 40_000,
+                    script_pubkey: foreign,
+                },
+                TxOut {
+                    value: 100_000 - 40_000 - 440 - 124,
+                    script_pubkey: account_address(network, &change).unwrap().script_pubkey(),
+                },
+            ]
+        );
+        assert_eq!(psbt.inputs[0].non_witness_utxo.as_ref(), Some(&funding));
+        assert_eq!(psbt.inputs[0].bip32_derivation.len(), 1);
+        assert_eq!(psbt.outputs[1].bip32_derivation.len(), 1);
+
+        let signature = SoftwareSigner::new()
+            .sign_message(
+                &unsigned.payload,
+                network,
+                account.derivation_path.as_ref().unwrap(),
+            )
+            .unwrap();
+        let tx_uid = api
+            .send_transaction(&SignedTransaction {
+                unsigned,
+                signature,
+            })
+            .await
+            .unwrap();
+
+        let broadcast = broadcast.lock().unwrap().clone().unwrap();
+        assert_eq!(tx_uid.uid, psbt.unsigned_tx.txid().to_string());
+        assert_eq!(broadcast.txid(), psbt.unsigned_tx.txid());
+        assert_eq!(broadcast.input[0].witness.len(), 2);
+    }
+}
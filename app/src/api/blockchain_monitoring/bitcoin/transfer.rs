@@ -0,0 +1,508 @@
+//! Transfers of coins of the account. Backends provide unspent outputs of the account, PSBT
+//! spending them is built here the same way for all of them, so that ledger bitcoin app
+//! recognizes its inputs and change output as internal ones.
+
+use std::{cmp::Reverse, collections::BTreeMap, future::Future, str::FromStr};
+
+use ::bitcoin::{
+    OutPoint, PublicKey, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+    absolute::LockTime,
+    bip32::{ChildNumber, Fingerprint, KeySource},
+    key::XOnlyPublicKey,
+    psbt::Psbt,
+    script::{Builder, PushBytes},
+    taproot::TapLeafHash,
+};
+use bigdecimal::{BigDecimal, ToPrimitive};
+
+use super::{
+    super::{Account, Network, UnsignedTransaction},
+    account_address,
+};
+use crate::api::common_types::{AddressChain, DerivationScheme};
+
+/// Number of blocks transaction is expected to be confirmed within, fee rate is estimated
+/// for it.
+pub const FEE_TARGET_BLOCKS: u32 = 6;
+
+/// Fee rate in sat/vB used if backend can't estimate one, it's the minimal relay fee rate.
+pub const MIN_FEE_RATE: u64 = 1;
+
+/// Outputs below this value aren't relayed by nodes. It's the limit of P2PKH outputs,
+/// which is the highest among the supported script types.
+const DUST_LIMIT: u64 = 546;
+
+/// Virtual size of transaction version, lock time, input and output counts and segwit marker.
+const TX_OVERHEAD_VSIZE: u64 = 11;
+
+/// Unspent output of the account.
+pub struct SpendableOutput {
+    pub outpoint: OutPoint,
+    pub txout: TxOut,
+    /// Account of the address output belongs to, it has the full derivation path.
+    pub owner: Account,
+}
+
+/// Builds PSBT sending `amount` to `to` with fee rate `fee_rate` sat/vB. Inputs are selected
+/// from `utxos` largest first, change is sent to `change` unless it's below the dust limit.
+/// `previous_transaction` fetches transaction by its id, transactions creating the spent
+/// outputs are included into PSBT as device verifies input amounts against them.
+///
+/// Key origins are set for every input and for the change output, so the device signs inputs
+/// and doesn't show change as a recipient. Fingerprint of the master key is unknown here, so
+/// it's left zero and filled in by the ledger api.
+pub async fn build_transfer<F, Fut>(
+    network: Network,
+    mut utxos: Vec<SpendableOutput>,
+    to: &Account,
+    amount: BigDecimal,
+    fee_rate: u64,
+    change: &Account,
+    mut previous_transaction: F,
+) -> Option<UnsignedTransaction>
+where
+    F: FnMut(Txid) -> Fut,
+    Fut: Future<Output = Option<Transaction>>,
+{
+    let Some(receiver) = account_address(network, to) else {
+        log::error!("Invalid receiver address {}", to.public_key);
+        return None;
+    };
+    let change_address = account_address(network, change)?;
+
+    let amount = to_sats(network, amount)?;
+    if amount < DUST_LIMIT {
+        log::error!("Amount of {} sats is below the dust limit", amount);
+        return None;
+    }
+
+    let receiver_vsize = TX_OVERHEAD_VSIZE + output_vsize(&receiver.script_pubkey());
+    let change_vsize = output_vsize(&change_address.script_pubkey());
+
+    utxos.sort_by_key(|utxo| Reverse(utxo.txout.value));
+
+    let mut selected = vec![];
+    let mut inputs_vsize = 0;
+    let mut total = 0;
+    for utxo in utxos {
+        let Some(vsize) = input_vsize(&utxo.txout.script_pubkey) else {
+            log::warn!(
+                "Output {} has unsupported script, skipping it",
+                utxo.outpoint
+            );
+            continue;
+        };
+
+        inputs_vsize += vsize;
+        total += utxo.txout.value;
+        selected.push(utxo);
+
+        if total >= amount + (receiver_vsize + inputs_vsize) * fee_rate {
+            break;
+        }
+    }
+
+    let fee = (receiver_vsize + inputs_vsize) * fee_rate;
+    if total < amount + fee {
+        log::error!(
+            "Insufficient funds: {} sats available, {} sats required",
+            total,
+            amount + fee
+        );
+        return None;
+    }
+
+    // Change which doesn't cover its own output goes to the fee.
+    let change_value = (total - amount - fee)
+        .checked_sub(change_vsize * fee_rate)
+        .filter(|&value| value >= DUST_LIMIT);
+
+    let mut output = vec![TxOut {
+        value: amount,
+        script_pubkey: receiver.script_pubkey(),
+    }];
+    if let Some(value) = change_value {
+        output.push(TxOut {
+            value,
+            script_pubkey: change_address.script_pubkey(),
+        });
+    }
+
+    let tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: selected
+            .iter()
+            .map(|utxo| TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output,
+    };
+
+    let mut psbt = Psbt::from_unsigned_tx(tx).ok()?;
+
+    for (input, utxo) in psbt.inputs.iter_mut().zip(&selected) {
+        let script = &utxo.txout.script_pubkey;
+        let (public_key, source) = key_origin(network, &utxo.owner)?;
+
+        add_key_origin(
+            script,
+            public_key,
+            source,
+            &mut input.bip32_derivation,
+            &mut input.tap_internal_key,
+            &mut input.tap_key_origins,
+        );
+
+        // Taproot signatures commit to amounts of all the inputs, so previous transactions
+        // aren't needed for them. Legacy inputs have amounts committed only this way.
+        if !script.is_v1_p2tr() {
+            input.non_witness_utxo = Some(
+                previous_output_tx(&utxo.outpoint, &utxo.txout, &mut previous_transaction).await?,
+            );
+        }
+        if !script.is_p2pkh() {
+            input.witness_utxo = Some(utxo.txout.clone());
+        }
+        // Nested segwit output commits to the witness program.
+        if script.is_p2sh() {
+            input.redeem_script = Some(ScriptBuf::new_v0_p2wpkh(&public_key.wpubkey_hash()?));
+        }
+    }
+
+    if change_value.is_some() {
+        let (public_key, source) = key_origin(network, change)?;
+        let output = &mut psbt.outputs[1];
+
+        add_key_origin(
+            &change_address.script_pubkey(),
+            public_key,
+            source,
+            &mut output.bip32_derivation,
+            &mut output.tap_internal_key,
+            &mut output.tap_key_origins,
+        );
+    }
+
+    Some(UnsignedTransaction {
+        payload: psbt.serialize(),
+    })
+}
+
+/// Transaction the output is created by, checked to match the output.
+async fn previous_output_tx<F, Fut>(
+    outpoint: &OutPoint,
+    txout: &TxOut,
+    previous_transaction: &mut F,
+) -> Option<Transaction>
+where
+    F: FnMut(Txid) -> Fut,
+    Fut: Future<Output = Option<Transaction>>,
+{
+    let tx = previous_transaction(outpoint.txid).await?;
+
+    if tx.txid() != outpoint.txid || tx.output.get(outpoint.vout as usize) != Some(txout) {
+        log::error!(
+            "Transaction received for output {} doesn't match it",
+            outpoint
+        );
+        return None;
+    }
+
+    Some(tx)
+}
+
+/// Account of the change address following the used ones on the change chain, `used` are
+/// accounts of the used addresses. Change is returned to the account itself if it has no xpub.
+pub fn change_account<'a>(
+    account: &Account,
+    used: impl IntoIterator<Item = &'a Account>,
+) -> Option<Account> {
+    if account.xpub.is_none() {
+        return Some(account.clone());
+    }
+
+    let index = used
+        .into_iter()
+        .filter_map(|used| match used.derivation_path.as_ref()?.components() {
+            [.., chain, index] if *chain == AddressChain::Change.index() => Some(index + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+
+    account.derive_address_account(AddressChain::Change, index)
+}
+
+/// Moves signatures of the signed PSBT into its transaction, `None` if some of the inputs
+/// isn't signed.
+pub fn finalize_transaction(signed: &[u8]) -> Option<Transaction> {
+    let psbt = Psbt::deserialize(signed)
+        .inspect_err(|e| log::error!("Failed to deserialize signed PSBT: {}", e))
+        .ok()?;
+
+    let mut tx = psbt.unsigned_tx.clone();
+    for (index, (tx_input, input)) in tx.input.iter_mut().zip(&psbt.inputs).enumerate() {
+        let script = &psbt.spend_utxo(index).ok()?.script_pubkey;
+
+        if script.is_v1_p2tr() {
+            let Some(signature) = input.tap_key_sig else {
+                log::error!("Input #{} is not signed", index);
+                return None;
+            };
+
+            tx_input.witness = Witness::from_slice(&[signature.to_vec()]);
+            continue;
+        }
+
+        let Some((public_key, signature)) = input.partial_sigs.iter().next() else {
+            log::error!("Input #{} is not signed", index);
+            return None;
+        };
+
+        if script.is_p2pkh() {
+            tx_input.script_sig = Builder::new()
+                .push_slice(signature.serialize())
+                .push_key(public_key)
+                .into_script();
+            continue;
+        }
+
+        tx_input.witness = Witness::from_slice(&[signature.to_vec(), public_key.to_bytes()]);
+        if script.is_p2sh() {
+            let redeem_script: &PushBytes =
+                input.redeem_script.as_ref()?.as_bytes().try_into().ok()?;
+            tx_input.script_sig = Builder::new().push_slice(redeem_script).into_script();
+        }
+    }
+
+    Some(tx)
+}
+
+fn to_sats(network: Network, amount: BigDecimal) -> Option<u64> {
+    let decimals = network.get_info().decimals as i64;
+
+    (amount * BigDecimal::new(1.into(), -decimals))
+        .with_scale(0)
+        .to_u64()
+}
+
+/// Public key of the address account along with its origin.
+fn key_origin(network: Network, account: &Account) -> Option<(PublicKey, KeySource)> {
+    let public_key = PublicKey::from_str(&account.public_key)
+        .inspect_err(|e| log::error!("Invalid public key of the account: {}", e))
+        .ok()?;
+    let path: Vec<_> = account
+        .derivation_path
+        .clone()
+        .unwrap_or_else(|| DerivationScheme::default_path(network))
+        .components()
+        .iter()
+        .copied()
+        .map(ChildNumber::from)
+        .collect();
+
+    Some((public_key, (Fingerprint::default(), path.into())))
+}
+
+/// Sets key origin of the input or output with the script, taproot ones are spent with
+/// the tweaked internal key.
+fn add_key_origin(
+    script: &ScriptBuf,
+    public_key: PublicKey,
+    source: KeySource,
+    bip32_derivation: &mut BTreeMap<::bitcoin::secp256k1::PublicKey, KeySource>,
+    tap_internal_key: &mut Option<XOnlyPublicKey>,
+    tap_key_origins: &mut BTreeMap<XOnlyPublicKey, (Vec<TapLeafHash>, KeySource)>,
+) {
+    if script.is_v1_p2tr() {
+        let (x_only, _) = public_key.inner.x_only_public_key();
+        *tap_internal_key = Some(x_only);
+        tap_key_origins.insert(x_only, (vec![], source));
+    } else {
+        bip32_derivation.insert(public_key.inner, source);
+    }
+}
+
+/// Virtual size of input spending output with the script, `None` for unsupported scripts.
+fn input_vsize(script: &ScriptBuf) -> Option<u64> {
+    if script.is_v0_p2wpkh() {
+        Some(68)
+    } else if script.is_p2sh() {
+        Some(91)
+    } else if script.is_p2pkh() {
+        Some(148)
+    } else if script.is_v1_p2tr() {
+        Some(58)
+    } else {
+        None
+    }
+}
+
+/// Virtual size of output with the script: value, script length and the script itself.
+fn output_vsize(script: &ScriptBuf) -> u64 {
+    8 + 1 + script.len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use ::bitcoin::{WPubkeyHash, hashes::Hash};
+
+    use super::*;
+    use crate::api::{common_types::bip84_test_account, ledger::SoftwareSigner};
+
+    fn funding_transaction(outputs: &[(&Account, u64)]) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: outputs
+                .iter()
+                .map(|(account, value)| TxOut {
+                    value: *value,
+                    script_pubkey: account_address(Network::BITCOIN, account)
+                        .unwrap()
+                        .script_pubkey(),
+                })
+                .collect(),
+        }
+    }
+
+    fn spendable_outputs(funding: &Transaction, owners: &[&Account]) -> Vec<SpendableOutput> {
+        owners
+            .iter()
+            .enumerate()
+            .map(|(vout, owner)| SpendableOutput {
+                outpoint: OutPoint::new(funding.txid(), vout as u32),
+                txout: funding.output[vout].clone(),
+                owner: (*owner).clone(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_build_and_finalize_transfer() {
+        let network = Network::BITCOIN;
+        let account = bip84_test_account(0);
+        let address_account = |chain, index| account.derive_address_account(chain, index).unwrap();
+        let receive = address_account(AddressChain::Receive, 0);
+        let change = address_account(AddressChain::Change, 0);
+        let small = address_account(AddressChain::Receive, 1);
+        let receiver = Account::external(
+            ::bitcoin::Address::from_script(
+                &ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::all_zeros()),
+                network.bitcoin_network(),
+            )
+            .unwrap()
+            .to_string(),
+        );
+
+        let funding =
+            funding_transaction(&[(&receive, 50_000), (&change, 30_000), (&small, 5_000)]);
+        let owners = [&receive, &change, &small];
+        let next_change = change_account(&account, owners).unwrap();
+        assert_eq!(
+            next_change.derivation_path,
+            Some("m/84'/0'/0'/1/1".parse().unwrap())
+        );
+
+        let build = |amount: &str| {
+            build_transfer(
+                network,
+                spendable_outputs(&funding, &owners),
+                &receiver,
+                amount.parse().unwrap(),
+                2,
+                &next_change,
+                |txid| {
+                    assert_eq!(txid, funding.txid());
+                    let funding = funding.clone();
+                    async move { Some(funding) }
+                },
+            )
+        };
+
+        assert!(build("0.001").await.is_none());
+
+        let unsigned = build("0.0006").await.unwrap();
+        let psbt = Psbt::deserialize(&unsigned.payload).unwrap();
+
+        // Two largest outputs cover the amount and the fee of 2 sat/vB for 178 vB.
+        let tx = &psbt.unsigned_tx;
+        let inputs: Vec<_> = tx.input.iter().map(|input| input.previous_output).collect();
+        assert_eq!(
+            inputs,
+            [
+                OutPoint::new(funding.txid(), 0),
+                OutPoint::new(funding.txid(), 1)
+            ]
+        );
+        assert_eq!(tx.output[0].value, 60_000);
+        assert_eq!(tx.output[1].value, 80_000 - 60_000 - 356 - 62);
+        assert_eq!(
+            tx.output[1].script_pubkey,
+            account_address(network, &next_change)
+                .unwrap()
+                .script_pubkey()
+        );
+
+        let origin = |account: &Account| {
+            let public_key = PublicKey::from_str(&account.public_key).unwrap().inner;
+            let path = account.derivation_path.as_ref().unwrap().to_string();
+            (public_key, (Fingerprint::default(), path.parse().unwrap()))
+        };
+        for (input, owner) in psbt.inputs.iter().zip([&receive, &change]) {
+            assert_eq!(input.bip32_derivation, BTreeMap::from([origin(owner)]));
+            assert!(input.witness_utxo.is_some());
+            assert_eq!(input.non_witness_utxo.as_ref(), Some(&funding));
+        }
+        assert_eq!(psbt.outputs[0].bip32_derivation, BTreeMap::new());
+        assert_eq!(
+            psbt.outputs[1].bip32_derivation,
+            BTreeMap::from([origin(&next_change)])
+        );
+
+        // Remainder below the dust limit goes to the fee.
+        let unsigned_without_change = build("0.000796").await.unwrap();
+        let psbt_without_change = Psbt::deserialize(&unsigned_without_change.payload).unwrap();
+        assert_eq!(psbt_without_change.unsigned_tx.output.len(), 1);
+
+        assert!(finalize_transaction(&unsigned.payload).is_none());
+
+        let signed = SoftwareSigner::new()
+            .sign_message(
+                &unsigned.payload,
+                network,
+                account.derivation_path.as_ref().unwrap(),
+            )
+            .unwrap();
+        let signed_psbt = Psbt::deserialize(&signed).unwrap();
+        let finalized = finalize_transaction(&signed).unwrap();
+        assert_eq!(finalized.txid(), tx.txid());
+
+        for ((input, signed_input), owner) in finalized
+            .input
+            .iter()
+            .zip(&signed_psbt.inputs)
+            .zip([&receive, &change])
+        {
+            let public_key = PublicKey::from_str(&owner.public_key).unwrap();
+            let signature = signed_input.partial_sigs[&public_key];
+
+            assert!(input.script_sig.is_empty());
+            assert_eq!(
+                input.witness.to_vec(),
+                [signature.to_vec(), public_key.to_bytes()]
+            );
+        }
+    }
+}
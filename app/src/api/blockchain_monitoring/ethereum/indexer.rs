@@ -0,0 +1,428 @@
+//! History of ethereum accounts fetched from Etherscan compatible API, see
+//! [specification](https://docs.etherscan.io/api-endpoints/accounts). Blockscout serves
+//! the same API, so self-hosted instances can be used as well.
+
+use std::{collections::HashMap, str::FromStr};
+
+use alloy::primitives::{Address, TxHash, U256};
+use chrono::DateTime;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use super::{
+    super::{Account, IndexerConfig, TransactionInfo, TransactionType, TransactionUid},
+    to_decimal,
+};
+
+/// Number of entries requested per page.
+const PAGE_SIZE: usize = 100;
+/// Etherscan doesn't return entries past the first 10000 ones of the list.
+const MAX_PAGES: usize = 10_000 / PAGE_SIZE;
+
+const ACTIONS: [Action; 3] = [Action::Transactions, Action::Internal, Action::Tokens];
+
+#[derive(Clone, Copy)]
+enum Action {
+    /// Transactions sent or received by the account.
+    Transactions,
+    /// Transfers of coins made by contracts during execution of transactions.
+    Internal,
+    /// Transfers of ERC-20 tokens.
+    Tokens,
+}
+
+impl Action {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Transactions => "txlist",
+            Self::Internal => "txlistinternal",
+            Self::Tokens => "tokentx",
+        }
+    }
+}
+
+pub struct Indexer {
+    client: reqwest::Client,
+    config: IndexerConfig,
+    chain_id: u64,
+    decimals: u8,
+    /// Transactions found by `get_transactions`.
+    transactions: Mutex<HashMap<TransactionUid, TransactionInfo>>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    status: String,
+    message: String,
+    /// List of entries on success, error description otherwise.
+    result: Value,
+}
+
+/// Entry of any of the lists, all the values are strings.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    block_number: String,
+    time_stamp: String,
+    hash: String,
+    from: String,
+    /// Empty for transactions deploying contracts.
+    to: String,
+    value: String,
+    #[serde(default)]
+    gas_used: Option<String>,
+    #[serde(default)]
+    gas_price: Option<String>,
+    /// `"1"` for reverted transactions, missing for token transfers.
+    #[serde(default)]
+    is_error: Option<String>,
+    #[serde(default)]
+    token_symbol: Option<String>,
+    #[serde(default)]
+    token_decimal: Option<String>,
+}
+
+/// Transaction found by the indexer.
+pub struct IndexedTransaction {
+    pub hash: TxHash,
+    pub uid: TransactionUid,
+}
+
+impl Indexer {
+    pub fn new(config: IndexerConfig, chain_id: u64, decimals: u8) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+            chain_id,
+            decimals,
+            transactions: Default::default(),
+        }
+    }
+
+    /// Transactions and transfers of the account, latest first. Transaction making several
+    /// transfers is listed once per transfer, `None` if any of the lists can't be fetched.
+    pub async fn get_transactions(&self, account: Address) -> Option<Vec<IndexedTransaction>> {
+        let mut transfers = vec![];
+        for action in ACTIONS {
+            for entry in self.fetch(action, account).await? {
+                match self.describe(action, &entry, account) {
+                    Some((hash, block_number, info)) => transfers.push((block_number, hash, info)),
+                    None => log::debug!("Skipping {} entry of {}", action.name(), entry.hash),
+                }
+            }
+        }
+
+        // Sort is stable, so transfers of the same transaction keep the order of lists.
+        transfers.sort_by_key(|(block_number, _, _)| std::cmp::Reverse(*block_number));
+
+        let mut transfer_counts: HashMap<TxHash, usize> = HashMap::new();
+        let mut transactions = self.transactions.lock().await;
+        let indexed = transfers
+            .into_iter()
+            .map(|(_, hash, info)| {
+                let count = transfer_counts.entry(hash).or_default();
+                let uid = match *count {
+                    0 => hash.to_string(),
+                    n => format!("{}:{}", hash, n),
+                };
+                *count += 1;
+
+                let uid = TransactionUid { uid };
+                transactions.insert(uid.clone(), info);
+
+                IndexedTransaction { hash, uid }
+            })
+            .collect();
+
+        Some(indexed)
+    }
+
+    pub async fn get_transaction_info(&self, tx_uid: &TransactionUid) -> Option<TransactionInfo> {
+        self.transactions.lock().await.get(tx_uid).cloned()
+    }
+
+    /// Fetches all the pages of the list, latest entries first.
+    async fn fetch(&self, action: Action, account: Address) -> Option<Vec<Entry>> {
+        let mut entries = vec![];
+
+        for page in 1..=MAX_PAGES {
+            let page_entries = self.fetch_page(action, account, page).await?;
+            let page_size = page_entries.len();
+            entries.extend(page_entries);
+
+            if page_size < PAGE_SIZE {
+                break;
+            }
+        }
+
+        Some(entries)
+    }
+
+    async fn fetch_page(
+        &self,
+        action: Action,
+        account: Address,
+        page: usize,
+    ) -> Option<Vec<Entry>> {
+        let mut query = vec![
+            ("chainid", self.chain_id.to_string()),
+            ("module", "account".to_string()),
+            ("action", action.name().to_string()),
+            ("address", account.to_string()),
+            ("page", page.to_string()),
+            ("offset", PAGE_SIZE.to_string()),
+            ("sort", "desc".to_string()),
+        ];
+        if let Some(api_key) = &self.config.api_key {
+            query.push(("apikey", api_key.clone()));
+        }
+
+        let response = self
+            .client
+            .get(&self.config.url)
+            .query(&query)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let response: Response = match response {
+            Ok(response) => response
+                .json()
+                .await
+                .inspect_err(|e| log::error!("Invalid response of {}: {}", action.name(), e))
+                .ok()?,
+            Err(e) => {
+                log::error!("Failed to fetch {}: {}", action.name(), e);
+                return None;
+            }
+        };
+
+        // Empty list is reported as an error with empty result.
+        if response.status != "1" && response.result.as_array().is_none_or(|r| !r.is_empty()) {
+            log::error!(
+                "Failed to fetch {}: {} {}",
+                action.name(),
+                response.message,
+                response.result
+            );
+            return None;
+        }
+
+        serde_json::from_value(response.result)
+            .inspect_err(|e| log::error!("Invalid entries of {}: {}", action.name(), e))
+            .ok()
+    }
+
+    /// Describes the entry relative to the account, `None` if it's malformed or reverted
+    /// without any effect on the account.
+    fn describe(
+        &self,
+        action: Action,
+        entry: &Entry,
+        account: Address,
+    ) -> Option<(TxHash, u64, TransactionInfo)> {
+        let hash = TxHash::from_str(&entry.hash).ok()?;
+        let block_number = entry.block_number.parse().ok()?;
+        let timestamp = DateTime::from_timestamp(entry.time_stamp.parse().ok()?, 0)?;
+        let from = Address::from_str(&entry.from).ok()?;
+        let to = Address::from_str(&entry.to).ok();
+        let reverted = entry.is_error.as_deref() == Some("1");
+
+        let mut value = parse_units(&entry.value)?;
+        let (decimals, token) = match action {
+            Action::Tokens => (
+                entry.token_decimal.as_ref()?.parse().ok()?,
+                Some(entry.token_symbol.clone()?),
+            ),
+            Action::Transactions | Action::Internal => (self.decimals, None),
+        };
+
+        let ty = if from == account {
+            if reverted {
+                match action {
+                    // Reverted transaction doesn't transfer coins, but the fee is paid anyway.
+                    Action::Transactions => value = U256::ZERO,
+                    Action::Internal | Action::Tokens => return None,
+                }
+            }
+            // Fee is paid in coins by the transaction and not by the transfers it makes.
+            if let Action::Transactions = action {
+                let gas_used = parse_units(entry.gas_used.as_ref()?)?;
+                let gas_price = parse_units(entry.gas_price.as_ref()?)?;
+                value += gas_used * gas_price;
+            }
+
+            TransactionType::Withdraw {
+                to: Account::external(to.unwrap_or_default().to_string()),
+                amount: to_decimal(value, decimals)?,
+            }
+        } else {
+            if reverted {
+                return None;
+            }
+
+            TransactionType::Deposit {
+                from: Account::external(from.to_string()),
+                amount: to_decimal(value, decimals)?,
+            }
+        };
+
+        Some((
+            hash,
+            block_number,
+            TransactionInfo {
+                ty,
+                timestamp,
+                token,
+            },
+        ))
+    }
+}
+
+fn parse_units(value: &str) -> Option<U256> {
+    U256::from_str_radix(value, 10).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::stand_in;
+
+    const ACCOUNT: Address = Address::repeat_byte(0x11);
+    const OTHER: Address = Address::repeat_byte(0x22);
+    const CONTRACT: Address = Address::repeat_byte(0x33);
+
+    fn hash(idx: u32) -> String {
+        format!("0x{:064x}", idx)
+    }
+
+    fn entry(idx: u32, from: Address, to: Address, value: &str) -> Value {
+        json!({
+            "blockNumber": idx.to_string(),
+            "timeStamp": (1_700_000_000 + idx).to_string(),
+            "hash": hash(idx),
+            "from": from.to_string().to_lowercase(),
+            "to": to.to_string().to_lowercase(),
+            "value": value,
+            "gasUsed": "21000",
+            "gasPrice": "1000000000",
+            "isError": "0",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_indexer_history() {
+        // 150 deposits in blocks 1..=150 span two pages of the transaction list.
+        let mut txlist: Vec<Value> = (1..=150)
+            .rev()
+            .map(|idx| entry(idx, OTHER, ACCOUNT, "1000000000000000000"))
+            .collect();
+        // Reverted withdrawal in block 200 costs only the fee.
+        let mut reverted = entry(200, ACCOUNT, OTHER, "5000000000000000000");
+        reverted["isError"] = json!("1");
+        txlist.insert(0, reverted);
+        // Contract call in block 300 pays out coins and transfers tokens to the account.
+        txlist.insert(0, entry(300, ACCOUNT, CONTRACT, "0"));
+        let internal = vec![entry(300, CONTRACT, ACCOUNT, "250000000000000000")];
+        let mut token_transfer = entry(300, CONTRACT, ACCOUNT, "2500000");
+        let token_transfer = token_transfer.as_object_mut().unwrap();
+        token_transfer.remove("isError");
+        token_transfer.insert("tokenSymbol".to_string(), json!("USDC"));
+        token_transfer.insert("tokenDecimal".to_string(), json!("6"));
+        let tokens = vec![Value::Object(token_transfer.clone())];
+
+        let url = stand_in::serve(move |path, _| {
+            let url = reqwest::Url::parse(&format!("http://stand-in{}", path)).unwrap();
+            let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+            assert_eq!(query["chainid"], "1");
+            assert_eq!(query["apikey"], "KEY");
+            assert_eq!(query["address"], ACCOUNT.to_string());
+
+            let list = match query["action"].as_str() {
+                "txlist" => &txlist,
+                "txlistinternal" => &internal,
+                "tokentx" => &tokens,
+                action => panic!("Unexpected action {}", action),
+            };
+            let page: usize = query["page"].parse().unwrap();
+            let offset: usize = query["offset"].parse().unwrap();
+            let result: Vec<_> = list.iter().skip((page - 1) * offset).take(offset).collect();
+
+            Some(if result.is_empty() {
+                json!({"status": "0", "message": "No transactions found", "result": []})
+            } else {
+                json!({"status": "1", "message": "OK", "result": result})
+            })
+        })
+        .await;
+
+        let indexer = Indexer::new(
+            IndexerConfig {
+                url: format!("{}/api", url),
+                api_key: Some("KEY".to_string()),
+            },
+            1,
+            18,
+        );
+
+        let txs = indexer.get_transactions(ACCOUNT).await.unwrap();
+        let uids: Vec<_> = txs.iter().map(|tx| tx.uid.uid.clone()).collect();
+        assert_eq!(txs.len(), 154);
+        assert_eq!(
+            uids[..5],
+            [
+                hash(300),
+                format!("{}:1", hash(300)),
+                format!("{}:2", hash(300)),
+                hash(200),
+                hash(150)
+            ]
+        );
+        assert_eq!(uids[153], hash(1));
+
+        let info = |idx: usize| {
+            let indexer = &indexer;
+            let uid = txs[idx].uid.clone();
+            async move { indexer.get_transaction_info(&uid).await.unwrap() }
+        };
+
+        let call = info(0).await;
+        let TransactionType::Withdraw { to, amount } = call.ty else {
+            panic!("Contract call should be a withdrawal");
+        };
+        assert_eq!(to.public_key, CONTRACT.to_string());
+        assert_eq!(amount, Decimal::new(21, 6));
+
+        let payout = info(1).await;
+        let TransactionType::Deposit { from, amount } = payout.ty else {
+            panic!("Internal transfer should be a deposit");
+        };
+        assert_eq!(from.public_key, CONTRACT.to_string());
+        assert_eq!(amount, Decimal::new(25, 2));
+        assert_eq!(payout.token, None);
+
+        let token_deposit = info(2).await;
+        let TransactionType::Deposit { amount, .. } = token_deposit.ty else {
+            panic!("Token transfer should be a deposit");
+        };
+        assert_eq!(amount, Decimal::new(25, 1));
+        assert_eq!(token_deposit.token.as_deref(), Some("USDC"));
+
+        let reverted = info(3).await;
+        let TransactionType::Withdraw { amount, .. } = reverted.ty else {
+            panic!("Reverted transaction should be a withdrawal");
+        };
+        assert_eq!(amount, Decimal::new(21, 6));
+
+        let deposit = info(153).await;
+        let TransactionType::Deposit { from, amount } = deposit.ty else {
+            panic!("Transaction should be a deposit");
+        };
+        assert_eq!(from.public_key, OTHER.to_string());
+        assert_eq!(amount, Decimal::ONE);
+        assert_eq!(deposit.timestamp.timestamp(), 1_700_000_001);
+    }
+}
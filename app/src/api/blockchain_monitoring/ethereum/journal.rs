@@ -0,0 +1,150 @@
+//! Journal of transactions sent through ledger-tui. RPC API can't list transactions of an
+//! account, so history of ethereum accounts is made of the transactions recorded here.
+
+use alloy::primitives::{Address, TxHash, U256};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::super::{Network, SharedStorage};
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub hash: TxHash,
+    pub from: Address,
+    pub nonce: u64,
+    /// Receiver of the transfer: account the coins are sent to or the receiver of the tokens.
+    pub to: Address,
+    /// Amount of coins attached to the transaction in base units.
+    pub value: U256,
+    /// Token transferred by the transaction, `None` for transfers of coins.
+    #[serde(default)]
+    pub token: Option<TokenTransfer>,
+    /// Fee paid in base units of the coin, known once transaction is included into a block.
+    #[serde(default)]
+    pub fee: Option<U256>,
+    pub status: TransactionStatus,
+    /// Time of the block transaction is included into or time it was sent at if it's pending.
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct TokenTransfer {
+    pub contract: Address,
+    /// Amount of tokens in base units.
+    pub amount: U256,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// Broadcast, but not included into a block yet.
+    Pending,
+    Succeeded,
+    /// Included into a block, but reverted. Fee is paid anyway.
+    Failed,
+    /// Never included into a block, its nonce is used by another transaction.
+    Dropped,
+}
+
+pub struct Journal {
+    storage: SharedStorage,
+    /// Name of the journal in the storage, every network has its own one.
+    name: String,
+    /// Entries in the order transactions were sent, loaded on the first access.
+    entries: Option<Vec<JournalEntry>>,
+}
+
+impl Journal {
+    pub fn new(network: Network, storage: SharedStorage) -> Self {
+        Self {
+            storage,
+            name: format!("{}_transactions.json", network.id()),
+            entries: None,
+        }
+    }
+
+    pub async fn entries(&mut self) -> &mut Vec<JournalEntry> {
+        if self.entries.is_none() {
+            let stored = self.storage.lock().await.load(&self.name).await;
+            let entries = match stored {
+                Some(stored) => serde_json::from_str(&stored).unwrap_or_else(|e| {
+                    log::error!("Failed to parse transaction journal {}: {}", self.name, e);
+                    vec![]
+                }),
+                None => vec![],
+            };
+
+            self.entries = Some(entries);
+        }
+
+        self.entries.as_mut().expect("Entries are loaded above")
+    }
+
+    pub async fn record(&mut self, entry: JournalEntry) {
+        self.entries().await.push(entry);
+        self.save().await;
+    }
+
+    pub async fn get(&mut self, hash: &TxHash) -> Option<JournalEntry> {
+        self.entries()
+            .await
+            .iter()
+            .find(|entry| entry.hash == *hash)
+            .cloned()
+    }
+
+    pub async fn save(&mut self) {
+        let entries = serde_json::to_string(self.entries().await)
+            .expect("Journal entries should be serializable");
+
+        self.storage.lock().await.save(&self.name, entries).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use super::*;
+    use crate::api::storage::mock::StorageApiMock;
+
+    #[tokio::test]
+    async fn test_journal_persistence() {
+        let storage: SharedStorage = Arc::new(Mutex::new(Box::new(StorageApiMock::new())));
+        let entry = JournalEntry {
+            hash: TxHash::repeat_byte(0x11),
+            from: Address::repeat_byte(0x22),
+            nonce: 7,
+            to: Address::repeat_byte(0x33),
+            value: U256::ZERO,
+            token: Some(TokenTransfer {
+                contract: Address::repeat_byte(0x44),
+                amount: U256::from(1_000_000),
+            }),
+            fee: None,
+            status: TransactionStatus::Pending,
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        };
+
+        let mut journal = Journal::new(Network::ETHEREUM, storage.clone());
+        journal.record(entry.clone()).await;
+
+        // Journals of other networks are stored separately.
+        let mut polygon_journal = Journal::new("polygon".parse().unwrap(), storage.clone());
+        assert!(polygon_journal.entries().await.is_empty());
+
+        let mut journal = Journal::new(Network::ETHEREUM, storage.clone());
+        assert_eq!(journal.get(&entry.hash).await, Some(entry.clone()));
+
+        journal.entries().await[0].status = TransactionStatus::Succeeded;
+        journal.save().await;
+
+        let mut journal = Journal::new(Network::ETHEREUM, storage);
+        assert_eq!(
+            journal.entries().await[0].status,
+            TransactionStatus::Succeeded
+        );
+    }
+}
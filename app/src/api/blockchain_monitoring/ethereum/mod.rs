@@ -0,0 +1,898 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxEnvelope, TxType},
+    eips::{BlockNumberOrTag, eip2718::Encodable2718},
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, Signature, TxHash, TxKind, U256},
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rlp::Decodable,
+    rpc::types::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+    transports::http::{Client, Http},
+};
+use async_trait::async_trait;
+use bigdecimal::{
+    BigDecimal,
+    num_bigint::{BigInt, Sign},
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, prelude::Zero};
+use tokio::sync::Mutex;
+
+use super::{
+    Account, Network, NetworkApi, NetworkApiConfig, SharedStorage, SignedTransaction, Token,
+    TransactionInfo, TransactionType, TransactionUid, UnsignedTransaction,
+};
+use indexer::Indexer;
+use journal::{Journal, JournalEntry, TokenTransfer, TransactionStatus};
+use scanner::Scanner;
+
+mod indexer;
+mod journal;
+mod scanner;
+
+sol! {
+    interface IERC20 {
+        function balanceOf(address owner) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+    }
+}
+
+pub struct Api {
+    provider: RootProvider<Http<Client>>,
+    /// Decimals of the native coin of the network.
+    decimals: u8,
+    chain_id: u64,
+    journal: Mutex<Journal>,
+    /// Source of the complete account history, only the journal is used if it's missing.
+    indexer: Option<Indexer>,
+    /// Source of the history of coin transfers on networks without an indexer.
+    scanner: Option<Mutex<Scanner>>,
+    /// Tokens journaled transfers are described with, keyed by contract. Builtin tokens of
+    /// the network and the ones balances are requested or transfers prepared for.
+    tokens: Mutex<HashMap<Address, Token>>,
+}
+
+impl Api {
+    pub fn new(network: Network, config: NetworkApiConfig, storage: SharedStorage) -> Api {
+        let rpc_url = config
+            .endpoint
+            .parse()
+            .expect("Endpoint should be validated before the api is instantiated");
+        let provider = ProviderBuilder::new().on_http(rpc_url);
+
+        let decimals = network.get_info().decimals;
+        let chain_id = network
+            .chain_id()
+            .expect("Networks of ethereum kind should have chain id");
+
+        // Blocks aren't scanned if the indexer is configured, it provides the complete history.
+        let scanner = match (&config.indexer, config.scan_from_block) {
+            (None, Some(start_block)) => Some(Mutex::new(Scanner::new(
+                provider.clone(),
+                network,
+                storage.clone(),
+                start_block,
+                decimals,
+            ))),
+            _ => None,
+        };
+
+        Api {
+            provider,
+            decimals,
+            chain_id,
+            journal: Mutex::new(Journal::new(network, storage)),
+            indexer: config
+                .indexer
+                .map(|indexer| Indexer::new(indexer, chain_id, decimals)),
+            scanner,
+            tokens: Mutex::new(
+                Token::builtin()
+                    .into_iter()
+                    .filter(|token| token.network == network)
+                    .filter_map(|token| Some((Address::from_str(&token.contract).ok()?, token)))
+                    .collect(),
+            ),
+        }
+    }
+
+    async fn remember_token(&self, contract: Address, token: &Token) {
+        self.tokens
+            .lock()
+            .await
+            .entry(contract)
+            .or_insert_with(|| token.clone());
+    }
+
+    /// Builds EIP-1559 transaction calling `to` with `value` attached, gas and fees
+    /// are estimated by the node.
+    async fn prepare_call(
+        &self,
+        from: &Account,
+        to: Address,
+        value: U256,
+        input: Bytes,
+    ) -> Option<UnsignedTransaction> {
+        let Ok(from) = Address::from_str(&from.public_key) else {
+            log::error!("Invalid sender address: {}", from.public_key);
+            return None;
+        };
+
+        let request = TransactionRequest::default()
+            .with_from(from)
+            .with_to(to)
+            .with_value(value)
+            .with_input(input.clone());
+
+        let (nonce, node_chain_id, fees, gas_limit) = match tokio::try_join!(
+            self.provider.get_transaction_count(from).into_future(),
+            self.provider.get_chain_id().into_future(),
+            self.provider.estimate_eip1559_fees(None),
+            self.provider.estimate_gas(&request).into_future(),
+        ) {
+            Ok(tx_parameters) => tx_parameters,
+            Err(error) => {
+                log::error!("Failed to fetch transaction parameters: {}", error);
+                return None;
+            }
+        };
+
+        // Nonce and fees fetched from the node of another chain are meaningless here.
+        if node_chain_id != self.chain_id {
+            log::error!(
+                "Node is connected to chain {}, expected chain {}",
+                node_chain_id,
+                self.chain_id
+            );
+            return None;
+        }
+
+        let tx = TxEip1559 {
+            chain_id: self.chain_id,
+            nonce,
+            gas_limit,
+            max_fee_per_gas: fees.max_fee_per_gas,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+            to: TxKind::Call(to),
+            value,
+            access_list: Default::default(),
+            input,
+        };
+
+        let mut payload = vec![];
+        tx.encode_for_signing(&mut payload);
+
+        Some(UnsignedTransaction { payload })
+    }
+}
+
+impl Api {
+    /// Updates pending transactions sent from `from` according to their receipts. Transaction
+    /// without receipt is dropped if its nonce is already used by another transaction.
+    async fn reconcile(&self, journal: &mut Journal, from: Address) {
+        let pending =
+            |entry: &JournalEntry| entry.from == from && entry.status == TransactionStatus::Pending;
+        if !journal.entries().await.iter().any(pending) {
+            return;
+        }
+
+        // Nonce is fetched before receipts, so transaction mined in between isn't dropped.
+        let confirmed_nonce = match self.provider.get_transaction_count(from).await {
+            Ok(nonce) => nonce,
+            Err(error) => {
+                log::error!("Failed to fetch account nonce: {}", error);
+                return;
+            }
+        };
+
+        let mut updated = false;
+        for entry in journal
+            .entries()
+            .await
+            .iter_mut()
+            .filter(|entry| pending(entry))
+        {
+            let receipt = match self.provider.get_transaction_receipt(entry.hash).await {
+                Ok(receipt) => receipt,
+                Err(error) => {
+                    log::error!("Failed to fetch receipt of {}: {}", entry.hash, error);
+                    continue;
+                }
+            };
+
+            let Some(receipt) = receipt else {
+                if confirmed_nonce > entry.nonce {
+                    entry.status = TransactionStatus::Dropped;
+                    updated = true;
+                }
+                continue;
+            };
+
+            entry.status = if receipt.status() {
+                TransactionStatus::Succeeded
+            } else {
+                TransactionStatus::Failed
+            };
+            entry.fee =
+                Some(U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price));
+
+            if let Some(block_number) = receipt.block_number {
+                match self
+                    .provider
+                    .get_block_by_number(BlockNumberOrTag::Number(block_number), false)
+                    .await
+                {
+                    Ok(Some(block)) => {
+                        if let Some(timestamp) =
+                            DateTime::from_timestamp(block.header.timestamp as i64, 0)
+                        {
+                            entry.timestamp = timestamp;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(error) => {
+                        log::error!("Failed to fetch block {}: {}", block_number, error)
+                    }
+                }
+            }
+
+            updated = true;
+        }
+
+        if updated {
+            journal.save().await;
+        }
+    }
+}
+
+#[async_trait]
+impl NetworkApi for Api {
+    async fn get_balance(&self, account: &Account) -> BigDecimal {
+        let Ok(account) = Address::from_str(&account.public_key) else {
+            log::error!("Invalid account address: {}", account.public_key);
+            return BigDecimal::zero();
+        };
+
+        match self.provider.get_balance(account).await {
+            Ok(balance) => from_base_units(balance, self.decimals),
+            Err(error) => {
+                log::error!("Failed to fetch balance of {}: {}", account, error);
+                BigDecimal::zero()
+            }
+        }
+    }
+
+    async fn get_token_balance(&self, account: &Account, token: &Token) -> BigDecimal {
+        let Ok(account) = Address::from_str(&account.public_key) else {
+            log::error!("Invalid account address: {}", account.public_key);
+            return BigDecimal::zero();
+        };
+        let Ok(contract) = Address::from_str(&token.contract) else {
+            log::error!(
+                "Invalid {} contract address: {}",
+                token.symbol,
+                token.contract
+            );
+            return BigDecimal::zero();
+        };
+        self.remember_token(contract, token).await;
+
+        let input = IERC20::balanceOfCall { owner: account }.abi_encode();
+        let request = TransactionRequest::default()
+            .with_to(contract)
+            .with_input(input);
+
+        let balance = match self.provider.call(&request).await {
+            Ok(response) => IERC20::balanceOfCall::abi_decode_returns(&response, true),
+            Err(error) => {
+                log::error!("Failed to request {} balance: {}", token.symbol, error);
+                return BigDecimal::zero();
+            }
+        };
+
+        match balance {
+            Ok(balance) => from_base_units(balance._0, token.decimals),
+            Err(error) => {
+                log::error!("Failed to decode {} balance: {}", token.symbol, error);
+                BigDecimal::zero()
+            }
+        }
+    }
+
+    /// It's impossible to request transactions of an account using only RPC API, so they're
+    /// fetched from the indexer or found by scanning blocks if either is configured.
+    /// Transactions sent through ledger-tui and not indexed yet go first, latest first.
+    async fn get_transactions(&self, account: &Account) -> Vec<TransactionUid> {
+        let Ok(from) = Address::from_str(&account.public_key) else {
+            log::error!("Invalid account address: {}", account.public_key);
+            return vec![];
+        };
+
+        let indexed = match (&self.indexer, &self.scanner) {
+            (Some(indexer), _) => indexer.get_transactions(from).await.unwrap_or_default(),
+            (None, Some(scanner)) => scanner.lock().await.get_transactions(from).await,
+            (None, None) => vec![],
+        };
+        let indexed_hashes: HashSet<_> = indexed.iter().map(|tx| tx.hash).collect();
+
+        let mut journal = self.journal.lock().await;
+        self.reconcile(&mut journal, from).await;
+
+        journal
+            .entries()
+            .await
+            .iter()
+            .rev()
+            .filter(|entry| entry.from == from && entry.status != TransactionStatus::Dropped)
+            .filter(|entry| !indexed_hashes.contains(&entry.hash))
+            .map(|entry| TransactionUid {
+                uid: entry.hash.to_string(),
+            })
+            .chain(indexed.into_iter().map(|tx| tx.uid))
+            .collect()
+    }
+
+    async fn get_transaction_info(&self, tx_uid: &TransactionUid) -> Option<TransactionInfo> {
+        if let Some(indexer) = &self.indexer {
+            if let Some(info) = indexer.get_transaction_info(tx_uid).await {
+                return Some(info);
+            }
+        }
+
+        if let Some(scanner) = &self.scanner {
+            if let Some(info) = scanner.lock().await.get_transaction_info(tx_uid) {
+                return Some(info);
+            }
+        }
+
+        let hash = TxHash::from_str(&tx_uid.uid).ok()?;
+        let entry = self.journal.lock().await.get(&hash).await?;
+
+        let to = Account::external(entry.to.to_string());
+
+        // Fee of token transfers is paid in coins, so it's not included into the amount.
+        if let Some(transfer) = &entry.token {
+            let Some(token) = self.tokens.lock().await.get(&transfer.contract).cloned() else {
+                log::error!(
+                    "Transaction {} transfers unknown token {}",
+                    tx_uid.uid,
+                    transfer.contract
+                );
+                return None;
+            };
+
+            let amount = match entry.status {
+                TransactionStatus::Failed => U256::ZERO,
+                _ => transfer.amount,
+            };
+
+            return Some(TransactionInfo {
+                ty: TransactionType::Withdraw {
+                    to,
+                    amount: to_decimal(amount, token.decimals)?,
+                },
+                timestamp: entry.timestamp,
+                token: Some(token.symbol),
+            });
+        }
+
+        // Coins aren't transferred by reverted transactions, but the fee is paid.
+        let value = match entry.status {
+            TransactionStatus::Failed => U256::ZERO,
+            _ => entry.value,
+        };
+        let amount = value + entry.fee.unwrap_or_default();
+
+        Some(TransactionInfo {
+            ty: TransactionType::Withdraw {
+                to,
+                amount: to_decimal(amount, self.decimals)?,
+            },
+            timestamp: entry.timestamp,
+            token: None,
+        })
+    }
+
+    async fn is_account_used(&self, account: &Account) -> bool {
+        let Ok(address) = Address::from_str(&account.public_key) else {
+            log::error!("Invalid account address: {}", account.public_key);
+            return false;
+        };
+
+        // Account that has only received funds has zero nonce, so balance is checked as well.
+        let nonce = match self.provider.get_transaction_count(address).await {
+            Ok(nonce) => nonce,
+            Err(error) => {
+                log::error!("Failed to fetch nonce of {}: {}", address, error);
+                return false;
+            }
+        };
+        if nonce > 0 {
+            return true;
+        }
+
+        match self.provider.get_balance(address).await {
+            Ok(balance) => !balance.is_zero(),
+            Err(error) => {
+                log::error!("Failed to fetch balance of {}: {}", address, error);
+                false
+            }
+        }
+    }
+
+    async fn prepare_transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let Ok(to) = Address::from_str(&to.public_key) else {
+            log::error!("Invalid receiver address: {}", to.public_key);
+            return None;
+        };
+        let value = to_base_units(amount, self.decimals)?;
+
+        self.prepare_call(from, to, value, Bytes::new()).await
+    }
+
+    async fn prepare_token_transfer(
+        &self,
+        token: &Token,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let Ok(contract) = Address::from_str(&token.contract) else {
+            log::error!(
+                "Invalid {} contract address: {}",
+                token.symbol,
+                token.contract
+            );
+            return None;
+        };
+        let Ok(to) = Address::from_str(&to.public_key) else {
+            log::error!("Invalid receiver address: {}", to.public_key);
+            return None;
+        };
+        self.remember_token(contract, token).await;
+        let amount = to_base_units(amount, token.decimals)?;
+
+        let input = IERC20::transferCall { to, amount }.abi_encode();
+
+        self.prepare_call(from, contract, U256::ZERO, input.into())
+            .await
+    }
+
+    async fn send_transaction(&self, tx: &SignedTransaction) -> Option<TransactionUid> {
+        let payload = &tx.unsigned.payload;
+        if payload.first() != Some(&(TxType::Eip1559 as u8)) {
+            log::error!("Expected EIP-1559 transaction payload");
+            return None;
+        }
+
+        let unsigned_tx = match TxEip1559::decode(&mut &payload[1..]) {
+            Ok(unsigned_tx) => unsigned_tx,
+            Err(error) => {
+                log::error!("Failed to decode transaction payload: {}", error);
+                return None;
+            }
+        };
+
+        let signature = decode_signature(&tx.signature)?;
+        let signed_tx = unsigned_tx.into_signed(signature);
+        let from = match signed_tx.recover_signer() {
+            Ok(from) => from,
+            Err(error) => {
+                log::error!("Failed to recover transaction signer: {}", error);
+                return None;
+            }
+        };
+
+        let mut entry = journal_entry(signed_tx.tx(), from);
+        let signed_tx = TxEnvelope::from(signed_tx);
+
+        let hash = match self
+            .provider
+            .send_raw_transaction(&signed_tx.encoded_2718())
+            .await
+        {
+            Ok(pending_tx) => *pending_tx.tx_hash(),
+            Err(error) => {
+                log::error!("Failed to broadcast transaction: {}", error);
+                return None;
+            }
+        };
+
+        entry.hash = hash;
+        self.journal.lock().await.record(entry).await;
+
+        Some(TransactionUid {
+            uid: hash.to_string(),
+        })
+    }
+}
+
+/// Pending entry of the transaction, its hash is filled once it's broadcast. Calls of
+/// `transfer` method of token contracts are recorded as token transfers.
+fn journal_entry(tx: &TxEip1559, from: Address) -> JournalEntry {
+    let contract = tx.to.to().copied().unwrap_or_default();
+    let token_transfer = IERC20::transferCall::abi_decode(&tx.input, true).ok();
+
+    let (to, token) = match token_transfer {
+        Some(transfer) => (
+            transfer.to,
+            Some(TokenTransfer {
+                contract,
+                amount: transfer.amount,
+            }),
+        ),
+        None => (contract, None),
+    };
+
+    JournalEntry {
+        hash: TxHash::ZERO,
+        from,
+        nonce: tx.nonce,
+        to,
+        value: tx.value,
+        token,
+        fee: None,
+        status: TransactionStatus::Pending,
+        timestamp: Utc::now(),
+    }
+}
+
+/// Converts amount in base units(e.g. wei) to the amount of coins, `None` if it doesn't
+/// fit into `Decimal`.
+fn to_decimal(amount: U256, decimals: u8) -> Option<Decimal> {
+    let amount = i128::try_from(amount).ok()?;
+    Decimal::try_from_i128_with_scale(amount, decimals as u32).ok()
+}
+
+/// Converts amount in base units(e.g. wei) to the amount of coins or tokens.
+fn from_base_units(amount: U256, decimals: u8) -> BigDecimal {
+    let amount = BigInt::from_bytes_be(Sign::Plus, &amount.to_be_bytes::<32>());
+
+    BigDecimal::new(amount, decimals as i64)
+}
+
+/// Converts amount of coins or tokens to the base units(e.g. wei).
+pub(super) fn to_base_units(amount: BigDecimal, decimals: u8) -> Option<U256> {
+    let amount = amount * BigDecimal::new(1.into(), -(decimals as i64));
+    if !amount.is_integer() {
+        log::error!("Amount is more precise than 1 base unit");
+        return None;
+    }
+
+    let amount = amount.with_scale(0).to_string();
+    match U256::from_str_radix(&amount, 10) {
+        Ok(amount) => Some(amount),
+        Err(_) => {
+            log::error!("Amount is out of range: {}", amount);
+            None
+        }
+    }
+}
+
+/// Decodes `v || r || s` signature returned by the ethereum ledger app.
+fn decode_signature(signature: &[u8]) -> Option<Signature> {
+    if signature.len() != 65 {
+        log::error!("Invalid signature length: {}", signature.len());
+        return None;
+    }
+
+    // Typed transactions are signed with a bare y-parity, but some versions of the app
+    // still add legacy offset of 27 to it.
+    let y_parity = match signature[0] {
+        0 | 27 => false,
+        1 | 28 => true,
+        v => {
+            log::error!("Unexpected signature v value: {}", v);
+            return None;
+        }
+    };
+
+    let r = U256::from_be_slice(&signature[1..33]);
+    let s = U256::from_be_slice(&signature[33..65]);
+
+    Signature::from_rs_and_parity(r, s, y_parity).ok()
+}
+
+#[ignore = "requires local anvil node"]
+#[tokio::test]
+async fn test_ethereum_send_transfer() {
+    use std::sync::Arc;
+
+    use alloy::{
+        primitives::keccak256,
+        signers::{SignerSync, local::PrivateKeySigner},
+    };
+
+    use crate::api::storage::mock::StorageApiMock;
+
+    // First of the default anvil accounts.
+    let signer = PrivateKeySigner::from_str(
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    )
+    .unwrap();
+
+    let api = Api::new(
+        "anvil".parse().unwrap(),
+        NetworkApiConfig {
+            endpoint: "http://127.0.0.1:8545".to_string(),
+            backend: None,
+            address_gap_limit: 20,
+            indexer: None,
+            scan_from_block: None,
+            wallet_birthday: None,
+        },
+        Arc::new(Mutex::new(Box::new(StorageApiMock::new()))),
+    );
+
+    let from = Account::external(signer.address().to_string());
+    let to = Account::external("0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string());
+    let amount = BigDecimal::from_str("0.5").unwrap();
+
+    let unsigned = api.prepare_transfer(&from, &to, amount).await.unwrap();
+
+    let signature = signer
+        .sign_hash_sync(&keccak256(&unsigned.payload))
+        .unwrap();
+    let signature = [
+        &[signature.v().y_parity_byte()][..],
+        &signature.r().to_be_bytes::<32>()[..],
+        &signature.s().to_be_bytes::<32>()[..],
+    ]
+    .concat();
+
+    let tx_uid = api
+        .send_transaction(&SignedTransaction {
+            unsigned,
+            signature,
+        })
+        .await
+        .unwrap();
+
+    // Anvil mines transactions instantly, so receipt is available right away.
+    assert_eq!(api.get_transactions(&from).await, [tx_uid.clone()]);
+    let tx_info = api.get_transaction_info(&tx_uid).await.unwrap();
+    let TransactionType::Withdraw {
+        to: receiver,
+        amount,
+    } = tx_info.ty
+    else {
+        panic!("Sent transaction should be a withdrawal");
+    };
+    assert_eq!(receiver.public_key, to.public_key);
+    assert!(amount > Decimal::from_str("0.5").unwrap());
+}
+
+#[tokio::test]
+async fn test_account_usage() {
+    use std::sync::Arc;
+
+    use serde_json::{Value, json};
+
+    use crate::api::{stand_in, storage::mock::StorageApiMock};
+
+    let funded = Address::repeat_byte(0x11);
+    let endpoint = stand_in::serve(move |path, body| {
+        // Requests to other paths stand for node failure and are answered with 404.
+        if path != "/" {
+            return None;
+        }
+
+        let request: Value = serde_json::from_str(body).ok()?;
+        let address: Address = request["params"][0].as_str()?.parse().ok()?;
+        let result = match request["method"].as_str()? {
+            "eth_getTransactionCount" => "0x0",
+            "eth_getBalance" if address == funded => "0x5",
+            "eth_getBalance" => "0x0",
+            _ => return None,
+        };
+        Some(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    })
+    .await;
+
+    let api = |endpoint: &str| {
+        Api::new(
+            "anvil".parse().unwrap(),
+            NetworkApiConfig {
+                endpoint: endpoint.to_string(),
+                backend: None,
+                address_gap_limit: 20,
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+            Arc::new(Mutex::new(Box::new(StorageApiMock::new()))),
+        )
+    };
+
+    let api_ok = api(&endpoint);
+    assert!(
+        api_ok
+            .is_account_used(&Account::external(funded.to_string()))
+            .await
+    );
+    assert!(
+        !api_ok
+            .is_account_used(&Account::external(Address::repeat_byte(0x22).to_string()))
+            .await
+    );
+    assert!(
+        !api_ok
+            .is_account_used(&Account::external("not an address".to_string()))
+            .await
+    );
+
+    let api_failing = api(&format!("{}/missing", endpoint));
+    assert!(
+        !api_failing
+            .is_account_used(&Account::external(funded.to_string()))
+            .await
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_addresses_have_zero_balance() {
+    use std::sync::Arc;
+
+    use crate::api::storage::mock::StorageApiMock;
+
+    let network: Network = "anvil".parse().unwrap();
+    let api = Api::new(
+        network,
+        NetworkApiConfig {
+            // Nothing listens there, invalid addresses are rejected before any request.
+            endpoint: "http://127.0.0.1:1".to_string(),
+            backend: None,
+            address_gap_limit: 20,
+            indexer: None,
+            scan_from_block: None,
+            wallet_birthday: None,
+        },
+        Arc::new(Mutex::new(Box::new(StorageApiMock::new()))),
+    );
+    let account = Account::external(Address::repeat_byte(0x11).to_string());
+    let token = Token {
+        network,
+        symbol: "BAD".to_string(),
+        contract: "0x1234".to_string(),
+        decimals: 18,
+        ledger_signature: None,
+    };
+
+    assert!(
+        api.get_balance(&Account::external("not an address".to_string()))
+            .await
+            .is_zero()
+    );
+    assert!(api.get_balance(&account).await.is_zero());
+    assert!(api.get_token_balance(&account, &token).await.is_zero());
+}
+
+#[test]
+fn test_journal_entry() {
+    let to = Address::repeat_byte(0x33);
+    let contract = Address::repeat_byte(0x44);
+    let from = Address::repeat_byte(0x22);
+    let tx = |to: Address, value: U256, input: Vec<u8>| TxEip1559 {
+        chain_id: 1,
+        nonce: 5,
+        to: TxKind::Call(to),
+        value,
+        input: input.into(),
+        ..Default::default()
+    };
+
+    let entry = journal_entry(&tx(to, U256::from(1_000), vec![]), from);
+    assert_eq!((entry.from, entry.to, entry.nonce), (from, to, 5));
+    assert_eq!(entry.value, U256::from(1_000));
+    assert_eq!(entry.token, None);
+    assert_eq!(entry.status, TransactionStatus::Pending);
+
+    let input = IERC20::transferCall {
+        to,
+        amount: U256::from(2_000),
+    }
+    .abi_encode();
+    let entry = journal_entry(&tx(contract, U256::ZERO, input), from);
+    assert_eq!(entry.to, to);
+    assert_eq!(
+        entry.token,
+        Some(TokenTransfer {
+            contract,
+            amount: U256::from(2_000)
+        })
+    );
+
+    assert_eq!(
+        to_decimal(U256::from(1_500_000_000_000_000_000u64), 18),
+        Some(Decimal::new(15, 1))
+    );
+    assert_eq!(to_decimal(U256::MAX, 18), None);
+}
+
+#[tokio::test]
+async fn test_journaled_token_transfer_info() {
+    use std::sync::Arc;
+
+    use crate::api::storage::mock::StorageApiMock;
+
+    // Node isn't requested, journaled transactions are described locally.
+    let api = Api::new(
+        Network::ETHEREUM,
+        NetworkApiConfig {
+            endpoint: "http://127.0.0.1:1".to_string(),
+            backend: None,
+            address_gap_limit: 20,
+            indexer: None,
+            scan_from_block: None,
+            wallet_birthday: None,
+        },
+        Arc::new(Mutex::new(Box::new(StorageApiMock::new()))),
+    );
+
+    let usdt = Token::builtin().remove(0);
+    let to = Address::repeat_byte(0x33);
+    let input = IERC20::transferCall {
+        to,
+        amount: U256::from(2_500_000),
+    }
+    .abi_encode();
+    let tx = TxEip1559 {
+        chain_id: 1,
+        to: TxKind::Call(Address::from_str(&usdt.contract).unwrap()),
+        input: input.into(),
+        ..Default::default()
+    };
+
+    let mut entry = journal_entry(&tx, Address::repeat_byte(0x22));
+    entry.hash = TxHash::repeat_byte(0x11);
+    entry.fee = Some(U256::from(1_000_000_000_000_000u64));
+    entry.status = TransactionStatus::Succeeded;
+    api.journal.lock().await.record(entry.clone()).await;
+
+    let tx_uid = TransactionUid {
+        uid: entry.hash.to_string(),
+    };
+    let info = api.get_transaction_info(&tx_uid).await.unwrap();
+    let TransactionType::Withdraw {
+        to: receiver,
+        amount,
+    } = info.ty
+    else {
+        panic!("Sent token transfer should be a withdrawal");
+    };
+    assert_eq!(receiver.public_key, to.to_string());
+    assert_eq!(amount, Decimal::new(25, 1));
+    assert_eq!(info.token.as_deref(), Some("USDT"));
+
+    // Transfers of unknown tokens can't be described.
+    let mut unknown = entry.clone();
+    unknown.hash = TxHash::repeat_byte(0x12);
+    unknown.token.as_mut().unwrap().contract = Address::repeat_byte(0x44);
+    api.journal.lock().await.record(unknown.clone()).await;
+    let tx_uid = TransactionUid {
+        uid: unknown.hash.to_string(),
+    };
+    assert!(api.get_transaction_info(&tx_uid).await.is_none());
+}
+
+#[test]
+fn test_base_units_conversion() {
+    let amount = BigDecimal::from_str("12.345678").unwrap();
+
+    let units = to_base_units(amount.clone(), 6).unwrap();
+    assert_eq!(units, U256::from(12_345_678));
+    assert_eq!(from_base_units(units, 6), amount);
+
+    assert!(to_base_units(amount, 5).is_none());
+}
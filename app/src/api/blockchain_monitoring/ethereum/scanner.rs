@@ -0,0 +1,553 @@
+//! History of ethereum accounts reconstructed by scanning blocks. It's used on networks without
+//! an indexer, e.g. private chains and devnets. Only transfers of coins made by transactions
+//! themselves are found, internal transfers and transfers of tokens are not.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, TxHash, U256},
+    providers::{Provider, RootProvider},
+    transports::http::{Client, Http},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    super::{Account, Network, SharedStorage, TransactionInfo, TransactionType, TransactionUid},
+    indexer::IndexedTransaction,
+    to_decimal,
+};
+
+/// Scan progress is saved every this number of blocks, so interrupted scan isn't started over.
+const SAVE_INTERVAL: u64 = 100;
+
+pub struct Scanner {
+    provider: RootProvider<Http<Client>>,
+    storage: SharedStorage,
+    /// Name of the scan state in the storage, every network has its own one.
+    name: String,
+    /// Block scanning of newly tracked accounts starts from.
+    start_block: u64,
+    decimals: u8,
+    /// Loaded on the first scan.
+    state: Option<ScanState>,
+    transactions: HashMap<TransactionUid, TransactionInfo>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ScanState {
+    /// Tracked accounts and numbers of the blocks their scanning continues from.
+    accounts: BTreeMap<Address, u64>,
+    /// Transactions sent or received by the tracked accounts in the order of blocks.
+    transfers: Vec<Transfer>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Transfer {
+    hash: TxHash,
+    block_number: u64,
+    from: Address,
+    /// `None` for transactions deploying contracts.
+    to: Option<Address>,
+    /// Amount of coins attached to the transaction in base units.
+    value: U256,
+    /// Fee paid in base units of the coin.
+    fee: U256,
+    /// `false` for reverted transactions, they don't transfer coins, but the fee is paid.
+    succeeded: bool,
+    timestamp: DateTime<Utc>,
+}
+
+impl Scanner {
+    pub fn new(
+        provider: RootProvider<Http<Client>>,
+        network: Network,
+        storage: SharedStorage,
+        start_block: u64,
+        decimals: u8,
+    ) -> Self {
+        Self {
+            provider,
+            storage,
+            name: format!("{}_scan.json", network.id()),
+            start_block,
+            decimals,
+            state: None,
+            transactions: HashMap::new(),
+        }
+    }
+
+    /// Scans blocks up to the latest one and returns transactions of the account found so far,
+    /// latest first. Account is tracked from the first call on, so only blocks added since
+    /// the previous call are scanned for it.
+    pub async fn get_transactions(&mut self, account: Address) -> Vec<IndexedTransaction> {
+        let mut state = match self.state.take() {
+            Some(state) => state,
+            None => self.load().await,
+        };
+
+        state.accounts.entry(account).or_insert(self.start_block);
+        self.scan(&mut state).await;
+
+        let mut transactions = vec![];
+        for transfer in state.transfers.iter().rev() {
+            let Some(info) = self.describe(transfer, account) else {
+                continue;
+            };
+
+            let uid = TransactionUid {
+                uid: transfer.hash.to_string(),
+            };
+            self.transactions.insert(uid.clone(), info);
+            transactions.push(IndexedTransaction {
+                hash: transfer.hash,
+                uid,
+            });
+        }
+
+        self.state = Some(state);
+
+        transactions
+    }
+
+    pub fn get_transaction_info(&self, tx_uid: &TransactionUid) -> Option<TransactionInfo> {
+        self.transactions.get(tx_uid).cloned()
+    }
+
+    async fn load(&self) -> ScanState {
+        match self.storage.lock().await.load(&self.name).await {
+            Some(stored) => serde_json::from_str(&stored).unwrap_or_else(|e| {
+                log::error!("Failed to parse scan state {}: {}", self.name, e);
+                ScanState::default()
+            }),
+            None => ScanState::default(),
+        }
+    }
+
+    async fn save(&self, state: &ScanState) {
+        let state = serde_json::to_string(state).expect("Scan state should be serializable");
+
+        self.storage.lock().await.save(&self.name, state).await;
+    }
+
+    /// Scans blocks every tracked account isn't scanned up to yet. Progress is kept if
+    /// any of the blocks can't be fetched, so the scan continues from it next time.
+    async fn scan(&self, state: &mut ScanState) {
+        let head = match self.provider.get_block_number().await {
+            Ok(head) => head,
+            Err(error) => {
+                log::error!("Failed to fetch latest block number: {}", error);
+                return;
+            }
+        };
+
+        let Some(&first) = state.accounts.values().min() else {
+            return;
+        };
+
+        let mut known: HashSet<_> = state.transfers.iter().map(|t| t.hash).collect();
+        for number in first..=head {
+            // Accounts added later are scanned from the start, but blocks are fetched once.
+            let accounts: HashSet<_> = state
+                .accounts
+                .iter()
+                .filter(|(_, next)| **next <= number)
+                .map(|(account, _)| *account)
+                .collect();
+
+            let Some(transfers) = self.scan_block(number, &accounts).await else {
+                break;
+            };
+
+            for transfer in transfers {
+                if known.insert(transfer.hash) {
+                    state.transfers.push(transfer);
+                }
+            }
+            for next in state.accounts.values_mut() {
+                *next = (*next).max(number + 1);
+            }
+
+            if (number + 1) % SAVE_INTERVAL == 0 {
+                self.save(state).await;
+            }
+        }
+
+        // Blocks scanned for newly tracked accounts precede the ones scanned before.
+        state
+            .transfers
+            .sort_by_key(|transfer| transfer.block_number);
+        self.save(state).await;
+    }
+
+    /// Transactions of the block sent or received by any of the accounts.
+    async fn scan_block(&self, number: u64, accounts: &HashSet<Address>) -> Option<Vec<Transfer>> {
+        let block = match self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Number(number), true)
+            .await
+        {
+            Ok(Some(block)) => block,
+            Ok(None) => {
+                log::error!("Block {} is not found", number);
+                return None;
+            }
+            Err(error) => {
+                log::error!("Failed to fetch block {}: {}", number, error);
+                return None;
+            }
+        };
+        let timestamp = DateTime::from_timestamp(block.header.timestamp as i64, 0)?;
+
+        let mut transfers = vec![];
+        for tx in block.transactions.txns() {
+            let matches =
+                accounts.contains(&tx.from) || tx.to.is_some_and(|to| accounts.contains(&to));
+            if !matches {
+                continue;
+            }
+
+            let receipt = match self.provider.get_transaction_receipt(tx.hash).await {
+                Ok(Some(receipt)) => receipt,
+                Ok(None) => {
+                    log::error!("Receipt of {} is not found", tx.hash);
+                    return None;
+                }
+                Err(error) => {
+                    log::error!("Failed to fetch receipt of {}: {}", tx.hash, error);
+                    return None;
+                }
+            };
+
+            transfers.push(Transfer {
+                hash: tx.hash,
+                block_number: number,
+                from: tx.from,
+                to: tx.to,
+                value: tx.value,
+                fee: U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price),
+                succeeded: receipt.status(),
+                timestamp,
+            });
+        }
+
+        Some(transfers)
+    }
+
+    /// Describes the transfer relative to the account, `None` if it doesn't affect the account.
+    fn describe(&self, transfer: &Transfer, account: Address) -> Option<TransactionInfo> {
+        let ty = if transfer.from == account {
+            let value = if transfer.succeeded {
+                transfer.value
+            } else {
+                U256::ZERO
+            };
+
+            TransactionType::Withdraw {
+                to: Account::external(transfer.to.unwrap_or_default().to_string()),
+                amount: to_decimal(value + transfer.fee, self.decimals)?,
+            }
+        } else if transfer.to == Some(account) && transfer.succeeded {
+            TransactionType::Deposit {
+                from: Account::external(transfer.from.to_string()),
+                amount: to_decimal(transfer.value, self.decimals)?,
+            }
+        } else {
+            return None;
+        };
+
+        Some(TransactionInfo {
+            ty,
+            timestamp: transfer.timestamp,
+            token: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::{
+            Arc,
+            atomic::{AtomicU64, Ordering},
+        },
+    };
+
+    use alloy::providers::ProviderBuilder;
+    use rust_decimal::Decimal;
+    use serde_json::{Value, json};
+    use tokio::sync::Mutex;
+
+    use super::*;
+    use crate::api::{stand_in, storage::mock::StorageApiMock};
+
+    const ACCOUNT: Address = Address::repeat_byte(0x11);
+    const OTHER_ACCOUNT: Address = Address::repeat_byte(0x22);
+    const STRANGER: Address = Address::repeat_byte(0x33);
+
+    const GAS_USED: u64 = 21_000;
+    const GAS_PRICE: u64 = 1_000_000_000;
+
+    fn hash(block_number: u64) -> TxHash {
+        TxHash::left_padding_from(&block_number.to_be_bytes())
+    }
+
+    /// Every block has a single transaction, it's reverted in the blocks divisible by 10.
+    fn transaction(block_number: u64) -> Option<(Address, Address, u64)> {
+        match block_number {
+            2 => Some((STRANGER, ACCOUNT, 3_000_000_000_000_000_000)),
+            3 => Some((ACCOUNT, STRANGER, 1_000_000_000_000_000_000)),
+            4 => Some((STRANGER, OTHER_ACCOUNT, 2_000_000_000_000_000_000)),
+            5 => Some((STRANGER, STRANGER, 1)),
+            6 => Some((ACCOUNT, OTHER_ACCOUNT, 500_000_000_000_000_000)),
+            10 => Some((ACCOUNT, STRANGER, 1_000_000_000_000_000_000)),
+            _ => None,
+        }
+    }
+
+    fn block(number: u64) -> Value {
+        let transactions: Vec<_> = transaction(number)
+            .map(|(from, to, value)| {
+                json!({
+                    "hash": hash(number),
+                    "nonce": "0x0",
+                    "blockHash": hash(number),
+                    "blockNumber": format!("{:#x}", number),
+                    "transactionIndex": "0x0",
+                    "from": from,
+                    "to": to,
+                    "value": format!("{:#x}", value),
+                    "gasPrice": format!("{:#x}", GAS_PRICE),
+                    "gas": format!("{:#x}", GAS_USED),
+                    "input": "0x",
+                    "type": "0x0",
+                    "v": "0x1b",
+                    "r": "0x1",
+                    "s": "0x1",
+                })
+            })
+            .into_iter()
+            .collect();
+
+        json!({
+            "hash": hash(number),
+            "parentHash": hash(number.saturating_sub(1)),
+            "sha3Uncles": TxHash::ZERO,
+            "miner": Address::ZERO,
+            "stateRoot": TxHash::ZERO,
+            "transactionsRoot": TxHash::ZERO,
+            "receiptsRoot": TxHash::ZERO,
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "difficulty": "0x0",
+            "number": format!("{:#x}", number),
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x0",
+            "timestamp": format!("{:#x}", 1_700_000_000 + number * 12),
+            "extraData": "0x",
+            "uncles": [],
+            "transactions": transactions,
+        })
+    }
+
+    fn receipt(number: u64) -> Value {
+        let (from, to, _) = transaction(number).unwrap();
+
+        json!({
+            "type": "0x0",
+            "status": if number % 10 == 0 { "0x0" } else { "0x1" },
+            "cumulativeGasUsed": format!("{:#x}", GAS_USED),
+            "logs": [],
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "transactionHash": hash(number),
+            "transactionIndex": "0x0",
+            "blockHash": hash(number),
+            "blockNumber": format!("{:#x}", number),
+            "gasUsed": format!("{:#x}", GAS_USED),
+            "effectiveGasPrice": format!("{:#x}", GAS_PRICE),
+            "from": from,
+            "to": to,
+            "contractAddress": null,
+        })
+    }
+
+    /// Serves the chain up to the `head` block and records numbers of the requested blocks.
+    async fn serve_chain(
+        head: Arc<AtomicU64>,
+        requested: Arc<std::sync::Mutex<Vec<u64>>>,
+    ) -> String {
+        stand_in::serve(move |_, body| {
+            let request: Value = serde_json::from_str(body).unwrap();
+            let params = &request["params"];
+            let parse_number = |value: &Value| {
+                u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+            };
+
+            let result = match request["method"].as_str().unwrap() {
+                "eth_blockNumber" => json!(format!("{:#x}", head.load(Ordering::SeqCst))),
+                "eth_getBlockByNumber" => {
+                    let number = parse_number(&params[0]);
+                    assert_eq!(params[1], json!(true));
+                    requested.lock().unwrap().push(number);
+                    block(number)
+                }
+                "eth_getTransactionReceipt" => {
+                    let tx_hash: TxHash = serde_json::from_value(params[0].clone()).unwrap();
+                    let number = (1..=head.load(Ordering::SeqCst))
+                        .find(|number| hash(*number) == tx_hash)
+                        .unwrap();
+                    receipt(number)
+                }
+                method => panic!("Unexpected method {}", method),
+            };
+
+            Some(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+        })
+        .await
+    }
+
+    fn stand_in_scanner(url: &str, storage: SharedStorage) -> Scanner {
+        Scanner::new(
+            ProviderBuilder::new().on_http(url.parse().unwrap()),
+            "anvil".parse().unwrap(),
+            storage,
+            1,
+            18,
+        )
+    }
+
+    fn hashes(transactions: &[IndexedTransaction]) -> Vec<TxHash> {
+        transactions.iter().map(|tx| tx.hash).collect()
+    }
+
+    #[tokio::test]
+    async fn test_incremental_scan() {
+        let head = Arc::new(AtomicU64::new(4));
+        let requested = Arc::new(std::sync::Mutex::new(vec![]));
+        let url = serve_chain(head.clone(), requested.clone()).await;
+        let storage: SharedStorage = Arc::new(Mutex::new(Box::new(StorageApiMock::new())));
+
+        let mut scanner = stand_in_scanner(&url, storage.clone());
+        let txs = scanner.get_transactions(ACCOUNT).await;
+        assert_eq!(hashes(&txs), [hash(3), hash(2)]);
+        assert_eq!(*requested.lock().unwrap(), [1, 2, 3, 4]);
+
+        let fee = Decimal::new(21, 6);
+        let Some(TransactionInfo {
+            ty: TransactionType::Withdraw { to, amount },
+            ..
+        }) = scanner.get_transaction_info(&txs[0].uid)
+        else {
+            panic!("Transaction should be a withdrawal");
+        };
+        assert_eq!(to.public_key, STRANGER.to_string());
+        assert_eq!(amount, Decimal::ONE + fee);
+
+        // Progress is persisted, so only new blocks are scanned after restart.
+        head.store(10, Ordering::SeqCst);
+        requested.lock().unwrap().clear();
+        let mut scanner = stand_in_scanner(&url, storage.clone());
+        let txs = scanner.get_transactions(ACCOUNT).await;
+        assert_eq!(hashes(&txs), [hash(10), hash(6), hash(3), hash(2)]);
+        assert_eq!(*requested.lock().unwrap(), (5..=10).collect::<Vec<_>>());
+
+        // Reverted withdrawal costs only the fee.
+        let Some(TransactionInfo {
+            ty: TransactionType::Withdraw { amount, .. },
+            ..
+        }) = scanner.get_transaction_info(&txs[0].uid)
+        else {
+            panic!("Transaction should be a withdrawal");
+        };
+        assert_eq!(amount, fee);
+
+        // Newly tracked account is scanned from the start, transactions found for
+        // the other accounts are not duplicated.
+        requested.lock().unwrap().clear();
+        let txs = scanner.get_transactions(OTHER_ACCOUNT).await;
+        assert_eq!(hashes(&txs), [hash(6), hash(4)]);
+        assert_eq!(*requested.lock().unwrap(), (1..=10).collect::<Vec<_>>());
+        let Some(TransactionInfo {
+            ty: TransactionType::Deposit { from, amount },
+            ..
+        }) = scanner.get_transaction_info(&txs[0].uid)
+        else {
+            panic!("Transaction should be a deposit");
+        };
+        assert_eq!(from.public_key, ACCOUNT.to_string());
+        assert_eq!(amount, Decimal::new(5, 1));
+
+        requested.lock().unwrap().clear();
+        assert_eq!(scanner.get_transactions(ACCOUNT).await.len(), 4);
+        assert!(requested.lock().unwrap().is_empty());
+    }
+
+    #[ignore = "requires local anvil node"]
+    #[tokio::test]
+    async fn test_anvil_scan() {
+        use alloy::{
+            network::TransactionBuilder, primitives::keccak256, rpc::types::TransactionRequest,
+        };
+
+        let url = "http://127.0.0.1:8545";
+        let provider = ProviderBuilder::new().on_http(url.parse().unwrap());
+        let storage: SharedStorage = Arc::new(Mutex::new(Box::new(StorageApiMock::new())));
+
+        // Third of the default anvil accounts, it's unlocked, so node signs its transactions.
+        let sender = Address::from_str("0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC").unwrap();
+        // Fresh account, so transactions of the previous runs aren't found.
+        let receiver = Address::from_slice(&keccak256(Utc::now().to_rfc3339())[12..]);
+
+        let transfer = |value: u64| {
+            let provider = provider.clone();
+            async move {
+                let request = TransactionRequest::default()
+                    .with_from(sender)
+                    .with_to(receiver)
+                    .with_value(U256::from(value));
+                provider
+                    .send_transaction(request)
+                    .await
+                    .unwrap()
+                    .get_receipt()
+                    .await
+                    .unwrap()
+                    .transaction_hash
+            }
+        };
+
+        let start_block = provider.get_block_number().await.unwrap() + 1;
+        let new_scanner = || {
+            Scanner::new(
+                provider.clone(),
+                "anvil".parse().unwrap(),
+                storage.clone(),
+                start_block,
+                18,
+            )
+        };
+
+        let first = transfer(1_000).await;
+        let second = transfer(2_000).await;
+        let mut scanner = new_scanner();
+        let txs = scanner.get_transactions(receiver).await;
+        assert_eq!(hashes(&txs), [second, first]);
+
+        let Some(TransactionInfo {
+            ty: TransactionType::Deposit { from, amount },
+            ..
+        }) = scanner.get_transaction_info(&txs[0].uid)
+        else {
+            panic!("Transaction should be a deposit");
+        };
+        assert_eq!(from.public_key, sender.to_string());
+        assert_eq!(amount, Decimal::new(2_000, 18));
+
+        // Scan continues from the persisted height.
+        let third = transfer(3_000).await;
+        let mut scanner = new_scanner();
+        let txs = scanner.get_transactions(receiver).await;
+        assert_eq!(hashes(&txs), [third, second, first]);
+    }
+}
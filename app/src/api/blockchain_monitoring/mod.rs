@@ -1,7 +1,4 @@
-use std::{
-    collections::{HashMap, hash_map::Entry},
-    sync::Arc,
-};
+use std::{collections::HashMap, sync::Arc};
 
 use api_proc_macro::implement_cache;
 use async_trait::async_trait;
@@ -11,7 +8,10 @@ use rust_decimal::Decimal;
 use serde::Deserialize;
 use tokio::sync::Mutex;
 
-use super::common_types::{Account, Network};
+use super::{
+    common_types::{Account, Network, NetworkKind, Token},
+    storage::StorageApiT,
+};
 
 mod bitcoin;
 mod ethereum;
@@ -21,9 +21,35 @@ implement_cache! {
     pub trait BlockchainMonitoringApiT: Send + Sync + 'static {
         async fn get_balance(&self, network: Network, account: &Account) -> BigDecimal;
 
+        async fn get_token_balance(&self, network: Network, account: &Account, token: &Token) -> BigDecimal;
+
         async fn get_transactions(&self, network: Network, account: &Account) -> Vec<TransactionUid>;
 
-        async fn get_transaction_info(&self, network: Network, tx_uid: &TransactionUid) -> TransactionInfo;
+        /// Returns `None` if transaction can't be described, e.g. it's unknown to the backend.
+        async fn get_transaction_info(&self, network: Network, tx_uid: &TransactionUid) -> Option<TransactionInfo>;
+
+        /// Checks if account has ever been used on chain.
+        async fn is_account_used(&self, network: Network, account: &Account) -> bool;
+
+        async fn prepare_transfer(
+            &self,
+            network: Network,
+            from: &Account,
+            to: &Account,
+            amount: BigDecimal
+        ) -> Option<UnsignedTransaction>;
+
+        /// Prepares a call to `transfer` method of the token contract.
+        async fn prepare_token_transfer(
+            &self,
+            network: Network,
+            token: &Token,
+            from: &Account,
+            to: &Account,
+            amount: BigDecimal
+        ) -> Option<UnsignedTransaction>;
+
+        async fn send_transaction(&self, network: Network, tx: &SignedTransaction) -> Option<TransactionUid>;
     }
 }
 
@@ -33,10 +59,25 @@ pub struct TransactionUid {
     pub uid: String,
 }
 
+/// Transaction payload in the form expected by the ledger app of the corresponding network.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UnsignedTransaction {
+    pub payload: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SignedTransaction {
+    pub unsigned: UnsignedTransaction,
+    /// Signature as returned by the ledger app.
+    pub signature: Vec<u8>,
+}
+
 #[derive(Clone, Debug)]
 pub struct TransactionInfo {
     pub ty: TransactionType,
     pub timestamp: DateTime<Utc>,
+    /// Symbol of the transferred token, `None` if coins of the network are transferred.
+    pub token: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -46,10 +87,17 @@ pub enum TransactionType {
 }
 
 pub struct BlockchainMonitoringApi {
-    network_apis: Mutex<HashMap<Network, Arc<Box<dyn NetworkApi>>>>,
+    /// Apis of the networks requested so far, `None` for the ones that aren't configured.
+    network_apis: Mutex<HashMap<Network, Option<SharedNetworkApi>>>,
     config: Config,
+    /// Storage of the data backends keep locally, e.g. journal of sent ethereum transactions.
+    storage: SharedStorage,
 }
 
+type SharedStorage = Arc<Mutex<Box<dyn StorageApiT>>>;
+
+type SharedNetworkApi = Arc<Box<dyn NetworkApi>>;
+
 pub struct Config {
     pub network_configs: HashMap<Network, NetworkApiConfig>,
 }
@@ -57,46 +105,150 @@ pub struct Config {
 #[derive(Clone, Deserialize)]
 pub struct NetworkApiConfig {
     pub endpoint: String,
+    /// Kind of the service behind the endpoint, node of the network is expected if not set.
+    #[serde(default)]
+    pub backend: Option<Backend>,
+    /// Number of unused addresses in a row after which scanning of the account addresses stops.
+    #[serde(default = "default_address_gap_limit")]
+    pub address_gap_limit: u32,
+    /// Etherscan compatible API history of ethereum accounts is fetched from. Only transactions
+    /// sent through ledger-tui are listed if it's not set.
+    #[serde(default)]
+    pub indexer: Option<IndexerConfig>,
+    /// Block scanning of ethereum accounts starts from, history of coin transfers is found by
+    /// scanning blocks if it's set and the indexer is not. Meant for devnets and private chains.
+    #[serde(default)]
+    pub scan_from_block: Option<u64>,
+    /// Unix time of the first transaction of bitcoin accounts, Bitcoin Core wallets rescan
+    /// the chain starting from it. Only transactions made after wallet creation are found
+    /// if it's not set.
+    #[serde(default)]
+    pub wallet_birthday: Option<u64>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct IndexerConfig {
+    /// Base URL of the API, e.g. `https://api.etherscan.io/v2/api` or the one of Blockscout
+    /// instance `https://eth.blockscout.com/api`.
+    pub url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// Node of the network, e.g. Bitcoin Core or an ethereum execution client.
+    Node,
+    /// Electrum protocol server, e.g. electrs. Bitcoin networks only.
+    Electrum,
+    /// Esplora REST API, e.g. the one of blockstream.info. Bitcoin networks only.
+    Esplora,
+}
+
+fn default_address_gap_limit() -> u32 {
+    20
 }
 
 impl BlockchainMonitoringApi {
-    pub async fn new(config: Config) -> Self {
+    pub async fn new(config: Config, storage_api: impl StorageApiT) -> Self {
         Self {
             network_apis: Default::default(),
             config,
+            storage: Arc::new(Mutex::new(Box::new(storage_api))),
         }
     }
 
-    async fn get_or_instantiate_network_api(&self, network: Network) -> Arc<Box<dyn NetworkApi>> {
-        match self.network_apis.lock().await.entry(network) {
-            Entry::Occupied(entry) => entry.get().clone(),
-            Entry::Vacant(entry) => {
-                let network_config = self.config.network_configs.get(&network).unwrap();
-
-                let api: Box<dyn NetworkApi> = match network {
-                    Network::Ethereum => Box::from(ethereum::Api::new(network_config.clone())),
-                    Network::Bitcoin => Box::from(bitcoin::Api::new(network_config.clone())),
-                };
-
-                let api: Arc<Box<dyn NetworkApi>> = Arc::from(api);
-
-                entry.insert(api.clone());
+    /// Api of the network, `None` if the network isn't configured. Networks are instantiated
+    /// once, so the ones that can't be are logged only once.
+    async fn get_or_instantiate_network_api(&self, network: Network) -> Option<SharedNetworkApi> {
+        self.network_apis
+            .lock()
+            .await
+            .entry(network)
+            .or_insert_with(|| self.instantiate_network_api(network))
+            .clone()
+    }
 
-                api
+    fn instantiate_network_api(&self, network: Network) -> Option<SharedNetworkApi> {
+        let Some(network_config) = self.config.network_configs.get(&network) else {
+            log::warn!("Network {} is not configured, skipping it", network.id());
+            return None;
+        };
+
+        let backend = network_config.backend.unwrap_or(Backend::Node);
+        // Electrum endpoint is `host:port`, the other backends are reached by URL.
+        let endpoint_valid = match backend {
+            _ if network_config.endpoint.trim().is_empty() => false,
+            Backend::Electrum => true,
+            Backend::Node | Backend::Esplora => {
+                reqwest::Url::parse(&network_config.endpoint).is_ok()
             }
+        };
+        if !endpoint_valid {
+            log::error!(
+                "Network {} has invalid endpoint {:?}, skipping it",
+                network.id(),
+                network_config.endpoint
+            );
+            return None;
         }
+
+        let api: Box<dyn NetworkApi> = match (network.kind(), backend) {
+            (NetworkKind::Ethereum, Backend::Node) => Box::from(ethereum::Api::new(
+                network,
+                network_config.clone(),
+                self.storage.clone(),
+            )),
+            (NetworkKind::Bitcoin, Backend::Node) => {
+                Box::from(bitcoin::Api::new(network, network_config.clone()))
+            }
+            (NetworkKind::Bitcoin, Backend::Electrum) => {
+                Box::from(bitcoin::electrum::Api::new(network, network_config.clone()))
+            }
+            (NetworkKind::Bitcoin, Backend::Esplora) => {
+                Box::from(bitcoin::esplora::Api::new(network, network_config.clone()))
+            }
+            (kind, backend) => {
+                log::error!(
+                    "{:?} backend is not supported for {:?} networks, skipping network {}",
+                    backend,
+                    kind,
+                    network.id()
+                );
+                return None;
+            }
+        };
+
+        Some(Arc::from(api))
     }
 }
 
 #[async_trait]
 impl BlockchainMonitoringApiT for BlockchainMonitoringApi {
     async fn get_balance(&self, network: Network, account: &Account) -> BigDecimal {
-        let network_api = self.get_or_instantiate_network_api(network).await;
+        let Some(network_api) = self.get_or_instantiate_network_api(network).await else {
+            return BigDecimal::from(0);
+        };
         network_api.get_balance(account).await
     }
 
+    async fn get_token_balance(
+        &self,
+        network: Network,
+        account: &Account,
+        token: &Token,
+    ) -> BigDecimal {
+        let Some(network_api) = self.get_or_instantiate_network_api(network).await else {
+            return BigDecimal::from(0);
+        };
+        network_api.get_token_balance(account, token).await
+    }
+
     async fn get_transactions(&self, network: Network, account: &Account) -> Vec<TransactionUid> {
-        let network_api = self.get_or_instantiate_network_api(network).await;
+        let Some(network_api) = self.get_or_instantiate_network_api(network).await else {
+            return vec![];
+        };
         network_api.get_transactions(account).await
     }
 
@@ -104,30 +256,190 @@ impl BlockchainMonitoringApiT for BlockchainMonitoringApi {
         &self,
         network: Network,
         tx_uid: &TransactionUid,
-    ) -> TransactionInfo {
-        let network_api = self.get_or_instantiate_network_api(network).await;
+    ) -> Option<TransactionInfo> {
+        let network_api = self.get_or_instantiate_network_api(network).await?;
         network_api.get_transaction_info(tx_uid).await
     }
+
+    async fn is_account_used(&self, network: Network, account: &Account) -> bool {
+        let Some(network_api) = self.get_or_instantiate_network_api(network).await else {
+            return false;
+        };
+        network_api.is_account_used(account).await
+    }
+
+    async fn prepare_transfer(
+        &self,
+        network: Network,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let network_api = self.get_or_instantiate_network_api(network).await?;
+        network_api.prepare_transfer(from, to, amount).await
+    }
+
+    async fn prepare_token_transfer(
+        &self,
+        network: Network,
+        token: &Token,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let network_api = self.get_or_instantiate_network_api(network).await?;
+        network_api
+            .prepare_token_transfer(token, from, to, amount)
+            .await
+    }
+
+    async fn send_transaction(
+        &self,
+        network: Network,
+        tx: &SignedTransaction,
+    ) -> Option<TransactionUid> {
+        let network_api = self.get_or_instantiate_network_api(network).await?;
+        network_api.send_transaction(tx).await
+    }
 }
 
 #[async_trait]
 trait NetworkApi: Send + Sync + 'static {
     async fn get_balance(&self, account: &Account) -> BigDecimal;
 
+    async fn get_token_balance(&self, account: &Account, token: &Token) -> BigDecimal;
+
     async fn get_transactions(&self, account: &Account) -> Vec<TransactionUid>;
 
-    async fn get_transaction_info(&self, tx_uid: &TransactionUid) -> TransactionInfo;
+    async fn get_transaction_info(&self, tx_uid: &TransactionUid) -> Option<TransactionInfo>;
+
+    async fn is_account_used(&self, account: &Account) -> bool;
+
+    async fn prepare_transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction>;
+
+    async fn prepare_token_transfer(
+        &self,
+        token: &Token,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction>;
+
+    async fn send_transaction(&self, tx: &SignedTransaction) -> Option<TransactionUid>;
 }
 
 pub mod mock {
-    use std::{collections::HashMap, iter};
-
+    use std::{collections::HashMap, iter, str::FromStr};
+
+    use ::bitcoin::{
+        Address, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+        absolute::LockTime,
+    };
+    use alloy::{
+        consensus::{SignableTransaction, TxEip1559},
+        primitives::{Address as EthereumAddress, Bytes, TxKind, U256},
+        sol_types::SolCall,
+    };
+    use bigdecimal::ToPrimitive;
     use rust_decimal::prelude::FromPrimitive;
 
-    use super::*;
+    use super::{
+        bitcoin::transfer::{
+            MIN_FEE_RATE, SpendableOutput, build_transfer, change_account, finalize_transaction,
+        },
+        ethereum::{IERC20, to_base_units},
+        *,
+    };
+
+    /// Made up output of the sender exceeds the amount by this value, so it covers the fee
+    /// and leaves some change.
+    const MOCK_SURPLUS: u64 = 10_000;
+
+    const MOCK_GAS_LIMIT: u64 = 100_000;
+    const MOCK_MAX_FEE_PER_GAS: u128 = 30_000_000_000;
+    const MOCK_MAX_PRIORITY_FEE_PER_GAS: u128 = 1_000_000_000;
+
+    /// EIP-1559 transaction with made up nonce and fees, so it can be signed by the mock
+    /// ledger api.
+    fn mock_eip1559_tx(network: Network, to: &str, value: U256, input: Bytes) -> Option<Vec<u8>> {
+        let tx = TxEip1559 {
+            chain_id: network.chain_id()?,
+            nonce: 0,
+            gas_limit: MOCK_GAS_LIMIT,
+            max_fee_per_gas: MOCK_MAX_FEE_PER_GAS,
+            max_priority_fee_per_gas: MOCK_MAX_PRIORITY_FEE_PER_GAS,
+            to: TxKind::Call(EthereumAddress::from_str(to).ok()?),
+            value,
+            access_list: Default::default(),
+            input,
+        };
+
+        let mut payload = vec![];
+        tx.encode_for_signing(&mut payload);
+
+        Some(payload)
+    }
+
+    /// PSBT spending a made up output of `from` account, so it can be signed by the mock
+    /// ledger api.
+    async fn mock_psbt(
+        network: Network,
+        from: &Account,
+        to: &Account,
+        amount: BigDecimal,
+    ) -> Option<UnsignedTransaction> {
+        let decimals = network.get_info().decimals as i64;
+        let value = (amount.clone() * BigDecimal::new(1.into(), -decimals))
+            .with_scale(0)
+            .to_u64()?;
+
+        let funding = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: value + MOCK_SURPLUS,
+                script_pubkey: Address::from_str(&from.receive_address(network))
+                    .ok()?
+                    .assume_checked()
+                    .script_pubkey(),
+            }],
+        };
+        let utxo = SpendableOutput {
+            outpoint: OutPoint::new(funding.txid(), 0),
+            txout: funding.output[0].clone(),
+            owner: from.clone(),
+        };
+
+        build_transfer(
+            network,
+            vec![utxo],
+            to,
+            amount,
+            MIN_FEE_RATE,
+            &change_account(from, [])?,
+            |_| {
+                let funding = funding.clone();
+                async move { Some(funding) }
+            },
+        )
+        .await
+    }
 
     pub struct BlockchainMonitoringApiMock {
         txs: HashMap<TransactionUid, TransactionInfo>,
+        /// Transactions passed to `send_transaction`.
+        sent: std::sync::Mutex<Vec<SignedTransaction>>,
     }
 
     impl BlockchainMonitoringApiMock {
@@ -135,22 +447,20 @@ pub mod mock {
             let txs = vec![
                 (
                     TransactionType::Withdraw {
-                        to: Account {
-                            public_key:
-                                "0xMOCK_000000000000000000000000000000000000000000000000000000_MOCK"
-                                    .to_string(),
-                        },
+                        to: Account::external(
+                            "0xMOCK_000000000000000000000000000000000000000000000000000000_MOCK"
+                                .to_string(),
+                        ),
                         amount: Decimal::from_u64(10).unwrap(),
                     },
                     Utc::now(),
                 ),
                 (
                     TransactionType::Deposit {
-                        from: Account {
-                            public_key:
-                                "0xMOCK_000000000000000000000000000000000000000000000000000000_MOCK"
-                                    .to_string(),
-                        },
+                        from: Account::external(
+                            "0xMOCK_000000000000000000000000000000000000000000000000000000_MOCK"
+                                .to_string(),
+                        ),
                         amount: Decimal::from_i128_with_scale(12345, 3),
                     },
                     Utc::now(),
@@ -166,12 +476,24 @@ pub mod mock {
                         TransactionUid {
                             uid: format!("MOCK_TX_HASH_{}", idx),
                         },
-                        TransactionInfo { ty, timestamp },
+                        TransactionInfo {
+                            ty,
+                            timestamp,
+                            token: None,
+                        },
                     )
                 })
                 .collect();
 
-            Self { txs }
+            Self {
+                txs,
+                sent: Default::default(),
+            }
+        }
+
+        #[cfg(test)]
+        pub fn sent_transactions(&self) -> Vec<SignedTransaction> {
+            self.sent.lock().unwrap().clone()
         }
     }
 
@@ -181,6 +503,15 @@ pub mod mock {
             BigDecimal::from_u32(102312).expect("Failed to create BigDecimal from u32")
         }
 
+        async fn get_token_balance(
+            &self,
+            _network: Network,
+            _account: &Account,
+            _token: &Token,
+        ) -> BigDecimal {
+            BigDecimal::new(2500125.into(), 2)
+        }
+
         async fn get_transactions(
             &self,
             _network: Network,
@@ -193,8 +524,98 @@ pub mod mock {
             &self,
             _network: Network,
             tx_uid: &TransactionUid,
-        ) -> TransactionInfo {
-            self.txs.get(tx_uid).cloned().unwrap()
+        ) -> Option<TransactionInfo> {
+            self.txs.get(tx_uid).cloned()
+        }
+
+        async fn is_account_used(&self, _network: Network, _account: &Account) -> bool {
+            true
+        }
+
+        async fn prepare_transfer(
+            &self,
+            network: Network,
+            from: &Account,
+            to: &Account,
+            amount: BigDecimal,
+        ) -> Option<UnsignedTransaction> {
+            match network.kind() {
+                NetworkKind::Bitcoin => mock_psbt(network, from, to, amount).await,
+                NetworkKind::Ethereum => {
+                    let value = to_base_units(amount, network.get_info().decimals)?;
+                    let payload = mock_eip1559_tx(network, &to.public_key, value, Bytes::new())?;
+
+                    Some(UnsignedTransaction { payload })
+                }
+            }
+        }
+
+        async fn prepare_token_transfer(
+            &self,
+            network: Network,
+            token: &Token,
+            _from: &Account,
+            to: &Account,
+            amount: BigDecimal,
+        ) -> Option<UnsignedTransaction> {
+            let input = IERC20::transferCall {
+                to: EthereumAddress::from_str(&to.public_key).ok()?,
+                amount: to_base_units(amount, token.decimals)?,
+            }
+            .abi_encode();
+
+            Some(UnsignedTransaction {
+                payload: mock_eip1559_tx(network, &token.contract, U256::ZERO, input.into())?,
+            })
+        }
+
+        /// Bitcoin transactions are finalized, so their hash is the real one.
+        async fn send_transaction(
+            &self,
+            network: Network,
+            tx: &SignedTransaction,
+        ) -> Option<TransactionUid> {
+            let uid = match network.kind() {
+                NetworkKind::Bitcoin => finalize_transaction(&tx.signature)?.txid().to_string(),
+                NetworkKind::Ethereum => "MOCK_SENT_TX_HASH".to_string(),
+            };
+
+            self.sent.lock().unwrap().push(tx.clone());
+
+            Some(TransactionUid { uid })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{common_types::bip84_test_account, storage::mock::StorageApiMock};
+
+    #[tokio::test]
+    async fn test_unconfigured_networks_are_skipped() {
+        let network_configs = HashMap::from([(
+            Network::ETHEREUM,
+            NetworkApiConfig {
+                endpoint: " ".into(),
+                backend: None,
+                address_gap_limit: default_address_gap_limit(),
+                indexer: None,
+                scan_from_block: None,
+                wallet_birthday: None,
+            },
+        )]);
+        let api =
+            BlockchainMonitoringApi::new(Config { network_configs }, StorageApiMock::new()).await;
+        let account = bip84_test_account(0);
+
+        for network in [Network::BITCOIN, Network::ETHEREUM] {
+            assert_eq!(
+                api.get_balance(network, &account).await,
+                BigDecimal::from(0)
+            );
+            assert!(api.get_transactions(network, &account).await.is_empty());
+            assert!(!api.is_account_used(network, &account).await);
         }
     }
 }
@@ -35,21 +35,19 @@ pub enum TimePeriod {
 /// Uniformly distributed prices for given period of time, arranged from historical to most recent.
 pub type PriceHistory = Vec<Decimal>;
 
-#[allow(clippy::upper_case_acronyms)]
+/// Coin identified by its ticker, e.g. `BTC`.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub enum Coin {
-    BTC,
-    ETH,
-    USDT,
-}
+pub struct Coin(&'static str);
 
 impl Coin {
+    pub const USDT: Self = Self::new("USDT");
+
+    pub const fn new(ticker: &'static str) -> Self {
+        Self(ticker)
+    }
+
     fn to_api_string(self) -> String {
-        match self {
-            Self::BTC => "BTC".to_string(),
-            Self::ETH => "ETH".to_string(),
-            Self::USDT => "USDT".to_string(),
-        }
+        self.0.to_string()
     }
 }
 
@@ -188,8 +186,10 @@ pub mod mock {
         pub fn new() -> Self {
             let mut prices = HashMap::new();
 
-            prices.insert((Coin::BTC, Coin::USDT), dec!(123000.023));
-            prices.insert((Coin::ETH, Coin::USDT), dec!(4203.908));
+            prices.insert((Coin::new("BTC"), Coin::USDT), dec!(123000.023));
+            prices.insert((Coin::new("ETH"), Coin::USDT), dec!(4203.908));
+            prices.insert((Coin::new("POL"), Coin::USDT), dec!(0.3821));
+            prices.insert((Coin::new("BNB"), Coin::USDT), dec!(1094.52));
             prices.insert((Coin::USDT, Coin::USDT), dec!(1));
 
             Self { prices }
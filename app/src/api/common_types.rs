@@ -1,43 +1,497 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use bitcoin::{
+    Address, PublicKey, base58,
+    bip32::{self, ChildNumber, ExtendedPubKey},
+    secp256k1::Secp256k1,
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
-pub enum Network {
+use super::coin_price::Coin;
+
+/// Network registered in [`NETWORKS`]. It's a cheap handle to the network description,
+/// networks are compared and serialized by their ids.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Network(&'static NetworkInfo);
+
+/// Protocol family of the network. It defines ledger app commands accounts are derived and
+/// signed with and blockchain monitoring backend used for the network.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NetworkKind {
     Bitcoin,
     Ethereum,
 }
 
 pub struct NetworkInfo {
-    pub name: String,
-    #[allow(dead_code)]
-    pub symbol: String,
+    /// Identifier used in configuration files, e.g. `bitcoin`.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    /// Glyph shown next to the network name.
+    pub icon: &'static str,
+    /// Number of decimal places of the native coin.
+    pub decimals: u8,
+    /// Name of the ledger app the network accounts are managed with.
+    pub ledger_app: &'static str,
+    /// Names and templates of the builtin derivation schemes, the first one is the default.
+    pub derivation_schemes: &'static [(&'static str, &'static str)],
+    /// Native coin as listed by the coin price api, test networks have none.
+    pub coin: Option<Coin>,
+    pub kind: NetworkKind,
+    /// Coins of test networks have no value, so such networks are marked in the UI.
+    pub testnet: bool,
+    /// EIP-155 chain id, present only for networks of ethereum kind.
+    pub chain_id: Option<u64>,
+    /// Network addresses are encoded for, present only for networks of bitcoin kind.
+    pub bitcoin_network: Option<bitcoin::Network>,
 }
 
+/// EVM networks share the ethereum app and addresses derived with it.
+const EVM_DERIVATION_SCHEMES: &[(&str, &str)] = &[
+    ("Ledger Live", "m/44'/60'/n'/0/0"),
+    ("Legacy", "m/44'/60'/0'/n"),
+];
+
+/// Test networks use coin type 1 for all the coins.
+const BITCOIN_TEST_DERIVATION_SCHEMES: &[(&str, &str)] = &[
+    ("Native SegWit", "m/84'/1'/n'/0/0"),
+    ("Taproot", "m/86'/1'/n'/0/0"),
+    ("Nested SegWit", "m/49'/1'/n'/0/0"),
+    ("Legacy", "m/44'/1'/n'/0/0"),
+];
+
+const EVM_TEST_DERIVATION_SCHEMES: &[(&str, &str)] = &[
+    ("Ledger Live", "m/44'/1'/n'/0/0"),
+    ("Legacy", "m/44'/1'/0'/n"),
+];
+
+/// Networks supported by the app. Adding a network requires an entry here and, if it's
+/// of a new kind, ledger app and blockchain monitoring implementations for it.
+pub const NETWORKS: &[NetworkInfo] = &[
+    NetworkInfo {
+        id: "bitcoin",
+        name: "Bitcoin",
+        symbol: "BTC",
+        icon: "₿",
+        decimals: 8,
+        ledger_app: "Bitcoin",
+        derivation_schemes: &[
+            ("Native SegWit", "m/84'/0'/n'/0/0"),
+            ("Taproot", "m/86'/0'/n'/0/0"),
+            ("Nested SegWit", "m/49'/0'/n'/0/0"),
+            ("Legacy", "m/44'/0'/n'/0/0"),
+        ],
+        coin: Some(Coin::new("BTC")),
+        kind: NetworkKind::Bitcoin,
+        testnet: false,
+        chain_id: None,
+        bitcoin_network: Some(bitcoin::Network::Bitcoin),
+    },
+    NetworkInfo {
+        id: "ethereum",
+        name: "Ethereum",
+        symbol: "ETH",
+        icon: "⟠",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_DERIVATION_SCHEMES,
+        coin: Some(Coin::new("ETH")),
+        kind: NetworkKind::Ethereum,
+        testnet: false,
+        chain_id: Some(1),
+        bitcoin_network: None,
+    },
+    NetworkInfo {
+        id: "polygon",
+        name: "Polygon",
+        symbol: "POL",
+        icon: "⬡",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_DERIVATION_SCHEMES,
+        coin: Some(Coin::new("POL")),
+        kind: NetworkKind::Ethereum,
+        testnet: false,
+        chain_id: Some(137),
+        bitcoin_network: None,
+    },
+    NetworkInfo {
+        id: "arbitrum",
+        name: "Arbitrum One",
+        symbol: "ETH",
+        icon: "◮",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_DERIVATION_SCHEMES,
+        coin: Some(Coin::new("ETH")),
+        kind: NetworkKind::Ethereum,
+        testnet: false,
+        chain_id: Some(42161),
+        bitcoin_network: None,
+    },
+    NetworkInfo {
+        id: "base",
+        name: "Base",
+        symbol: "ETH",
+        icon: "◉",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_DERIVATION_SCHEMES,
+        coin: Some(Coin::new("ETH")),
+        kind: NetworkKind::Ethereum,
+        testnet: false,
+        chain_id: Some(8453),
+        bitcoin_network: None,
+    },
+    NetworkInfo {
+        id: "bsc",
+        name: "BNB Smart Chain",
+        symbol: "BNB",
+        icon: "◆",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_DERIVATION_SCHEMES,
+        coin: Some(Coin::new("BNB")),
+        kind: NetworkKind::Ethereum,
+        testnet: false,
+        chain_id: Some(56),
+        bitcoin_network: None,
+    },
+    NetworkInfo {
+        id: "bitcoin-testnet",
+        name: "Bitcoin Testnet",
+        symbol: "tBTC",
+        icon: "₿",
+        decimals: 8,
+        ledger_app: "Bitcoin Test",
+        derivation_schemes: BITCOIN_TEST_DERIVATION_SCHEMES,
+        coin: None,
+        kind: NetworkKind::Bitcoin,
+        testnet: true,
+        chain_id: None,
+        bitcoin_network: Some(bitcoin::Network::Testnet),
+    },
+    NetworkInfo {
+        id: "bitcoin-signet",
+        name: "Bitcoin Signet",
+        symbol: "sBTC",
+        icon: "₿",
+        decimals: 8,
+        ledger_app: "Bitcoin Test",
+        derivation_schemes: BITCOIN_TEST_DERIVATION_SCHEMES,
+        coin: None,
+        kind: NetworkKind::Bitcoin,
+        testnet: true,
+        chain_id: None,
+        bitcoin_network: Some(bitcoin::Network::Signet),
+    },
+    NetworkInfo {
+        id: "bitcoin-regtest",
+        name: "Bitcoin Regtest",
+        symbol: "rBTC",
+        icon: "₿",
+        decimals: 8,
+        ledger_app: "Bitcoin Test",
+        derivation_schemes: BITCOIN_TEST_DERIVATION_SCHEMES,
+        coin: None,
+        kind: NetworkKind::Bitcoin,
+        testnet: true,
+        chain_id: None,
+        bitcoin_network: Some(bitcoin::Network::Regtest),
+    },
+    NetworkInfo {
+        id: "sepolia",
+        name: "Sepolia",
+        symbol: "SepoliaETH",
+        icon: "⟠",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_TEST_DERIVATION_SCHEMES,
+        coin: None,
+        kind: NetworkKind::Ethereum,
+        testnet: true,
+        chain_id: Some(11155111),
+        bitcoin_network: None,
+    },
+    // Local development node, e.g. anvil or hardhat.
+    NetworkInfo {
+        id: "anvil",
+        name: "Anvil",
+        symbol: "ETH",
+        icon: "⟠",
+        decimals: 18,
+        ledger_app: "Ethereum",
+        derivation_schemes: EVM_TEST_DERIVATION_SCHEMES,
+        coin: None,
+        kind: NetworkKind::Ethereum,
+        testnet: true,
+        chain_id: Some(31337),
+        bitcoin_network: None,
+    },
+];
+
 impl Network {
-    pub fn get_info(&self) -> NetworkInfo {
-        match self {
-            Self::Bitcoin => NetworkInfo {
-                name: "Bitcoin".into(),
-                symbol: "BTC".into(),
-            },
-            Self::Ethereum => NetworkInfo {
-                name: "Ethereum".into(),
-                symbol: "ETH".into(),
-            },
-        }
+    pub const BITCOIN: Self = Self(&NETWORKS[0]);
+    pub const ETHEREUM: Self = Self(&NETWORKS[1]);
+
+    /// All the registered networks in the order they're listed in.
+    pub fn all() -> impl Iterator<Item = Self> {
+        NETWORKS.iter().map(Self)
+    }
+
+    pub fn get_info(&self) -> &'static NetworkInfo {
+        self.0
+    }
+
+    pub fn id(&self) -> &'static str {
+        self.0.id
+    }
+
+    pub fn kind(&self) -> NetworkKind {
+        self.0.kind
+    }
+
+    pub fn chain_id(&self) -> Option<u64> {
+        self.0.chain_id
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        self.0.testnet
+    }
+
+    /// Networks the accounts of this one are valid in, including itself. Addresses are
+    /// the same in all the EVM networks using the same derivation schemes.
+    pub fn sharing_accounts(self) -> impl Iterator<Item = Self> {
+        Self::all().filter(move |network| match self.kind() {
+            NetworkKind::Bitcoin => *network == self,
+            NetworkKind::Ethereum => {
+                network.kind() == NetworkKind::Ethereum
+                    && network.get_info().derivation_schemes == self.get_info().derivation_schemes
+            }
+        })
+    }
+
+    /// Network addresses and extended keys are encoded for. Must be called only for
+    /// networks of bitcoin kind.
+    pub fn bitcoin_network(&self) -> bitcoin::Network {
+        self.0
+            .bitcoin_network
+            .expect("Networks of bitcoin kind should have bitcoin network")
+    }
+}
+
+impl PartialEq for Network {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Network {}
+
+impl Hash for Network {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Network({})", self.id())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownNetwork(String);
+
+impl fmt::Display for UnknownNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown network: {}", self.0)
+    }
+}
+
+impl FromStr for Network {
+    type Err = UnknownNetwork;
+
+    /// Ids are matched case-insensitively, so networks serialized by name are accepted as well.
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .find(|network| network.id().eq_ignore_ascii_case(id))
+            .ok_or_else(|| UnknownNetwork(id.to_string()))
+    }
+}
+
+impl TryFrom<String> for Network {
+    type Error = UnknownNetwork;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        id.parse()
+    }
+}
+
+impl From<Network> for String {
+    fn from(network: Network) -> Self {
+        network.id().to_string()
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct Account {
     pub public_key: String,
+    /// Path the account is derived with. It's present only for accounts derived from the device.
+    #[serde(default)]
+    pub derivation_path: Option<DerivationPath>,
+    /// Extended public key of the account level of derivation path (e.g. `m/84'/0'/0'`)
+    /// addresses are derived from. It's present only for bitcoin accounts derived from the device.
+    #[serde(default)]
+    pub xpub: Option<String>,
+    /// Hex encoded uncompressed public key the address is derived from. It's present only for
+    /// ethereum accounts derived from the device and is kept to verify signatures made with them.
+    #[serde(default)]
+    pub uncompressed_public_key: Option<String>,
+}
+
+/// BIP44 chain of addresses derived from the account xpub.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AddressChain {
+    /// External chain, addresses funds are received to.
+    Receive,
+    /// Internal chain, addresses change is returned to.
+    Change,
+}
+
+impl AddressChain {
+    /// Index of the chain in derivation path, e.g. 1 for `m/84'/0'/0'/1/5`.
+    pub fn index(self) -> u32 {
+        match self {
+            Self::Receive => 0,
+            Self::Change => 1,
+        }
+    }
 }
 
 impl Account {
+    /// Account not derived from the device, e.g. counterparty of a transaction.
+    pub fn external(public_key: String) -> Self {
+        Self {
+            public_key,
+            derivation_path: None,
+            xpub: None,
+            uncompressed_public_key: None,
+        }
+    }
+
     pub fn get_info(&self) -> AccountInfo {
         AccountInfo {
             public_key: self.public_key.clone(),
         }
     }
+
+    /// Address funds can be sent to. Bitcoin accounts are stored as public keys, so address
+    /// is derived from it according to the script type defined by purpose of derivation path.
+    /// Public key is returned as is if it can't be converted to an address.
+    pub fn receive_address(&self, network: Network) -> String {
+        match network.kind() {
+            NetworkKind::Ethereum => self.public_key.clone(),
+            NetworkKind::Bitcoin => {
+                bitcoin_address(self, network).unwrap_or_else(|| self.public_key.clone())
+            }
+        }
+    }
+
+    /// Derives the account of address with `index` on the `chain` from the account xpub,
+    /// e.g. `m/84'/0'/0'/0/5` for the 5th receive address of `m/84'/0'/0'` account.
+    /// Returns `None` if account has no xpub.
+    pub fn derive_address_account(&self, chain: AddressChain, index: u32) -> Option<Account> {
+        let xpub = parse_extended_pubkey(self.xpub.as_ref()?)
+            .inspect_err(|e| log::error!("Invalid account xpub: {}", e))
+            .ok()?;
+        let children = [
+            ChildNumber::from_normal_idx(chain.index()).ok()?,
+            ChildNumber::from_normal_idx(index).ok()?,
+        ];
+        let address_xpub = xpub
+            .derive_pub(&Secp256k1::verification_only(), &children)
+            .ok()?;
+
+        let account_path = self
+            .derivation_path
+            .as_ref()
+            .and_then(|path| path.ancestor(ACCOUNT_PATH_DEPTH))?;
+        let path = DerivationPath([account_path.components(), &[chain.index(), index]].concat());
+
+        Some(Account {
+            public_key: address_xpub.public_key.to_string(),
+            derivation_path: Some(path),
+            xpub: None,
+            uncompressed_public_key: None,
+        })
+    }
+
+    /// Index of the address on its chain, e.g. 5 for `m/84'/0'/0'/0/5`.
+    pub fn address_index(&self) -> Option<u32> {
+        let path = self.derivation_path.as_ref()?;
+        match path.components() {
+            [_, _, _, _, index] => Some(*index),
+            _ => None,
+        }
+    }
+}
+
+fn bitcoin_address(account: &Account, network: Network) -> Option<String> {
+    let public_key = PublicKey::from_str(&account.public_key).ok()?;
+    let path = account
+        .derivation_path
+        .clone()
+        .unwrap_or_else(|| DerivationScheme::default_path(network));
+
+    let network = network.bitcoin_network();
+    let address = match path.components().first()? ^ HARDENED_INDEX {
+        44 => Address::p2pkh(&public_key, network),
+        49 => Address::p2shwpkh(&public_key, network).ok()?,
+        84 => Address::p2wpkh(&public_key, network).ok()?,
+        86 => Address::p2tr(
+            &Secp256k1::verification_only(),
+            public_key.inner.into(),
+            None,
+            network,
+        ),
+        _ => return None,
+    };
+
+    Some(address.to_string())
+}
+
+/// Version bytes of SLIP-132 extended public keys and the BIP32 ones they correspond to.
+/// Keys are the same, versions only hint which script type addresses are derived for.
+const SLIP132_VERSIONS: &[([u8; 4], [u8; 4])] = &[
+    // ypub, zpub -> xpub
+    ([0x04, 0x9D, 0x7C, 0xB2], [0x04, 0x88, 0xB2, 0x1E]),
+    ([0x04, 0xB2, 0x47, 0x46], [0x04, 0x88, 0xB2, 0x1E]),
+    // upub, vpub -> tpub
+    ([0x04, 0x4A, 0x52, 0x62], [0x04, 0x35, 0x87, 0xCF]),
+    ([0x04, 0x5F, 0x1C, 0xF6], [0x04, 0x35, 0x87, 0xCF]),
+];
+
+/// Parses base58 encoded extended public key of mainnet(`xpub`, `ypub`, `zpub`)
+/// or testnet(`tpub`, `upub`, `vpub`).
+pub fn parse_extended_pubkey(xpub: &str) -> Result<ExtendedPubKey, bip32::Error> {
+    let mut data = base58::decode_check(xpub)?;
+
+    if let Some(version) = data.first_chunk_mut::<4>() {
+        if let Some((_, bip32_version)) = SLIP132_VERSIONS
+            .iter()
+            .find(|(slip132_version, _)| slip132_version == version)
+        {
+            *version = *bip32_version;
+        }
+    }
+
+    ExtendedPubKey::decode(&data)
 }
 
 pub struct AccountInfo {
@@ -45,3 +499,352 @@ pub struct AccountInfo {
     /// Public key of account in encoding native for network,
     pub public_key: String,
 }
+
+/// ERC-20 token deployed on the network.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Token {
+    pub network: Network,
+    pub symbol: String,
+    /// Address of the token contract.
+    pub contract: String,
+    pub decimals: u8,
+    /// Hex encoded signature of the token information issued by Ledger. It's looked up
+    /// in the Ledger crypto assets list if not set. Device displays amount and ticker
+    /// of the transfer only when signature is provided.
+    #[serde(default)]
+    pub ledger_signature: Option<String>,
+}
+
+impl Token {
+    fn new(network: Network, symbol: &str, contract: &str, decimals: u8) -> Self {
+        Self {
+            network,
+            symbol: symbol.to_string(),
+            contract: contract.to_string(),
+            decimals,
+            ledger_signature: None,
+        }
+    }
+
+    /// Widely used tokens that are tracked by default.
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new(
+                Network::ETHEREUM,
+                "USDT",
+                "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+                6,
+            ),
+            Self::new(
+                Network::ETHEREUM,
+                "USDC",
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                6,
+            ),
+            Self::new(
+                Network::ETHEREUM,
+                "DAI",
+                "0x6B175474E89094C44Da98b954EedeAC495271d0F",
+                18,
+            ),
+        ]
+    }
+}
+
+pub const HARDENED_INDEX: u32 = 1 << 31;
+
+/// Depth of `m/<purpose>'/<coin type>'/<account>'` paths.
+pub const ACCOUNT_PATH_DEPTH: usize = 3;
+
+/// BIP32 derivation path, e.g. `m/44'/60'/0'/0/0`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Child indices, hardened ones have the highest bit set.
+    pub fn components(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Path consisting of the first `depth` components.
+    pub fn ancestor(&self, depth: usize) -> Option<Self> {
+        self.0
+            .get(..depth)
+            .map(|components| Self(components.to_vec()))
+    }
+
+    /// Encodes path in the form accepted by ledger apps: number of derivations
+    /// followed by big endian child indices.
+    pub fn encode(&self) -> Vec<u8> {
+        [
+            &[self.0.len() as u8][..],
+            &self
+                .0
+                .iter()
+                .flat_map(|child| child.to_be_bytes())
+                .collect::<Vec<_>>(),
+        ]
+        .concat()
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidDerivationPath(String);
+
+impl fmt::Display for InvalidDerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid derivation path: {}", self.0)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = InvalidDerivationPath;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let error = || InvalidDerivationPath(path.to_string());
+
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(error());
+        }
+
+        components
+            .map(|component| {
+                let (index, hardened) = match component.strip_suffix(['\'', 'h']) {
+                    Some(index) => (index, true),
+                    None => (component, false),
+                };
+
+                let index: u32 = index.parse().map_err(|_| error())?;
+                if index >= HARDENED_INDEX {
+                    return Err(error());
+                }
+
+                Ok(if hardened {
+                    index | HARDENED_INDEX
+                } else {
+                    index
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl TryFrom<String> for DerivationPath {
+    type Error = InvalidDerivationPath;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        path.parse()
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for &child in &self.0 {
+            if child & HARDENED_INDEX != 0 {
+                write!(f, "/{}'", child ^ HARDENED_INDEX)?;
+            } else {
+                write!(f, "/{}", child)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<DerivationPath> for String {
+    fn from(path: DerivationPath) -> Self {
+        path.to_string()
+    }
+}
+
+/// Describes which paths accounts are derived with.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct DerivationScheme {
+    pub name: String,
+    /// Derivation path where component `n` is replaced with account index,
+    /// e.g. `m/44'/60'/n'/0/0`. Template without `n` describes a single account.
+    pub template: String,
+}
+
+impl DerivationScheme {
+    fn new(name: &str, template: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            template: template.to_string(),
+        }
+    }
+
+    /// Schemes used by the popular wallets. The first one is the default.
+    pub fn builtin(network: Network) -> Vec<Self> {
+        network
+            .get_info()
+            .derivation_schemes
+            .iter()
+            .map(|(name, template)| Self::new(name, template))
+            .collect()
+    }
+
+    pub fn default_path(network: Network) -> DerivationPath {
+        Self::builtin(network)[0]
+            .path(0)
+            .expect("Builtin derivation schemes are valid")
+    }
+
+    /// Returns `None` if there's no account with such an index in this scheme.
+    pub fn path(&self, account_index: u32) -> Option<DerivationPath> {
+        let mut has_index = false;
+
+        let path = self
+            .template
+            .split('/')
+            .map(|component| match component.strip_prefix('n') {
+                Some(suffix) => {
+                    has_index = true;
+                    format!("{}{}", account_index, suffix)
+                }
+                None => component.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if !has_index && account_index != 0 {
+            return None;
+        }
+
+        path.parse()
+            .inspect_err(|e| log::error!("Wrong derivation scheme {}: {}", self.name, e))
+            .ok()
+    }
+}
+
+/// First account of BIP84 test vector, `coin_type` goes to its derivation path.
+#[cfg(test)]
+pub fn bip84_test_account(coin_type: u32) -> Account {
+    Account {
+        public_key: "0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c"
+            .to_string(),
+        derivation_path: Some(format!("m/84'/{}'/0'/0/0", coin_type).parse().unwrap()),
+        xpub: Some("xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V".to_string()),
+        uncompressed_public_key: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Network::ETHEREUM).unwrap(),
+            "\"ethereum\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Network>("\"bitcoin\"").unwrap(),
+            Network::BITCOIN
+        );
+        // Networks used to be serialized by name.
+        assert_eq!(
+            serde_json::from_str::<Network>("\"Ethereum\"").unwrap(),
+            Network::ETHEREUM
+        );
+        assert!(serde_json::from_str::<Network>("\"dogecoin\"").is_err());
+
+        let polygon: Network = "polygon".parse().unwrap();
+        assert_eq!(polygon.chain_id(), Some(137));
+        assert_eq!(polygon.get_info().ledger_app, "Ethereum");
+        assert!(polygon.sharing_accounts().any(|n| n == Network::ETHEREUM));
+        assert!(
+            Network::BITCOIN
+                .sharing_accounts()
+                .eq([Network::BITCOIN].into_iter())
+        );
+    }
+
+    #[test]
+    fn test_parse_extended_pubkey() {
+        // Account `m/84'/0'/0'` of BIP84 test vector.
+        let xpub = parse_extended_pubkey("xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V").unwrap();
+        let zpub = parse_extended_pubkey("zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs").unwrap();
+        assert_eq!(xpub, zpub);
+        assert_eq!(xpub.network, bitcoin::Network::Bitcoin);
+
+        let tpub = ExtendedPubKey {
+            network: bitcoin::Network::Testnet,
+            ..xpub
+        };
+        let mut vpub = tpub.encode();
+        vpub[..4].copy_from_slice(&[0x04, 0x5F, 0x1C, 0xF6]);
+        let vpub = base58::encode_check(&vpub);
+        assert!(vpub.starts_with("vpub"));
+        assert_eq!(parse_extended_pubkey(&vpub).unwrap(), tpub);
+        assert_eq!(parse_extended_pubkey(&tpub.to_string()).unwrap(), tpub);
+
+        assert!(parse_extended_pubkey("vpub").is_err());
+    }
+
+    #[test]
+    fn test_derivation_scheme_path() {
+        let scheme = DerivationScheme::new("Ledger Live", "m/44'/60'/n'/0/0");
+        let path = scheme.path(3).unwrap();
+
+        assert_eq!(path.to_string(), "m/44'/60'/3'/0/0");
+        assert_eq!(
+            path.components(),
+            &[
+                44 | HARDENED_INDEX,
+                60 | HARDENED_INDEX,
+                3 | HARDENED_INDEX,
+                0,
+                0
+            ]
+        );
+
+        let single_account = DerivationScheme::new("Custom", "m/44'/60'/1'/0/7");
+        assert!(single_account.path(0).is_some());
+        assert!(single_account.path(1).is_none());
+    }
+
+    #[test]
+    fn test_bitcoin_address_derivation() {
+        let account = bip84_test_account(0);
+        let address = |chain, index| {
+            let address_account = account.derive_address_account(chain, index).unwrap();
+            (
+                address_account
+                    .derivation_path
+                    .as_ref()
+                    .unwrap()
+                    .to_string(),
+                address_account.receive_address(Network::BITCOIN),
+            )
+        };
+
+        assert_eq!(
+            account.receive_address(Network::BITCOIN),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            account.receive_address("bitcoin-regtest".parse().unwrap()),
+            "bcrt1qcr8te4kr609gcawutmrza0j4xv80jy8zeqchgx"
+        );
+        assert_eq!(
+            address(AddressChain::Receive, 1),
+            (
+                "m/84'/0'/0'/0/1".to_string(),
+                "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g".to_string()
+            )
+        );
+        assert_eq!(
+            address(AddressChain::Change, 0),
+            (
+                "m/84'/0'/0'/1/0".to_string(),
+                "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el".to_string()
+            )
+        );
+    }
+}
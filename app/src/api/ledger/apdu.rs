@@ -0,0 +1,305 @@
+//! Commands sent to ledger device and decoding of its responses. Commands of every app
+//! are built from their typed parameters here instead of assembling bytes inline, and
+//! responses are read with [`Reader`] so malformed ones produce errors instead of panics.
+//! Decoders are tested with random responses generated by proptest and fuzzed by the
+//! targets in `fuzz/`.
+
+use ledger_apdu::{APDUAnswer, APDUCommand};
+
+use super::{LedgerError, error::check_response_status, transport::Transport};
+
+/// Maximum length of the command data, longer data is split between several commands.
+pub const MAX_DATA_LENGTH: usize = 255;
+
+const CLA_DASHBOARD: u8 = 0xB0;
+const CLA_BOLOS: u8 = 0xE0;
+
+const INS_GET_RUNNING_APP: u8 = 0x01;
+const INS_QUIT_APP: u8 = 0xA7;
+const INS_GET_DASHBOARD_INFO: u8 = 0x01;
+const INS_OPEN_APP: u8 = 0xD8;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Command {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    pub data: Vec<u8>,
+}
+
+/// Position of the chunk among the commands data is split between.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Chunk {
+    pub first: bool,
+    pub last: bool,
+}
+
+impl Command {
+    pub fn new(cla: u8, ins: u8) -> Self {
+        Self {
+            cla,
+            ins,
+            p1: 0x00,
+            p2: 0x00,
+            data: vec![],
+        }
+    }
+
+    pub fn p1(self, p1: u8) -> Self {
+        Self { p1, ..self }
+    }
+
+    pub fn p2(self, p2: u8) -> Self {
+        Self { p2, ..self }
+    }
+
+    /// Panics if data doesn't fit into a single command, use [`Command::chunked`]
+    /// for data of arbitrary length.
+    pub fn data(self, data: Vec<u8>) -> Self {
+        assert!(
+            data.len() <= MAX_DATA_LENGTH,
+            "APDU data of {} bytes doesn't fit into a single command",
+            data.len()
+        );
+
+        Self { data, ..self }
+    }
+
+    /// Fails with `LedgerError::InvalidData` if data doesn't fit into a single command,
+    /// used when data comes from the user.
+    pub fn try_data(self, data: Vec<u8>) -> Result<Self, LedgerError> {
+        if data.len() > MAX_DATA_LENGTH {
+            log::error!(
+                "APDU data of {} bytes doesn't fit into a single command",
+                data.len()
+            );
+            return Err(LedgerError::InvalidData);
+        }
+
+        Ok(Self { data, ..self })
+    }
+
+    /// Splits data between commands, `params` returns P1 and P2 of the command
+    /// depending on the position of its chunk. Empty data is sent in a single command.
+    pub fn chunked(cla: u8, ins: u8, data: &[u8], params: impl Fn(Chunk) -> (u8, u8)) -> Vec<Self> {
+        let chunks: Vec<_> = if data.is_empty() {
+            vec![data]
+        } else {
+            data.chunks(MAX_DATA_LENGTH).collect()
+        };
+        let chunk_count = chunks.len();
+
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(idx, chunk)| {
+                let (p1, p2) = params(Chunk {
+                    first: idx == 0,
+                    last: idx + 1 == chunk_count,
+                });
+
+                Self::new(cla, ins).p1(p1).p2(p2).data(chunk.to_vec())
+            })
+            .collect()
+    }
+
+    /// Sends command and returns the answer as is, status word is not checked.
+    pub fn exchange(&self, transport: &dyn Transport) -> Result<APDUAnswer<Vec<u8>>, LedgerError> {
+        let command = APDUCommand {
+            cla: self.cla,
+            ins: self.ins,
+            p1: self.p1,
+            p2: self.p2,
+            data: &self.data[..],
+        };
+
+        Ok(transport.exchange(&command)?)
+    }
+
+    /// Sends command and returns response data if device reported success.
+    pub fn send(&self, transport: &dyn Transport) -> Result<Vec<u8>, LedgerError> {
+        let response = self.exchange(transport)?;
+        check_response_status(response.retcode(), response.data())
+    }
+}
+
+/// Sends commands one by one and returns response to the last one.
+pub fn send_all(commands: &[Command], transport: &dyn Transport) -> Result<Vec<u8>, LedgerError> {
+    let mut response = vec![];
+    for command in commands {
+        response = command.send(transport)?;
+    }
+
+    Ok(response)
+}
+
+/// Reads fields of the device response one by one. Every read fails with
+/// `LedgerError::InvalidResponse` if response is shorter than expected.
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, LedgerError> {
+        let [byte] = self.array()?;
+        Ok(byte)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, LedgerError> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], LedgerError> {
+        let (array, data) = self.data.split_first_chunk().ok_or_else(too_short)?;
+        self.data = data;
+
+        Ok(*array)
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], LedgerError> {
+        let (bytes, data) = self.data.split_at_checked(length).ok_or_else(too_short)?;
+        self.data = data;
+
+        Ok(bytes)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Reads all the remaining bytes.
+    pub fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    /// Reads bytes prefixed with their length(1 byte).
+    pub fn length_prefixed(&mut self) -> Result<&'a [u8], LedgerError> {
+        let length = self.u8()?;
+        self.bytes(length as usize)
+    }
+
+    /// Reads UTF-8 string prefixed with its length(1 byte).
+    pub fn string(&mut self) -> Result<String, LedgerError> {
+        let string = self.length_prefixed()?;
+        decode_string(string)
+    }
+}
+
+fn too_short() -> LedgerError {
+    log::error!("Response received from ledger device is too short");
+    LedgerError::InvalidResponse
+}
+
+pub fn decode_string(data: &[u8]) -> Result<String, LedgerError> {
+    String::from_utf8(data.to_vec()).map_err(|_| {
+        log::error!("Invalid string received from ledger device");
+        LedgerError::InvalidResponse
+    })
+}
+
+/// Returns name and version of the app currently open on the device.
+pub fn get_running_app() -> Command {
+    Command::new(CLA_DASHBOARD, INS_GET_RUNNING_APP)
+}
+
+/// Response has a form of `<format(1 byte)> <name length(1 byte)> <name>
+/// <version length(1 byte)> <version> ...`.
+pub fn decode_running_app(response: &[u8]) -> Result<(String, String), LedgerError> {
+    let mut reader = Reader::new(response);
+    let _format = reader.u8()?;
+
+    Ok((reader.string()?, reader.string()?))
+}
+
+/// Returns target id and firmware version. Supported only by the dashboard.
+pub fn get_dashboard_info() -> Command {
+    Command::new(CLA_BOLOS, INS_GET_DASHBOARD_INFO)
+}
+
+/// Response has a form of `<target id(4 bytes)> <version length(1 byte)> <version> ...`.
+pub fn decode_dashboard_info(response: &[u8]) -> Result<(u32, String), LedgerError> {
+    let mut reader = Reader::new(response);
+
+    Ok((reader.u32()?, reader.string()?))
+}
+
+/// Closes the app open on the device and returns to the dashboard.
+pub fn quit_app() -> Command {
+    Command::new(CLA_DASHBOARD, INS_QUIT_APP)
+}
+
+/// Opens the app with given name. Supported only by the dashboard.
+pub fn open_app(app_name: &str) -> Command {
+    Command::new(CLA_BOLOS, INS_OPEN_APP).data(app_name.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_dashboard_responses_decoding() {
+        let response = [&[0x01, 0x05][..], b"BOLOS", &[0x05], b"2.2.3"].concat();
+        assert_eq!(
+            decode_running_app(&response),
+            Ok(("BOLOS".to_string(), "2.2.3".to_string()))
+        );
+
+        for length in 0..response.len() {
+            assert_eq!(
+                decode_running_app(&response[..length]),
+                Err(LedgerError::InvalidResponse)
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_chunked_commands(data in proptest::collection::vec(any::<u8>(), 0..1024)) {
+            let commands = Command::chunked(0xE0, 0x04, &data, |chunk| {
+                (chunk.first as u8, chunk.last as u8)
+            });
+
+            let sent: Vec<_> = commands.iter().flat_map(|command| command.data.clone()).collect();
+            prop_assert_eq!(sent, data.clone());
+            prop_assert_eq!(commands.len(), data.len().div_ceil(MAX_DATA_LENGTH).max(1));
+            for (idx, command) in commands.iter().enumerate() {
+                prop_assert!(command.data.len() <= MAX_DATA_LENGTH);
+                prop_assert_eq!(command.p1, (idx == 0) as u8);
+                prop_assert_eq!(command.p2, (idx + 1 == commands.len()) as u8);
+            }
+        }
+
+        #[test]
+        fn test_arbitrary_dashboard_responses(response in proptest::collection::vec(any::<u8>(), 0..64)) {
+            let _ = decode_running_app(&response);
+            let _ = decode_dashboard_info(&response);
+        }
+
+        #[test]
+        fn test_reader_never_reads_past_the_end(
+            response in proptest::collection::vec(any::<u8>(), 0..64),
+            reads in proptest::collection::vec(0..40usize, 0..8),
+        ) {
+            let mut reader = Reader::new(&response);
+            let mut read = 0;
+            for length in reads {
+                match reader.bytes(length) {
+                    Ok(bytes) => read += bytes.len(),
+                    Err(e) => {
+                        prop_assert_eq!(e, LedgerError::InvalidResponse);
+                        prop_assert!(read + length > response.len());
+                    }
+                }
+            }
+            prop_assert_eq!(reader.rest().len(), response.len() - read);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Commands of bitcoin app(v2), see
+//! [specification](https://github.com/LedgerHQ/app-bitcoin-new/blob/develop/doc/bitcoin.md).
+
+use bitcoin::{
+    PublicKey, VarInt,
+    bip32::{ExtendedPubKey, Fingerprint},
+    consensus::encode::{deserialize_partial, serialize},
+    ecdsa, taproot,
+};
+
+use crate::api::common_types::{DerivationPath, parse_extended_pubkey};
+
+use super::{
+    super::{
+        LedgerError,
+        apdu::{Command, Reader, decode_string},
+    },
+    merkle::Hash,
+};
+
+const CLA_BITCOIN: u8 = 0xE1;
+const CLA_FRAMEWORK: u8 = 0xF8;
+
+const INS_GET_EXTENDED_PUBKEY: u8 = 0x00;
+const INS_GET_WALLET_ADDRESS: u8 = 0x03;
+const INS_SIGN_PSBT: u8 = 0x04;
+const INS_GET_MASTER_FINGERPRINT: u8 = 0x05;
+const INS_CONTINUE_INTERRUPTED: u8 = 0x01;
+
+/// Version of the protocol supported by bitcoin app since v2.1.0.
+const PROTOCOL_VERSION: u8 = 0x01;
+
+const TAPROOT_PUBLIC_KEY_LENGTH: usize = 32;
+
+/// Wallet policy registered on the device, default ones have zero HMAC.
+pub struct Wallet {
+    pub id: Hash,
+    pub hmac: Hash,
+}
+
+/// Merkle tree of the commitments to PSBT input or output maps.
+pub struct Commitments {
+    pub count: usize,
+    pub root: Hash,
+}
+
+pub fn get_extended_pubkey(path: &DerivationPath, display: bool) -> Command {
+    let data = [
+        &[display as u8][..],
+        // Number of BIP 32 derivations to perform (max 8) followed by derivation indices
+        &path.encode(),
+    ]
+    .concat();
+
+    Command::new(CLA_BITCOIN, INS_GET_EXTENDED_PUBKEY).data(data)
+}
+
+/// Response is a base58 encoded extended public key, `tpub` for test networks.
+pub fn decode_extended_pubkey(response: &[u8]) -> Result<ExtendedPubKey, LedgerError> {
+    let xpub = decode_string(response)?;
+
+    parse_extended_pubkey(&xpub).map_err(|e| {
+        log::error!(
+            "Invalid extended public key received from ledger device: {}",
+            e
+        );
+        LedgerError::InvalidResponse
+    })
+}
+
+pub fn get_master_fingerprint() -> Command {
+    Command::new(CLA_BITCOIN, INS_GET_MASTER_FINGERPRINT)
+}
+
+pub fn decode_master_fingerprint(response: &[u8]) -> Result<Fingerprint, LedgerError> {
+    let mut reader = Reader::new(response);
+    let fingerprint = reader.array::<4>()?;
+
+    Ok(Fingerprint::from(fingerprint))
+}
+
+pub fn get_wallet_address(
+    wallet: &Wallet,
+    change: bool,
+    address_index: u32,
+    display: bool,
+) -> Command {
+    let data = [
+        &[display as u8][..],
+        &wallet.id,
+        &wallet.hmac,
+        &[change as u8],
+        &address_index.to_be_bytes(),
+    ]
+    .concat();
+
+    Command::new(CLA_BITCOIN, INS_GET_WALLET_ADDRESS)
+        .p2(PROTOCOL_VERSION)
+        .data(data)
+}
+
+/// Response is an address in the form of string.
+pub fn decode_wallet_address(response: &[u8]) -> Result<String, LedgerError> {
+    decode_string(response)
+}
+
+pub fn sign_psbt(
+    global_map_commitment: &[u8],
+    inputs: &Commitments,
+    outputs: &Commitments,
+    wallet: &Wallet,
+) -> Command {
+    let data = [
+        global_map_commitment,
+        &serialize(&VarInt(inputs.count as u64)),
+        &inputs.root,
+        &serialize(&VarInt(outputs.count as u64)),
+        &outputs.root,
+        &wallet.id,
+        &wallet.hmac,
+    ]
+    .concat();
+
+    Command::new(CLA_BITCOIN, INS_SIGN_PSBT)
+        .p2(PROTOCOL_VERSION)
+        .data(data)
+}
+
+/// Answers client command of the device that interrupted processing of the previous command.
+pub fn continue_interrupted(client_response: Vec<u8>) -> Result<Command, LedgerError> {
+    Command::new(CLA_FRAMEWORK, INS_CONTINUE_INTERRUPTED).try_data(client_response)
+}
+
+pub struct PartialSignature {
+    pub input_index: usize,
+    pub signature: InputSignature,
+}
+
+pub enum InputSignature {
+    Ecdsa {
+        public_key: PublicKey,
+        signature: ecdsa::Signature,
+    },
+    TaprootKeySpend(taproot::Signature),
+}
+
+/// Decodes signature yielded by device while signing PSBT, which has the form of
+/// `<input index(varint)> <public key length(1 byte)> <public key> <signature>`.
+/// Public key is x-only for taproot signatures.
+pub fn decode_partial_signature(element: &[u8]) -> Result<PartialSignature, LedgerError> {
+    let (VarInt(input_index), read) = deserialize_partial(element).map_err(|_| {
+        log::error!("Invalid input index received from ledger device");
+        LedgerError::InvalidResponse
+    })?;
+
+    let mut reader = Reader::new(&element[read..]);
+    let public_key = reader.length_prefixed()?;
+    let signature = reader.rest();
+
+    let signature = match public_key.len() {
+        TAPROOT_PUBLIC_KEY_LENGTH => {
+            let signature = taproot::Signature::from_slice(signature).map_err(|e| {
+                log::error!("Invalid signature received from ledger device: {}", e);
+                LedgerError::InvalidResponse
+            })?;
+
+            InputSignature::TaprootKeySpend(signature)
+        }
+        _ => {
+            let public_key = PublicKey::from_slice(public_key).map_err(|e| {
+                log::error!("Invalid public key received from ledger device: {}", e);
+                LedgerError::InvalidResponse
+            })?;
+            let signature = ecdsa::Signature::from_slice(signature).map_err(|e| {
+                log::error!("Invalid signature received from ledger device: {}", e);
+                LedgerError::InvalidResponse
+            })?;
+
+            InputSignature::Ecdsa {
+                public_key,
+                signature,
+            }
+        }
+    };
+
+    Ok(PartialSignature {
+        input_index: input_index as usize,
+        signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_arbitrary_responses(response in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = decode_extended_pubkey(&response);
+            let _ = decode_master_fingerprint(&response);
+            let _ = decode_wallet_address(&response);
+            let _ = decode_partial_signature(&response);
+        }
+
+        #[test]
+        fn test_master_fingerprint_decoding(response in proptest::collection::vec(any::<u8>(), 0..8)) {
+            let fingerprint = decode_master_fingerprint(&response);
+            if response.len() < 4 {
+                prop_assert_eq!(fingerprint.err(), Some(LedgerError::InvalidResponse));
+            } else {
+                prop_assert_eq!(&fingerprint.unwrap()[..], &response[..4]);
+            }
+        }
+    }
+}
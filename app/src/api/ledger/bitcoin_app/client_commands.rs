@@ -0,0 +1,276 @@
+//! Interpreter of the commands that ledger bitcoin app(v2) sends back to the client while
+//! processing a request, see
+//! [specification](https://github.com/LedgerHQ/app-bitcoin-new/blob/develop/doc/bitcoin.md#client-commands).
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use bitcoin::{
+    VarInt,
+    consensus::encode::{deserialize_partial, serialize},
+};
+
+use super::merkle::{Hash, MerkleTree, sha256};
+
+const YIELD: u8 = 0x10;
+const GET_PREIMAGE: u8 = 0x40;
+const GET_MERKLE_LEAF_PROOF: u8 = 0x41;
+const GET_MERKLE_LEAF_INDEX: u8 = 0x42;
+const GET_MORE_ELEMENTS: u8 = 0xA0;
+
+pub const MAX_RESPONSE_LENGTH: usize = 255;
+const MAX_MORE_ELEMENTS_LENGTH: usize = 253;
+
+#[derive(Default)]
+pub struct ClientCommandInterpreter {
+    known_preimages: HashMap<Hash, Vec<u8>>,
+    known_trees: HashMap<Hash, MerkleTree>,
+    queue: VecDeque<Vec<u8>>,
+    yielded: Vec<Vec<u8>>,
+}
+
+type CommandResult = Result<Vec<u8>, ()>;
+
+impl ClientCommandInterpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_known_preimage(&mut self, preimage: Vec<u8>) {
+        self.known_preimages.insert(sha256(&preimage), preimage);
+    }
+
+    /// Makes elements of list and merkle tree built on them available for the device.
+    pub fn add_known_list<E: AsRef<[u8]>>(&mut self, elements: &[E]) {
+        for element in elements {
+            self.add_known_preimage([&[0x00][..], element.as_ref()].concat());
+        }
+
+        let tree = MerkleTree::from_elements(elements);
+        self.known_trees.insert(tree.root(), tree);
+    }
+
+    pub fn add_known_mapping(&mut self, map: &BTreeMap<Vec<u8>, Vec<u8>>) {
+        let keys: Vec<_> = map.keys().collect();
+        let values: Vec<_> = map.values().collect();
+
+        self.add_known_list(&keys);
+        self.add_known_list(&values);
+    }
+
+    /// Elements that were sent by the device using `YIELD` command.
+    pub fn yielded(&self) -> &[Vec<u8>] {
+        &self.yielded
+    }
+
+    pub fn execute(&mut self, request: &[u8]) -> CommandResult {
+        let Some((&command, data)) = request.split_first() else {
+            log::error!("Received empty client command from ledger device");
+            return Err(());
+        };
+
+        match command {
+            YIELD => {
+                self.yielded.push(data.to_vec());
+                Ok(vec![])
+            }
+            GET_PREIMAGE => self.get_preimage(data),
+            GET_MERKLE_LEAF_PROOF => self.get_merkle_leaf_proof(data),
+            GET_MERKLE_LEAF_INDEX => self.get_merkle_leaf_index(data),
+            GET_MORE_ELEMENTS => self.get_more_elements(),
+            command => {
+                log::error!("Unknown client command received: {:#04x}", command);
+                Err(())
+            }
+        }
+    }
+
+    fn get_preimage(&mut self, data: &[u8]) -> CommandResult {
+        let (&[0x00], hash) = data.split_at_checked(1).ok_or(())? else {
+            log::error!("Unsupported GET_PREIMAGE request");
+            return Err(());
+        };
+        let hash: Hash = hash.try_into().map_err(|_| ())?;
+
+        let Some(preimage) = self.known_preimages.get(&hash) else {
+            log::error!("Requested unknown preimage of {}", hex::encode(hash));
+            return Err(());
+        };
+
+        let preimage_length = serialize(&VarInt(preimage.len() as u64));
+        let max_payload_length = MAX_RESPONSE_LENGTH - preimage_length.len() - 1;
+        let payload_length = max_payload_length.min(preimage.len());
+
+        let (payload, remainder) = preimage.split_at(payload_length);
+
+        if !remainder.is_empty() {
+            if !self.queue.is_empty() {
+                log::error!("Client command queue is expected to be empty");
+                return Err(());
+            }
+
+            self.queue.extend(remainder.iter().map(|&byte| vec![byte]));
+        }
+
+        Ok([&preimage_length[..], &[payload_length as u8], payload].concat())
+    }
+
+    fn get_merkle_leaf_proof(&mut self, data: &[u8]) -> CommandResult {
+        let (root, data) = data.split_at_checked(32).ok_or(())?;
+        let root: Hash = root.try_into().map_err(|_| ())?;
+        let (VarInt(tree_size), read) = deserialize_partial(data).map_err(|_| ())?;
+        let (VarInt(leaf_index), _) = deserialize_partial(&data[read..]).map_err(|_| ())?;
+
+        let Some(tree) = self.known_trees.get(&root) else {
+            log::error!(
+                "Requested proof from unknown merkle tree {}",
+                hex::encode(root)
+            );
+            return Err(());
+        };
+
+        if tree.size() as u64 != tree_size {
+            log::error!("Invalid merkle tree size requested");
+            return Err(());
+        }
+
+        let leaf = *tree.leaf(leaf_index as usize).ok_or(())?;
+        let proof = tree.prove_leaf(leaf_index as usize).ok_or(())?;
+
+        // Leaf hash, proof length and number of proof elements in this response.
+        const MAX_PROOF_ELEMENTS: usize = (MAX_RESPONSE_LENGTH - 32 - 1 - 1) / 32;
+        let proof_elements = proof.len().min(MAX_PROOF_ELEMENTS);
+
+        if !self.queue.is_empty() {
+            log::error!("Client command queue is expected to be empty");
+            return Err(());
+        }
+
+        self.queue
+            .extend(proof[proof_elements..].iter().map(|hash| hash.to_vec()));
+
+        Ok([
+            &leaf[..],
+            &[proof.len() as u8, proof_elements as u8],
+            &proof[..proof_elements].concat(),
+        ]
+        .concat())
+    }
+
+    fn get_merkle_leaf_index(&mut self, data: &[u8]) -> CommandResult {
+        let (root, leaf) = data.split_at_checked(32).ok_or(())?;
+        let root: Hash = root.try_into().map_err(|_| ())?;
+        let leaf: Hash = leaf.try_into().map_err(|_| ())?;
+
+        let Some(tree) = self.known_trees.get(&root) else {
+            log::error!(
+                "Requested leaf of unknown merkle tree {}",
+                hex::encode(root)
+            );
+            return Err(());
+        };
+
+        let (found, index) = match tree.leaf_index(&leaf) {
+            Some(index) => (1u8, index),
+            None => (0u8, 0),
+        };
+
+        Ok([&[found][..], &serialize(&VarInt(index as u64))].concat())
+    }
+
+    fn get_more_elements(&mut self) -> CommandResult {
+        let Some(element_length) = self.queue.front().map(Vec::len) else {
+            log::error!("GET_MORE_ELEMENTS requested with empty queue");
+            return Err(());
+        };
+
+        let mut elements = vec![];
+        while let Some(element) = self.queue.front() {
+            let fits = (elements.len() + 1) * element_length <= MAX_MORE_ELEMENTS_LENGTH;
+            if element.len() != element_length || !fits {
+                break;
+            }
+
+            elements.push(self.queue.pop_front().expect("Checked to be non-empty"));
+        }
+
+        Ok([
+            &[elements.len() as u8, element_length as u8][..],
+            &elements.concat(),
+        ]
+        .concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_get_long_preimage() {
+        let preimage: Vec<u8> = (0..=255u8).cycle().take(600).collect();
+        let mut interpreter = ClientCommandInterpreter::new();
+        interpreter.add_known_preimage(preimage.clone());
+
+        let request = [&[GET_PREIMAGE, 0x00][..], &sha256(&preimage)].concat();
+        let response = interpreter.execute(&request).unwrap();
+
+        // 600 is encoded as 3-byte varint.
+        assert_eq!(&response[..3], &[0xFD, 0x58, 0x02]);
+        let payload_length = response[3] as usize;
+        assert_eq!(payload_length, 255 - 3 - 1);
+
+        let mut received = response[4..].to_vec();
+        while received.len() < preimage.len() {
+            let response = interpreter.execute(&[GET_MORE_ELEMENTS]).unwrap();
+            let (count, length) = (response[0] as usize, response[1] as usize);
+            assert_eq!(length, 1);
+            assert_eq!(response.len(), 2 + count);
+            received.extend_from_slice(&response[2..]);
+        }
+
+        assert_eq!(received, preimage);
+        assert!(interpreter.execute(&[GET_MORE_ELEMENTS]).is_err());
+    }
+
+    proptest! {
+        /// Requests are made of a known command and tree root followed by arbitrary bytes,
+        /// so both valid and malformed ones reach the command handlers.
+        #[test]
+        fn test_arbitrary_client_commands(
+            elements in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..300), 1..20),
+            requests in proptest::collection::vec(
+                (
+                    proptest::sample::select(vec![
+                        YIELD,
+                        GET_PREIMAGE,
+                        GET_MERKLE_LEAF_PROOF,
+                        GET_MERKLE_LEAF_INDEX,
+                        GET_MORE_ELEMENTS,
+                    ]),
+                    any::<bool>(),
+                    proptest::collection::vec(any::<u8>(), 0..80),
+                ),
+                0..20,
+            ),
+        ) {
+            let mut interpreter = ClientCommandInterpreter::new();
+            interpreter.add_known_list(&elements);
+            let root = MerkleTree::from_elements(&elements).root();
+
+            for (command, with_root, data) in requests {
+                let request = [
+                    &[command][..],
+                    if with_root { &root[..] } else { &[] },
+                    &data,
+                ]
+                .concat();
+
+                if let Ok(response) = interpreter.execute(&request) {
+                    prop_assert!(response.len() <= MAX_RESPONSE_LENGTH);
+                }
+            }
+        }
+    }
+}
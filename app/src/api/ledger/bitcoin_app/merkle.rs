@@ -0,0 +1,167 @@
+//! Merkle trees as defined in the protocol of the ledger bitcoin app(v2), see
+//! [specification](https://github.com/LedgerHQ/app-bitcoin-new/blob/develop/doc/merkle.md).
+
+use std::collections::BTreeMap;
+
+use bitcoin::{
+    VarInt,
+    consensus::encode::serialize,
+    hashes::{Hash as _, sha256},
+};
+
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub fn sha256(data: &[u8]) -> Hash {
+    sha256::Hash::hash(data).to_byte_array()
+}
+
+/// Hash of a list element, that is used as a leaf of merkle tree.
+pub fn element_hash(element: &[u8]) -> Hash {
+    sha256(&[&[LEAF_PREFIX][..], element].concat())
+}
+
+fn combine_hashes(left: &Hash, right: &Hash) -> Hash {
+    sha256(&[&[NODE_PREFIX][..], left, right].concat())
+}
+
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    leaves: Vec<Hash>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<Hash>) -> Self {
+        Self { leaves }
+    }
+
+    pub fn from_elements<E: AsRef<[u8]>>(elements: &[E]) -> Self {
+        Self::new(
+            elements
+                .iter()
+                .map(|element| element_hash(element.as_ref()))
+                .collect(),
+        )
+    }
+
+    pub fn root(&self) -> Hash {
+        subtree_root(&self.leaves)
+    }
+
+    pub fn size(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<&Hash> {
+        self.leaves.get(index)
+    }
+
+    pub fn leaf_index(&self, leaf: &Hash) -> Option<usize> {
+        self.leaves.iter().position(|l| l == leaf)
+    }
+
+    /// Hashes of the siblings of all the nodes on the path from leaf to the root, ordered
+    /// from the bottom to the top.
+    pub fn prove_leaf(&self, index: usize) -> Option<Vec<Hash>> {
+        (index < self.leaves.len()).then(|| subtree_proof(&self.leaves, index))
+    }
+}
+
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        0 => [0; 32],
+        1 => leaves[0],
+        len => {
+            let (left, right) = leaves.split_at(largest_power_of_2_less_than(len));
+            combine_hashes(&subtree_root(left), &subtree_root(right))
+        }
+    }
+}
+
+fn subtree_proof(leaves: &[Hash], index: usize) -> Vec<Hash> {
+    if leaves.len() <= 1 {
+        return vec![];
+    }
+
+    let split = largest_power_of_2_less_than(leaves.len());
+    let (left, right) = leaves.split_at(split);
+
+    if index < split {
+        let mut proof = subtree_proof(left, index);
+        proof.push(subtree_root(right));
+        proof
+    } else {
+        let mut proof = subtree_proof(right, index - split);
+        proof.push(subtree_root(left));
+        proof
+    }
+}
+
+fn largest_power_of_2_less_than(n: usize) -> usize {
+    assert!(n > 1);
+
+    if n.is_power_of_two() {
+        n / 2
+    } else {
+        1 << n.ilog2()
+    }
+}
+
+/// Commitment to a key-value map: number of entries followed by the merkle roots
+/// of lexicographically sorted keys and of the corresponding values.
+pub fn merkleized_map_commitment(map: &BTreeMap<Vec<u8>, Vec<u8>>) -> Vec<u8> {
+    let keys: Vec<_> = map.keys().collect();
+    let values: Vec<_> = map.values().collect();
+
+    [
+        serialize(&VarInt(map.len() as u64)),
+        MerkleTree::from_elements(&keys).root().to_vec(),
+        MerkleTree::from_elements(&values).root().to_vec(),
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify_proof(leaf: Hash, index: usize, size: usize, proof: &[Hash]) -> Hash {
+        if size <= 1 {
+            return leaf;
+        }
+
+        let split = largest_power_of_2_less_than(size);
+        let (sibling, rest) = proof.split_last().expect("Proof is too short");
+
+        if index < split {
+            combine_hashes(&verify_proof(leaf, index, split, rest), sibling)
+        } else {
+            combine_hashes(
+                sibling,
+                &verify_proof(leaf, index - split, size - split, rest),
+            )
+        }
+    }
+
+    #[test]
+    fn test_merkle_proofs() {
+        for size in 1..=33 {
+            let elements: Vec<_> = (0..size).map(|i: u32| i.to_le_bytes()).collect();
+            let tree = MerkleTree::from_elements(&elements);
+
+            for index in 0..size as usize {
+                let proof = tree.prove_leaf(index).unwrap();
+                let leaf = *tree.leaf(index).unwrap();
+
+                assert_eq!(
+                    verify_proof(leaf, index, size as usize, &proof),
+                    tree.root()
+                );
+            }
+
+            assert!(tree.prove_leaf(size as usize).is_none());
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use bitcoin::{
+    bip32::{ChildNumber, ExtendedPubKey, Fingerprint},
+    psbt::Psbt,
+    secp256k1::Secp256k1,
+};
+
+use crate::api::common_types::{ACCOUNT_PATH_DEPTH, Account, DerivationPath, HARDENED_INDEX};
+
+use super::{
+    Device, LedgerError, apdu::Command, error::check_response_status, transport::Transport,
+};
+
+mod apdu;
+mod client_commands;
+mod merkle;
+mod psbt;
+mod wallet_policy;
+
+use apdu::{InputSignature, PartialSignature};
+use client_commands::ClientCommandInterpreter;
+use merkle::{MerkleTree, merkleized_map_commitment};
+use psbt::PsbtV2Maps;
+use wallet_policy::WalletPolicy;
+
+#[cfg(fuzzing)]
+pub mod fuzzing {
+    use super::{
+        apdu,
+        client_commands::{ClientCommandInterpreter, MAX_RESPONSE_LENGTH},
+        merkle::MerkleTree,
+    };
+
+    /// Decodes the data as every kind of device response.
+    pub fn decode(data: &[u8]) {
+        let _ = apdu::decode_extended_pubkey(data);
+        let _ = apdu::decode_master_fingerprint(data);
+        let _ = apdu::decode_wallet_address(data);
+        let _ = apdu::decode_partial_signature(data);
+    }
+
+    /// Serves client commands made of the command byte, root of the merkle tree built
+    /// on `elements` if `with_root` is set and the data, so both valid and malformed ones
+    /// reach the command handlers.
+    pub fn execute_client_commands(elements: &[Vec<u8>], requests: &[(u8, bool, Vec<u8>)]) {
+        let mut interpreter = ClientCommandInterpreter::new();
+        interpreter.add_known_list(elements);
+        let root = MerkleTree::from_elements(elements).root();
+
+        for (command, with_root, data) in requests {
+            let request = [
+                &[*command][..],
+                if *with_root { &root[..] } else { &[] },
+                data,
+            ]
+            .concat();
+
+            if let Ok(response) = interpreter.execute(&request) {
+                assert!(response.len() <= MAX_RESPONSE_LENGTH);
+            }
+        }
+    }
+}
+
+/// Status word returned by device when it needs some data from the client to proceed.
+const SW_INTERRUPTED_EXECUTION: u16 = 0xE000;
+
+/// Default wallet policies don't need to be registered, so they have no HMAC.
+const DEFAULT_WALLET_HMAC: [u8; 32] = [0; 32];
+
+pub async fn get_account(device: &Device, path: &DerivationPath) -> Result<Account, LedgerError> {
+    log::info!("Deriving bitcoin account {}", path);
+
+    let transport = device.open_transport()?;
+
+    // Addresses are derived from the account level xpub, so the rest of the path
+    // is derived locally.
+    let xpub = get_extended_pubkey(transport.as_ref(), &account_path(path))?;
+    let account = account_from_xpub(&xpub, path)?;
+
+    log::info!(
+        "Derived bitcoin account {} with public key = {}",
+        path,
+        account.public_key
+    );
+
+    Ok(account)
+}
+
+/// Account level ancestor of the path, e.g. `m/84'/0'/0'` for `m/84'/0'/0'/0/5`.
+pub fn account_path(path: &DerivationPath) -> DerivationPath {
+    path.ancestor(ACCOUNT_PATH_DEPTH)
+        .unwrap_or_else(|| path.clone())
+}
+
+/// Derives account with the path from xpub of its account level ancestor.
+pub fn account_from_xpub(
+    xpub: &ExtendedPubKey,
+    path: &DerivationPath,
+) -> Result<Account, LedgerError> {
+    let children: Vec<_> = path.components()[account_path(path).components().len()..]
+        .iter()
+        .map(|&child| ChildNumber::from_normal_idx(child))
+        .collect::<Result<_, _>>()
+        .map_err(|_| {
+            log::error!("Derivation path {} has hardened address level", path);
+            LedgerError::UnsupportedDerivationPath
+        })?;
+    let public_key = xpub
+        .derive_pub(&Secp256k1::verification_only(), &children)
+        .map_err(|e| {
+            log::error!("Failed to derive public key {}: {}", path, e);
+            LedgerError::UnsupportedDerivationPath
+        })?
+        .public_key
+        .to_string();
+
+    Ok(Account {
+        public_key,
+        derivation_path: Some(path.clone()),
+        xpub: Some(xpub.to_string()),
+        uncompressed_public_key: None,
+    })
+}
+
+/// Signs all the inputs of a PSBT that belong to the account and returns
+/// the PSBT with partial signatures added. Account is expected to be derived
+/// with a standard path like `m/84'/0'/0'/0/0`. Key origins with zero fingerprint
+/// are treated as the ones of the device master key.
+pub async fn sign_message(
+    message: Vec<u8>,
+    device: &Device,
+    path: &DerivationPath,
+) -> Result<Vec<u8>, LedgerError> {
+    log::info!("Signing bitcoin PSBT");
+
+    let mut psbt = Psbt::deserialize(&message).map_err(|e| {
+        log::error!("Failed to deserialize PSBT: {}", e);
+        LedgerError::InvalidData
+    })?;
+
+    let transport = device.open_transport()?;
+
+    let fingerprint = get_master_fingerprint(transport.as_ref())?;
+    fill_fingerprints(&mut psbt, fingerprint);
+
+    let wallet_policy = get_default_wallet_policy(transport.as_ref(), path, fingerprint)?;
+
+    let signatures = sign_psbt(transport.as_ref(), &psbt, &wallet_policy)?;
+
+    for PartialSignature {
+        input_index,
+        signature,
+    } in signatures
+    {
+        let Some(input) = psbt.inputs.get_mut(input_index) else {
+            log::error!("Signature for non-existent input #{} received", input_index);
+            return Err(LedgerError::InvalidResponse);
+        };
+
+        match signature {
+            InputSignature::Ecdsa {
+                public_key,
+                signature,
+            } => {
+                log::info!(
+                    "Received signature for input #{} with public key {}",
+                    input_index,
+                    public_key
+                );
+
+                input.partial_sigs.insert(public_key, signature);
+            }
+            InputSignature::TaprootKeySpend(signature) => {
+                log::info!("Received taproot signature for input #{}", input_index);
+
+                input.tap_key_sig = Some(signature);
+            }
+        }
+    }
+
+    Ok(psbt.serialize())
+}
+
+/// Shows the address of the account on the device screen and returns it once user
+/// confirms it. Account is expected to be derived with a standard path like `m/84'/0'/0'/0/0`.
+pub async fn display_address(
+    device: &Device,
+    path: &DerivationPath,
+) -> Result<String, LedgerError> {
+    log::info!("Displaying bitcoin address {} on device", path);
+
+    let &[_, _, _, change, address_index] = path.components() else {
+        log::error!("Derivation path {} is not supported", path);
+        return Err(LedgerError::UnsupportedDerivationPath);
+    };
+    if change > 1 || address_index >= HARDENED_INDEX {
+        log::error!("Derivation path {} is not supported", path);
+        return Err(LedgerError::UnsupportedDerivationPath);
+    }
+
+    let transport = device.open_transport()?;
+
+    let fingerprint = get_master_fingerprint(transport.as_ref())?;
+    let wallet_policy = get_default_wallet_policy(transport.as_ref(), path, fingerprint)?;
+
+    let mut interpreter = ClientCommandInterpreter::new();
+    wallet_policy.provide_to(&mut interpreter);
+
+    let command = apdu::get_wallet_address(
+        &default_wallet(&wallet_policy),
+        change == 1,
+        address_index,
+        true,
+    );
+
+    let response = send_command_with_interpreter(&command, transport.as_ref(), &mut interpreter)?;
+
+    apdu::decode_wallet_address(&response)
+}
+
+/// Builds default wallet policy for the account the address with given derivation path
+/// belongs to.
+fn get_default_wallet_policy(
+    transport: &dyn Transport,
+    path: &DerivationPath,
+    fingerprint: Fingerprint,
+) -> Result<WalletPolicy, LedgerError> {
+    let Some(account_path) = path.ancestor(ACCOUNT_PATH_DEPTH) else {
+        log::error!("Derivation path {} is not supported", path);
+        return Err(LedgerError::UnsupportedDerivationPath);
+    };
+
+    let xpub = get_extended_pubkey(transport, &account_path)?;
+
+    // Derivation path is displayed as `m/...`, but key origin has a form of `[fingerprint/...]`.
+    let key_info = format!(
+        "[{}{}]{}",
+        fingerprint,
+        &account_path.to_string()[1..],
+        xpub
+    );
+
+    WalletPolicy::default_single_sig(&account_path, key_info).ok_or_else(|| {
+        log::error!("Derivation path {} is not supported", path);
+        LedgerError::UnsupportedDerivationPath
+    })
+}
+
+/// Blockchain monitoring api doesn't know the master key, so it leaves fingerprints
+/// of key origins zero. Device recognizes its keys by fingerprint, so they're set here.
+fn fill_fingerprints(psbt: &mut Psbt, fingerprint: Fingerprint) {
+    let sources = psbt
+        .inputs
+        .iter_mut()
+        .flat_map(|input| {
+            input
+                .bip32_derivation
+                .values_mut()
+                .chain(input.tap_key_origins.values_mut().map(|(_, source)| source))
+        })
+        .chain(psbt.outputs.iter_mut().flat_map(|output| {
+            output.bip32_derivation.values_mut().chain(
+                output
+                    .tap_key_origins
+                    .values_mut()
+                    .map(|(_, source)| source),
+            )
+        }));
+
+    for (source_fingerprint, _) in sources {
+        if *source_fingerprint == Fingerprint::default() {
+            *source_fingerprint = fingerprint;
+        }
+    }
+}
+
+fn sign_psbt(
+    transport: &dyn Transport,
+    psbt: &Psbt,
+    wallet_policy: &WalletPolicy,
+) -> Result<Vec<PartialSignature>, LedgerError> {
+    let maps = PsbtV2Maps::from_psbt(psbt);
+
+    let mut interpreter = ClientCommandInterpreter::new();
+
+    interpreter.add_known_mapping(&maps.global);
+    for map in maps.inputs.iter().chain(&maps.outputs) {
+        interpreter.add_known_mapping(map);
+    }
+
+    let input_commitments: Vec<_> = maps.inputs.iter().map(merkleized_map_commitment).collect();
+    let output_commitments: Vec<_> = maps.outputs.iter().map(merkleized_map_commitment).collect();
+
+    interpreter.add_known_list(&input_commitments);
+    interpreter.add_known_list(&output_commitments);
+
+    wallet_policy.provide_to(&mut interpreter);
+
+    let commitments = |commitments: &[Vec<u8>]| apdu::Commitments {
+        count: commitments.len(),
+        root: MerkleTree::from_elements(commitments).root(),
+    };
+
+    let command = apdu::sign_psbt(
+        &merkleized_map_commitment(&maps.global),
+        &commitments(&input_commitments),
+        &commitments(&output_commitments),
+        &default_wallet(wallet_policy),
+    );
+
+    send_command_with_interpreter(&command, transport, &mut interpreter)?;
+
+    interpreter
+        .yielded()
+        .iter()
+        .map(|element| apdu::decode_partial_signature(element))
+        .collect()
+}
+
+fn default_wallet(wallet_policy: &WalletPolicy) -> apdu::Wallet {
+    apdu::Wallet {
+        id: wallet_policy.id(),
+        hmac: DEFAULT_WALLET_HMAC,
+    }
+}
+
+fn get_master_fingerprint(transport: &dyn Transport) -> Result<Fingerprint, LedgerError> {
+    let response = apdu::get_master_fingerprint().send(transport)?;
+    apdu::decode_master_fingerprint(&response)
+}
+
+fn get_extended_pubkey(
+    transport: &dyn Transport,
+    path: &DerivationPath,
+) -> Result<ExtendedPubKey, LedgerError> {
+    let response = apdu::get_extended_pubkey(path, false).send(transport)?;
+    apdu::decode_extended_pubkey(&response)
+}
+
+/// Sends a command and serves client commands that the device sends in response
+/// until it finishes processing of the original command.
+fn send_command_with_interpreter(
+    command: &Command,
+    transport: &dyn Transport,
+    interpreter: &mut ClientCommandInterpreter,
+) -> Result<Vec<u8>, LedgerError> {
+    let mut response = command.exchange(transport)?;
+
+    while response.retcode() == SW_INTERRUPTED_EXECUTION {
+        let client_response = interpreter
+            .execute(response.data())
+            .map_err(|_| LedgerError::InvalidResponse)?;
+
+        response = apdu::continue_interrupted(client_response)?.exchange(transport)?;
+    }
+
+    check_response_status(response.retcode(), response.data())
+}
+
+#[tokio::test]
+async fn test_bitcoin_get_account_replay() {
+    let (device, transport) = super::replay_device("bitcoin_get_account");
+    let path = "m/84'/0'/0'/0/0".parse().unwrap();
+
+    let account = get_account(&device, &path).await.unwrap();
+    transport.assert_finished();
+
+    // Keys of the first BIP84 test vector account.
+    assert_eq!(
+        account.public_key,
+        "0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c"
+    );
+    assert_eq!(
+        account.xpub.as_deref(),
+        Some(
+            "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V"
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_bitcoin_sign_psbt_replay() {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use bitcoin::{PublicKey, secp256k1::Message, sighash::SighashCache};
+
+    use crate::api::{
+        blockchain_monitoring::{BlockchainMonitoringApiT, mock::BlockchainMonitoringApiMock},
+        common_types::{Network, bip84_test_account},
+        ledger::SoftwareSigner,
+    };
+
+    let (device, transport) = super::replay_device("bitcoin_sign_psbt");
+    let path = "m/84'/0'/0'/0/0".parse().unwrap();
+
+    let from = bip84_test_account(0);
+    let to = SoftwareSigner::new()
+        .get_account(Network::BITCOIN, &"m/84'/0'/0'/0/1".parse().unwrap())
+        .unwrap();
+    let unsigned = BlockchainMonitoringApiMock::new(0)
+        .prepare_transfer(
+            Network::BITCOIN,
+            &from,
+            &to,
+            BigDecimal::from_str("0.001").unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let signed = sign_message(unsigned.payload, &device, &path)
+        .await
+        .unwrap();
+    transport.assert_finished();
+
+    let psbt = Psbt::deserialize(&signed).unwrap();
+    let input = &psbt.inputs[0];
+    let public_key = PublicKey::from_str(&from.public_key).unwrap();
+    let signature = input.partial_sigs[&public_key];
+
+    let utxo = input.witness_utxo.as_ref().unwrap();
+    let sighash = SighashCache::new(&psbt.unsigned_tx)
+        .segwit_signature_hash(
+            0,
+            &utxo.script_pubkey.p2wpkh_script_code().unwrap(),
+            utxo.value,
+            signature.hash_ty,
+        )
+        .unwrap();
+    Secp256k1::verification_only()
+        .verify_ecdsa(
+            &Message::from_slice(sighash.as_ref()).unwrap(),
+            &signature.sig,
+            &public_key.inner,
+        )
+        .unwrap();
+}
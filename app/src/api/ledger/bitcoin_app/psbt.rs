@@ -0,0 +1,181 @@
+//! Conversion of PSBTs to version 2([BIP-370](https://github.com/bitcoin/bips/blob/master/bip-0370.mediawiki))
+//! key-value maps, which is the only format accepted by ledger bitcoin app(v2).
+
+use std::collections::BTreeMap;
+
+use bitcoin::{
+    VarInt,
+    consensus::encode::{deserialize_partial, serialize},
+    psbt::Psbt,
+};
+
+pub type PsbtMap = BTreeMap<Vec<u8>, Vec<u8>>;
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_TX_VERSION: u8 = 0x02;
+const PSBT_GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const PSBT_GLOBAL_INPUT_COUNT: u8 = 0x04;
+const PSBT_GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const PSBT_GLOBAL_VERSION: u8 = 0xFB;
+
+const PSBT_IN_PREVIOUS_TXID: u8 = 0x0E;
+const PSBT_IN_OUTPUT_INDEX: u8 = 0x0F;
+const PSBT_IN_SEQUENCE: u8 = 0x10;
+
+const PSBT_OUT_AMOUNT: u8 = 0x03;
+const PSBT_OUT_SCRIPT: u8 = 0x04;
+
+pub struct PsbtV2Maps {
+    pub global: PsbtMap,
+    pub inputs: Vec<PsbtMap>,
+    pub outputs: Vec<PsbtMap>,
+}
+
+impl PsbtV2Maps {
+    pub fn from_psbt(psbt: &Psbt) -> Self {
+        let serialized = psbt.serialize();
+        let mut data = serialized
+            .strip_prefix(PSBT_MAGIC)
+            .expect("Serialized PSBT always starts with magic");
+
+        let mut global = read_map(&mut data);
+        let mut inputs: Vec<_> = psbt.inputs.iter().map(|_| read_map(&mut data)).collect();
+        let mut outputs: Vec<_> = psbt.outputs.iter().map(|_| read_map(&mut data)).collect();
+
+        let tx = &psbt.unsigned_tx;
+
+        global.remove(&vec![PSBT_GLOBAL_UNSIGNED_TX]);
+        global.insert(
+            vec![PSBT_GLOBAL_TX_VERSION],
+            tx.version.to_le_bytes().to_vec(),
+        );
+        global.insert(
+            vec![PSBT_GLOBAL_FALLBACK_LOCKTIME],
+            tx.lock_time.to_consensus_u32().to_le_bytes().to_vec(),
+        );
+        global.insert(
+            vec![PSBT_GLOBAL_INPUT_COUNT],
+            serialize(&VarInt(tx.input.len() as u64)),
+        );
+        global.insert(
+            vec![PSBT_GLOBAL_OUTPUT_COUNT],
+            serialize(&VarInt(tx.output.len() as u64)),
+        );
+        global.insert(vec![PSBT_GLOBAL_VERSION], 2u32.to_le_bytes().to_vec());
+
+        for (map, input) in inputs.iter_mut().zip(&tx.input) {
+            map.insert(
+                vec![PSBT_IN_PREVIOUS_TXID],
+                serialize(&input.previous_output.txid),
+            );
+            map.insert(
+                vec![PSBT_IN_OUTPUT_INDEX],
+                input.previous_output.vout.to_le_bytes().to_vec(),
+            );
+            map.insert(
+                vec![PSBT_IN_SEQUENCE],
+                input.sequence.to_consensus_u32().to_le_bytes().to_vec(),
+            );
+        }
+
+        for (map, output) in outputs.iter_mut().zip(&tx.output) {
+            map.insert(vec![PSBT_OUT_AMOUNT], output.value.to_le_bytes().to_vec());
+            map.insert(vec![PSBT_OUT_SCRIPT], output.script_pubkey.to_bytes());
+        }
+
+        Self {
+            global,
+            inputs,
+            outputs,
+        }
+    }
+}
+
+/// Reads a single key-value map from serialized PSBT that is known to be valid.
+fn read_map(data: &mut &[u8]) -> PsbtMap {
+    let mut map = PsbtMap::new();
+
+    loop {
+        let key = read_length_prefixed(data);
+        if key.is_empty() {
+            return map;
+        }
+
+        let value = read_length_prefixed(data);
+        map.insert(key, value);
+    }
+}
+
+fn read_length_prefixed(data: &mut &[u8]) -> Vec<u8> {
+    let (VarInt(length), read) =
+        deserialize_partial(data).expect("Serialized PSBT expected to be valid");
+    let (value, rest) = data[read..].split_at(length as usize);
+    *data = rest;
+
+    value.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{
+        OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness, absolute::LockTime,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_psbt_v2_conversion() {
+        let txid =
+            Txid::from_str("f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126")
+                .unwrap();
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::from_consensus(12345),
+            input: vec![TxIn {
+                previous_output: OutPoint { txid, vout: 3 },
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: 1000,
+                script_pubkey: ScriptBuf::from_bytes(vec![0x00, 0x14, 0xAB]),
+            }],
+        };
+        let psbt = Psbt::from_unsigned_tx(tx).unwrap();
+
+        let maps = PsbtV2Maps::from_psbt(&psbt);
+
+        assert!(!maps.global.contains_key(&vec![PSBT_GLOBAL_UNSIGNED_TX]));
+        assert_eq!(maps.global[&vec![PSBT_GLOBAL_VERSION]], vec![2, 0, 0, 0]);
+        assert_eq!(maps.global[&vec![PSBT_GLOBAL_INPUT_COUNT]], vec![1]);
+        assert_eq!(
+            maps.global[&vec![PSBT_GLOBAL_FALLBACK_LOCKTIME]],
+            12345u32.to_le_bytes()
+        );
+
+        assert_eq!(maps.inputs.len(), 1);
+        assert_eq!(
+            maps.inputs[0][&vec![PSBT_IN_PREVIOUS_TXID]],
+            serialize(&txid)
+        );
+        assert_eq!(
+            maps.inputs[0][&vec![PSBT_IN_OUTPUT_INDEX]],
+            vec![3, 0, 0, 0]
+        );
+
+        assert_eq!(maps.outputs.len(), 1);
+        assert_eq!(
+            maps.outputs[0][&vec![PSBT_OUT_AMOUNT]],
+            1000u64.to_le_bytes()
+        );
+        assert_eq!(
+            maps.outputs[0][&vec![PSBT_OUT_SCRIPT]],
+            vec![0x00, 0x14, 0xAB]
+        );
+    }
+}
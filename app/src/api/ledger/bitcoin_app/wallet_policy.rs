@@ -0,0 +1,67 @@
+//! Wallet policies as defined in
+//! [BIP-388](https://github.com/bitcoin/bips/blob/master/bip-0388.mediawiki) and used by
+//! ledger bitcoin app(v2) to describe accounts.
+
+use bitcoin::{VarInt, consensus::encode::serialize};
+
+use crate::api::common_types::{DerivationPath, HARDENED_INDEX};
+
+use super::{
+    client_commands::ClientCommandInterpreter,
+    merkle::{Hash, MerkleTree, sha256},
+};
+
+const WALLET_POLICY_VERSION: u8 = 0x02;
+
+pub struct WalletPolicy {
+    name: String,
+    descriptor_template: String,
+    keys_info: Vec<String>,
+}
+
+impl WalletPolicy {
+    /// Default single-signature account with the script type defined by the purpose
+    /// of account path(BIP44, BIP49, BIP84 or BIP86). Such policies are not required to be
+    /// registered on the device.
+    pub fn default_single_sig(account_path: &DerivationPath, key_info: String) -> Option<Self> {
+        // Purpose is expected to be hardened, so non-hardened one won't match.
+        let purpose = account_path.components().first()? ^ HARDENED_INDEX;
+        let descriptor_template = match purpose {
+            44 => "pkh(@0/**)",
+            49 => "sh(wpkh(@0/**))",
+            84 => "wpkh(@0/**)",
+            86 => "tr(@0/**)",
+            _ => return None,
+        };
+
+        Some(Self {
+            name: String::new(),
+            descriptor_template: descriptor_template.to_string(),
+            keys_info: vec![key_info],
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        [
+            &[WALLET_POLICY_VERSION][..],
+            &[self.name.len() as u8],
+            self.name.as_bytes(),
+            &serialize(&VarInt(self.descriptor_template.len() as u64)),
+            &sha256(self.descriptor_template.as_bytes()),
+            &serialize(&VarInt(self.keys_info.len() as u64)),
+            &MerkleTree::from_elements(&self.keys_info).root(),
+        ]
+        .concat()
+    }
+
+    pub fn id(&self) -> Hash {
+        sha256(&self.serialize())
+    }
+
+    /// Makes all the data the device may request about this policy available for it.
+    pub fn provide_to(&self, interpreter: &mut ClientCommandInterpreter) {
+        interpreter.add_known_preimage(self.serialize());
+        interpreter.add_known_list(&self.keys_info);
+        interpreter.add_known_preimage(self.descriptor_template.as_bytes().to_vec());
+    }
+}
@@ -0,0 +1,99 @@
+use super::{LedgerError, apdu, transport::Transport};
+
+/// Name the dashboard reports itself with when no app is open.
+pub(super) const DASHBOARD_APP_NAME: &str = "BOLOS";
+
+/// Oldest app versions known to work with ledger-tui.
+const MIN_APP_VERSIONS: &[(&str, &str)] = &[
+    ("Bitcoin", "2.1.0"),
+    ("Bitcoin Test", "2.1.0"),
+    ("Ethereum", "1.10.0"),
+];
+
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub model: String,
+    /// Known only when device is on the dashboard.
+    pub target_id: Option<u32>,
+    /// Known only when device is on the dashboard.
+    pub firmware_version: Option<String>,
+    /// App open on the device, `None` if device is on the dashboard.
+    pub running_app: Option<AppInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppInfo {
+    pub name: String,
+    pub version: String,
+}
+
+impl AppInfo {
+    /// Returns minimum supported version of the app if the running one is older.
+    pub fn outdated_by(&self) -> Option<&'static str> {
+        let (_, min_version) = MIN_APP_VERSIONS
+            .iter()
+            .find(|(name, _)| *name == self.name)?;
+
+        (parse_version(&self.version) < parse_version(min_version)).then_some(*min_version)
+    }
+}
+
+/// Parses version like `1.10.3-rc2` into `[1, 10, 3]`.
+fn parse_version(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|component| {
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(component.len());
+
+            component[..digits].parse().unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Model name of the device identified by target id.
+pub(super) fn model_name(target_id: u32) -> Option<&'static str> {
+    match target_id {
+        0x31100002..=0x31100004 => Some("Nano S"),
+        0x33000004 => Some("Nano X"),
+        0x33100004 => Some("Nano S+"),
+        0x33200004 => Some("Stax"),
+        0x33300004 => Some("Flex"),
+        _ => None,
+    }
+}
+
+/// Returns the app currently open on the device. Dashboard is reported as `BOLOS`
+/// with the firmware version.
+pub(super) fn get_running_app(transport: &dyn Transport) -> Result<AppInfo, LedgerError> {
+    let response = apdu::get_running_app().send(transport)?;
+    let (name, version) = apdu::decode_running_app(&response)?;
+
+    Ok(AppInfo { name, version })
+}
+
+/// Returns target id and firmware version. Supported only by the dashboard.
+pub(super) fn get_dashboard_info(transport: &dyn Transport) -> Result<(u32, String), LedgerError> {
+    let response = apdu::get_dashboard_info().send(transport)?;
+    apdu::decode_dashboard_info(&response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_version_check() {
+        let app = |name: &str, version: &str| AppInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+        };
+
+        assert_eq!(app("Ethereum", "1.9.20").outdated_by(), Some("1.10.0"));
+        assert_eq!(app("Ethereum", "1.10.0").outdated_by(), None);
+        assert_eq!(app("Bitcoin", "2.0.6").outdated_by(), Some("2.1.0"));
+        assert_eq!(app("Bitcoin", "2.2.4-rc1").outdated_by(), None);
+        assert_eq!(app("Solana", "0.1.0").outdated_by(), None);
+    }
+}
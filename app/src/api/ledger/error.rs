@@ -0,0 +1,133 @@
+use std::fmt;
+
+use ledger_apdu::APDUErrorCode;
+
+use super::transport::TransportError;
+
+/// Reason ledger device failed to process a request. Displayed to the user
+/// alongside with a hint on how to fix it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LedgerError {
+    /// Device is locked with a PIN code.
+    DeviceLocked,
+    /// Request is not supported by the app open on the device, most likely
+    /// some other app (or no app at all) is open.
+    WrongApp,
+    /// Requested app is not installed on the device.
+    AppNotInstalled,
+    /// User rejected the request on the device.
+    UserRejected,
+    /// Device refused to process the data sent to it.
+    InvalidData,
+    /// Device responded with data that can't be decoded.
+    InvalidResponse,
+    /// Address returned by the device isn't derived from the public key it returned.
+    AddressMismatch,
+    /// Request can't be made for the account derivation path.
+    UnsupportedDerivationPath,
+    /// Device is disconnected or can't be reached.
+    Transport(String),
+    /// Device didn't get to the expected state in time.
+    Timeout,
+    /// Request was cancelled before device finished processing it.
+    Cancelled,
+    /// Device returned a status word not covered by other variants.
+    Device(u16),
+}
+
+impl LedgerError {
+    pub fn from_status_word(status_word: u16) -> Self {
+        match status_word {
+            0x5515 | 0x6982 => Self::DeviceLocked,
+            0x6E00 | 0x6D00 | 0x6511 => Self::WrongApp,
+            0x6807 => Self::AppNotInstalled,
+            0x6985 => Self::UserRejected,
+            0x6700 | 0x6A80 | 0x6B00 => Self::InvalidData,
+            status_word => Self::Device(status_word),
+        }
+    }
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeviceLocked => write!(f, "Device is locked, unlock it and try again"),
+            Self::WrongApp => write!(f, "Wrong app is open on the device, open the right one"),
+            Self::AppNotInstalled => write!(f, "App is not installed on the device, install it"),
+            Self::UserRejected => write!(f, "Request was rejected on the device"),
+            Self::InvalidData => write!(f, "Device rejected the data, try to update the app"),
+            Self::InvalidResponse => write!(f, "Unexpected response received from the device"),
+            Self::AddressMismatch => {
+                write!(
+                    f,
+                    "Address returned by the device doesn't match its public key"
+                )
+            }
+            Self::UnsupportedDerivationPath => {
+                write!(f, "Account derivation path is not supported")
+            }
+            Self::Transport(e) => write!(f, "Device is not reachable, reconnect it ({})", e),
+            Self::Timeout => write!(f, "Device didn't respond in time, check it and try again"),
+            Self::Cancelled => write!(f, "Request was cancelled"),
+            Self::Device(status_word) => write!(f, "Device returned error {:#06x}", status_word),
+        }
+    }
+}
+
+impl From<TransportError> for LedgerError {
+    fn from(value: TransportError) -> Self {
+        log::error!("Failed to communicate with ledger device: {}", value);
+
+        match value {
+            TransportError::InvalidResponse(_) => Self::InvalidResponse,
+            value => Self::Transport(value.to_string()),
+        }
+    }
+}
+
+/// Returns response data if device reported success and error matching the status word otherwise.
+pub(super) fn check_response_status(retcode: u16, data: &[u8]) -> Result<Vec<u8>, LedgerError> {
+    match APDUErrorCode::try_from(retcode) {
+        Ok(APDUErrorCode::NoError) => Ok(data.to_vec()),
+        Ok(error) => {
+            log::error!("Error returned from ledger device: {}", error);
+            Err(LedgerError::from_status_word(retcode))
+        }
+        Err(_) => {
+            log::error!("Error returned from ledger device: {:#06x}", retcode);
+            Err(LedgerError::from_status_word(retcode))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_word_mapping() {
+        assert_eq!(
+            LedgerError::from_status_word(0x5515),
+            LedgerError::DeviceLocked
+        );
+        assert_eq!(LedgerError::from_status_word(0x6E00), LedgerError::WrongApp);
+        assert_eq!(
+            LedgerError::from_status_word(0x6985),
+            LedgerError::UserRejected
+        );
+        assert_eq!(
+            LedgerError::from_status_word(0x6A80),
+            LedgerError::InvalidData
+        );
+        assert_eq!(
+            LedgerError::from_status_word(0x6F42),
+            LedgerError::Device(0x6F42)
+        );
+
+        assert_eq!(check_response_status(0x9000, &[1, 2]), Ok(vec![1, 2]));
+        assert_eq!(
+            check_response_status(0x6985, &[]),
+            Err(LedgerError::UserRejected)
+        );
+    }
+}
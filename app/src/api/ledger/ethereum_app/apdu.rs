@@ -0,0 +1,344 @@
+//! Commands of ethereum app, see
+//! [specification](https://github.com/LedgerHQ/app-ethereum/blob/develop/doc/ethapp.adoc).
+
+use crate::api::common_types::DerivationPath;
+
+use super::{
+    super::{
+        LedgerError,
+        apdu::{Chunk, Command, Reader},
+    },
+    EthereumSignature,
+    crypto_assets::TokenDescriptor,
+};
+
+const CLA: u8 = 0xE0;
+
+const INS_GET_ADDRESS: u8 = 0x02;
+const INS_SIGN_TRANSACTION: u8 = 0x04;
+const INS_SIGN_PERSONAL_MESSAGE: u8 = 0x08;
+const INS_PROVIDE_ERC20_TOKEN_INFO: u8 = 0x0A;
+const INS_SIGN_EIP712: u8 = 0x0C;
+const INS_EIP712_STRUCT_DEFINITION: u8 = 0x1A;
+const INS_EIP712_STRUCT_IMPLEMENTATION: u8 = 0x1C;
+
+const P1_FIRST_CHUNK: u8 = 0x00;
+const P1_SUBSEQUENT_CHUNK: u8 = 0x80;
+
+const P2_PROCESS: u8 = 0x00;
+const P2_STORE: u8 = 0x01;
+
+const P1_EIP712_COMPLETE: u8 = 0x00;
+const P1_EIP712_PARTIAL: u8 = 0x01;
+
+const P2_EIP712_SIGN_HASHED: u8 = 0x00;
+const P2_EIP712_SIGN_FULL: u8 = 0x01;
+
+const P2_EIP712_STRUCT_NAME: u8 = 0x00;
+const P2_EIP712_ROOT_STRUCT: u8 = 0x00;
+const P2_EIP712_ARRAY: u8 = 0x0F;
+const P2_EIP712_STRUCT_FIELD: u8 = 0xFF;
+
+/// Length of the address in hex without `0x` prefix.
+const ADDRESS_HEX_LENGTH: usize = 40;
+
+pub struct AddressResponse {
+    pub public_key: Vec<u8>,
+    /// Address with `0x` prefix.
+    pub address: String,
+}
+
+/// Chain id is optional, device shows the network it belongs to alongside the address.
+pub fn get_address(path: &DerivationPath, display: bool, chain_id: Option<u64>) -> Command {
+    let mut data = path.encode();
+    if let Some(chain_id) = chain_id {
+        data.extend(chain_id.to_be_bytes());
+    }
+
+    Command::new(CLA, INS_GET_ADDRESS)
+        .p1(display as u8) // 0x00 - return address; 0x01 - display address and return.
+        .p2(0x00) // 0x00 - do not return the chain code; 0x01 - return the chain code.
+        .data(data)
+}
+
+/// Response has a form of `<public key length(1 byte)> <public key>
+/// <address length(1 byte)> <address(hex without 0x)> ...`.
+pub fn decode_address(response: &[u8]) -> Result<AddressResponse, LedgerError> {
+    let mut reader = Reader::new(response);
+
+    let public_key = reader.length_prefixed()?.to_vec();
+    let address = reader.string()?;
+
+    if address.len() != ADDRESS_HEX_LENGTH || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        log::error!("Invalid ethereum address received from ledger device");
+        return Err(LedgerError::InvalidResponse);
+    }
+
+    Ok(AddressResponse {
+        public_key,
+        address: ["0x", &address].concat(),
+    })
+}
+
+/// Response has a form of `<v(1 byte)> <r(32 bytes)> <s(32 bytes)>`.
+pub fn decode_signature(response: &[u8]) -> Result<EthereumSignature, LedgerError> {
+    let mut reader = Reader::new(response);
+
+    Ok(EthereumSignature {
+        v: reader.u8()?,
+        r: reader.array()?,
+        s: reader.array()?,
+    })
+}
+
+/// Sends RLP encoded transaction to sign in chunks, device starts signing flow
+/// once the last one is received.
+pub fn sign_transaction(path: &DerivationPath, tx: &[u8]) -> Vec<Command> {
+    let data = [&path.encode()[..], tx].concat();
+
+    Command::chunked(CLA, INS_SIGN_TRANSACTION, &data, |Chunk { first, last }| {
+        let p1 = if first {
+            P1_FIRST_CHUNK
+        } else {
+            P1_SUBSEQUENT_CHUNK
+        };
+        let p2 = if last { P2_PROCESS } else { P2_STORE };
+
+        (p1, p2)
+    })
+}
+
+pub fn sign_personal_message(path: &DerivationPath, message: &[u8]) -> Vec<Command> {
+    let data = [
+        &path.encode()[..],
+        &(message.len() as u32).to_be_bytes(),
+        message,
+    ]
+    .concat();
+
+    Command::chunked(CLA, INS_SIGN_PERSONAL_MESSAGE, &data, |chunk| {
+        let p1 = if chunk.first {
+            P1_FIRST_CHUNK
+        } else {
+            P1_SUBSEQUENT_CHUNK
+        };
+
+        (p1, 0x00)
+    })
+}
+
+/// Data has the same form as descriptors in the Ledger crypto assets list.
+pub fn provide_erc20_token_info(token: &TokenDescriptor) -> Result<Command, LedgerError> {
+    let data = [
+        &length_prefixed(token.ticker.as_bytes())?,
+        &token.contract[..],
+        &token.decimals.to_be_bytes(),
+        &token.chain_id.to_be_bytes(),
+        &token.signature,
+    ]
+    .concat();
+
+    Command::new(CLA, INS_PROVIDE_ERC20_TOKEN_INFO).try_data(data)
+}
+
+/// Signs typed data by domain separator and message hash.
+pub fn sign_eip712_hashed(
+    path: &DerivationPath,
+    domain_separator: &[u8; 32],
+    message_hash: &[u8; 32],
+) -> Command {
+    let data = [&path.encode()[..], domain_separator, message_hash].concat();
+
+    Command::new(CLA, INS_SIGN_EIP712)
+        .p2(P2_EIP712_SIGN_HASHED)
+        .data(data)
+}
+
+/// Signs typed data which definitions and values were sent to the device before.
+pub fn sign_eip712_full(path: &DerivationPath) -> Command {
+    Command::new(CLA, INS_SIGN_EIP712)
+        .p2(P2_EIP712_SIGN_FULL)
+        .data(path.encode())
+}
+
+pub fn eip712_struct_name(name: &str) -> Result<Command, LedgerError> {
+    Command::new(CLA, INS_EIP712_STRUCT_DEFINITION)
+        .p1(P1_EIP712_COMPLETE)
+        .p2(P2_EIP712_STRUCT_NAME)
+        .try_data(name.as_bytes().to_vec())
+}
+
+/// Field definition encoded as specified by the app.
+pub fn eip712_struct_field(field: Vec<u8>) -> Result<Command, LedgerError> {
+    Command::new(CLA, INS_EIP712_STRUCT_DEFINITION)
+        .p1(P1_EIP712_COMPLETE)
+        .p2(P2_EIP712_STRUCT_FIELD)
+        .try_data(field)
+}
+
+pub fn eip712_root_struct(name: &str) -> Result<Command, LedgerError> {
+    Command::new(CLA, INS_EIP712_STRUCT_IMPLEMENTATION)
+        .p1(P1_EIP712_COMPLETE)
+        .p2(P2_EIP712_ROOT_STRUCT)
+        .try_data(name.as_bytes().to_vec())
+}
+
+pub fn eip712_array(length: usize) -> Result<Command, LedgerError> {
+    let length = u8::try_from(length).map_err(|_| too_long(length))?;
+
+    Ok(Command::new(CLA, INS_EIP712_STRUCT_IMPLEMENTATION)
+        .p1(P1_EIP712_COMPLETE)
+        .p2(P2_EIP712_ARRAY)
+        .data(vec![length]))
+}
+
+/// Sends value of the field prefixed with its length(2 bytes) in chunks.
+pub fn eip712_field_value(value: &[u8]) -> Result<Vec<Command>, LedgerError> {
+    let length = u16::try_from(value.len()).map_err(|_| too_long(value.len()))?;
+    let data = [&length.to_be_bytes()[..], value].concat();
+
+    Ok(Command::chunked(
+        CLA,
+        INS_EIP712_STRUCT_IMPLEMENTATION,
+        &data,
+        |chunk| {
+            let p1 = if chunk.last {
+                P1_EIP712_COMPLETE
+            } else {
+                P1_EIP712_PARTIAL
+            };
+
+            (p1, P2_EIP712_STRUCT_FIELD)
+        },
+    ))
+}
+
+/// Prefixes data with its length(1 byte).
+pub fn length_prefixed(data: &[u8]) -> Result<Vec<u8>, LedgerError> {
+    let length = u8::try_from(data.len()).map_err(|_| too_long(data.len()))?;
+
+    Ok([&[length][..], data].concat())
+}
+
+fn too_long(length: usize) -> LedgerError {
+    log::error!("Data of length {} can't be sent to ledger device", length);
+    LedgerError::InvalidData
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_sign_transaction_chunks() {
+        let path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+        let commands = sign_transaction(&path, &[0xAB; 600]);
+
+        // 21 bytes of path followed by transaction.
+        let params: Vec<_> = commands
+            .iter()
+            .map(|command| (command.p1, command.p2, command.data.len()))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                (P1_FIRST_CHUNK, P2_STORE, 255),
+                (P1_SUBSEQUENT_CHUNK, P2_STORE, 255),
+                (P1_SUBSEQUENT_CHUNK, P2_PROCESS, 111)
+            ]
+        );
+        assert_eq!(&commands[0].data[..21], &path.encode()[..]);
+    }
+
+    #[test]
+    fn test_get_address_chain_id() {
+        let path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+
+        assert_eq!(get_address(&path, false, None).data, path.encode());
+        assert_eq!(
+            get_address(&path, true, Some(137)).data,
+            [&path.encode()[..], &[0, 0, 0, 0, 0, 0, 0, 137]].concat()
+        );
+    }
+
+    /// Serializes command the way transports do it, length of data takes a single byte.
+    fn serialize(command: &Command) -> Vec<u8> {
+        ledger_apdu::APDUCommand {
+            cla: command.cla,
+            ins: command.ins,
+            p1: command.p1,
+            p2: command.p2,
+            data: &command.data[..],
+        }
+        .serialize()
+    }
+
+    proptest! {
+        #[test]
+        fn test_chunk_lengths_fit_into_length_byte(
+            depth in 0..=10usize,
+            payload in proptest::collection::vec(any::<u8>(), 0..2048),
+        ) {
+            let path: DerivationPath = ["m"].into_iter().chain(vec!["44'"; depth]).join("/").parse().unwrap();
+
+            let commands = [
+                sign_transaction(&path, &payload),
+                sign_personal_message(&path, &payload),
+                eip712_field_value(&payload).unwrap(),
+            ];
+            for command in commands.iter().flatten() {
+                let frame = serialize(command);
+                prop_assert!(command.data.len() <= 255);
+                prop_assert_eq!(frame[4] as usize, frame.len() - 5);
+            }
+
+            // Path goes into the first chunk alongside with the start of the payload.
+            let first = &commands[0][0];
+            prop_assert_eq!(&first.data[..path.encode().len()], &path.encode()[..]);
+        }
+
+        #[test]
+        fn test_arbitrary_responses(response in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = decode_address(&response);
+            let _ = decode_signature(&response);
+        }
+
+        #[test]
+        fn test_truncated_address_response(
+            public_key in proptest::collection::vec(any::<u8>(), 0..=65),
+            address in "[0-9a-fA-F]{40}",
+            cut in 0..usize::MAX,
+        ) {
+            let response = [
+                &length_prefixed(&public_key).unwrap()[..],
+                &length_prefixed(address.as_bytes()).unwrap(),
+            ]
+            .concat();
+
+            let decoded = decode_address(&response).unwrap();
+            prop_assert_eq!(decoded.public_key, public_key);
+            prop_assert_eq!(decoded.address, format!("0x{}", address));
+
+            let cut = cut % response.len();
+            prop_assert_eq!(
+                decode_address(&response[..cut]).err(),
+                Some(LedgerError::InvalidResponse)
+            );
+        }
+
+        #[test]
+        fn test_eip712_field_value_chunks(value in proptest::collection::vec(any::<u8>(), 0..1024)) {
+            let commands = eip712_field_value(&value).unwrap();
+
+            let sent: Vec<_> = commands.iter().flat_map(|command| command.data.clone()).collect();
+            prop_assert_eq!(&sent[2..], &value[..]);
+            for (idx, command) in commands.iter().enumerate() {
+                let is_last = idx + 1 == commands.len();
+                prop_assert_eq!(command.p1 == P1_EIP712_COMPLETE, is_last);
+            }
+        }
+    }
+}
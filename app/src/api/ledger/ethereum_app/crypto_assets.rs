@@ -0,0 +1,122 @@
+//! Token information signed by Ledger, fetched from its crypto assets list the same way
+//! Ledger Live does it. Device checks the signature, so it trusts ticker and decimals of
+//! the token and displays amount of the transfer instead of the raw contract call.
+
+use base64::Engine;
+
+use super::super::{LedgerError, apdu::Reader};
+
+/// Base URL of the Ledger crypto assets list.
+pub const CRYPTO_ASSETS_URL: &str = "https://cdn.live.ledger.com/cryptoassets";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TokenDescriptor {
+    pub ticker: String,
+    pub contract: [u8; 20],
+    pub decimals: u32,
+    pub chain_id: u32,
+    pub signature: Vec<u8>,
+}
+
+/// Fetches descriptor of the token deployed at `contract`, `None` if it's not listed
+/// or the list can't be fetched.
+pub async fn fetch_token_descriptor(
+    url: &str,
+    chain_id: u32,
+    contract: &[u8; 20],
+) -> Option<TokenDescriptor> {
+    let url = format!("{}/evm/{}/erc20-signatures.json", url, chain_id);
+
+    let response = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status());
+    let blob: String = match response {
+        Ok(response) => response
+            .json()
+            .await
+            .inspect_err(|e| log::error!("Invalid token descriptors list: {}", e))
+            .ok()?,
+        Err(e) => {
+            log::error!("Failed to fetch token descriptors from {}: {}", url, e);
+            return None;
+        }
+    };
+
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(blob)
+        .inspect_err(|e| log::error!("Invalid token descriptors list: {}", e))
+        .ok()?;
+
+    decode_token_descriptors(&blob)
+        .ok()?
+        .into_iter()
+        .find(|descriptor| descriptor.chain_id == chain_id && descriptor.contract == *contract)
+}
+
+/// List has a form of `<descriptor length(4 bytes)> <descriptor> ...`, where descriptor is
+/// `<ticker length(1 byte)> <ticker> <contract(20 bytes)> <decimals(4 bytes)>
+/// <chain id(4 bytes)> <signature>`.
+pub fn decode_token_descriptors(blob: &[u8]) -> Result<Vec<TokenDescriptor>, LedgerError> {
+    let mut reader = Reader::new(blob);
+    let mut descriptors = vec![];
+
+    while !reader.is_empty() {
+        let length = reader.u32()?;
+        let mut descriptor = Reader::new(reader.bytes(length as usize)?);
+
+        descriptors.push(TokenDescriptor {
+            ticker: descriptor.string()?,
+            contract: descriptor.array()?,
+            decimals: descriptor.u32()?,
+            chain_id: descriptor.u32()?,
+            signature: descriptor.rest().to_vec(),
+        });
+    }
+
+    Ok(descriptors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_descriptors_decoding() {
+        let descriptor = |ticker: &str, contract: u8, chain_id: u32| {
+            let data = [
+                &[ticker.len() as u8][..],
+                ticker.as_bytes(),
+                &[contract; 20],
+                &6u32.to_be_bytes(),
+                &chain_id.to_be_bytes(),
+                &[0x30; 70],
+            ]
+            .concat();
+            [&(data.len() as u32).to_be_bytes()[..], &data].concat()
+        };
+        let blob = [descriptor("USDT", 0xDA, 1), descriptor("USDC", 0x3C, 137)].concat();
+
+        let descriptors = decode_token_descriptors(&blob).unwrap();
+        assert_eq!(
+            descriptors[1],
+            TokenDescriptor {
+                ticker: "USDC".to_string(),
+                contract: [0x3C; 20],
+                decimals: 6,
+                chain_id: 137,
+                signature: vec![0x30; 70],
+            }
+        );
+        assert_eq!(descriptors[0].ticker, "USDT");
+
+        for length in 1..blob.len() {
+            if length == blob.len() / 2 {
+                continue;
+            }
+            assert_eq!(
+                decode_token_descriptors(&blob[..length]),
+                Err(LedgerError::InvalidResponse)
+            );
+        }
+    }
+}
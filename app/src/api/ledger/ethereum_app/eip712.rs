@@ -0,0 +1,278 @@
+use crate::api::common_types::DerivationPath;
+use alloy::{
+    dyn_abi::{DynSolValue, Eip712Types, PropertyDef, TypeDef, TypedData},
+    sol_types::Eip712Domain,
+};
+
+use super::{
+    super::{LedgerError, apdu::send_all, transport::Transport},
+    EthereumSignature,
+    apdu::{self, length_prefixed},
+};
+
+const TYPE_ARRAY_FLAG: u8 = 0x80;
+const TYPE_SIZE_FLAG: u8 = 0x40;
+
+const TYPE_CUSTOM: u8 = 0;
+const TYPE_INT: u8 = 1;
+const TYPE_UINT: u8 = 2;
+const TYPE_ADDRESS: u8 = 3;
+const TYPE_BOOL: u8 = 4;
+const TYPE_STRING: u8 = 5;
+const TYPE_FIXED_BYTES: u8 = 6;
+const TYPE_DYNAMIC_BYTES: u8 = 7;
+
+const ARRAY_LEVEL_DYNAMIC: u8 = 0;
+const ARRAY_LEVEL_FIXED: u8 = 1;
+
+const DOMAIN_TYPE_NAME: &str = "EIP712Domain";
+
+/// Signs typed data sending only domain separator and message hash to the device.
+/// Supported by all the app versions, but user can't review what's signed.
+pub fn sign_hashed(
+    typed_data: &TypedData,
+    transport: &dyn Transport,
+    path: &DerivationPath,
+) -> Result<EthereumSignature, LedgerError> {
+    let message_hash = typed_data.hash_struct().map_err(invalid_typed_data)?;
+
+    let command = apdu::sign_eip712_hashed(path, &typed_data.domain.separator(), &message_hash);
+    let response = command.send(transport)?;
+
+    apdu::decode_signature(&response)
+}
+
+/// Signs typed data sending all the type definitions and values to the device,
+/// so it can display them. Device responds with `LedgerError::WrongApp` if
+/// app doesn't support it.
+pub fn sign_full(
+    typed_data: &TypedData,
+    transport: &dyn Transport,
+    path: &DerivationPath,
+) -> Result<EthereumSignature, LedgerError> {
+    // Domain hash is computed from the present domain fields, so type definition
+    // is built from them as well.
+    let mut resolver = typed_data.resolver.clone();
+    resolver.ingest(
+        TypeDef::new(DOMAIN_TYPE_NAME, domain_type(&typed_data.domain))
+            .map_err(invalid_typed_data)?,
+    );
+
+    let domain = serde_json::to_value(&typed_data.domain).map_err(invalid_typed_data)?;
+    let domain = resolver
+        .resolve(DOMAIN_TYPE_NAME)
+        .and_then(|domain_type| domain_type.coerce_json(&domain))
+        .map_err(invalid_typed_data)?;
+    let message = typed_data.coerce().map_err(invalid_typed_data)?;
+
+    for (name, props) in &Eip712Types::from(&resolver) {
+        send_struct_definition(transport, name, props)?;
+    }
+
+    send_struct_implementation(transport, &domain)?;
+    send_struct_implementation(transport, &message)?;
+
+    let response = apdu::sign_eip712_full(path).send(transport)?;
+
+    apdu::decode_signature(&response)
+}
+
+fn invalid_typed_data(error: impl std::fmt::Display) -> LedgerError {
+    log::error!("Invalid EIP-712 typed data: {}", error);
+    LedgerError::InvalidData
+}
+
+fn domain_type(domain: &Eip712Domain) -> Vec<PropertyDef> {
+    [
+        ("string", "name", domain.name.is_some()),
+        ("string", "version", domain.version.is_some()),
+        ("uint256", "chainId", domain.chain_id.is_some()),
+        (
+            "address",
+            "verifyingContract",
+            domain.verifying_contract.is_some(),
+        ),
+        ("bytes32", "salt", domain.salt.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, _, is_present)| *is_present)
+    .map(|(type_name, name, _)| PropertyDef::new_unchecked(type_name, name))
+    .collect()
+}
+
+fn send_struct_definition(
+    transport: &dyn Transport,
+    name: &str,
+    props: &[PropertyDef],
+) -> Result<(), LedgerError> {
+    apdu::eip712_struct_name(name)?.send(transport)?;
+
+    for prop in props {
+        let field = encode_field_definition(prop.type_name(), prop.name())?;
+        apdu::eip712_struct_field(field)?.send(transport)?;
+    }
+
+    Ok(())
+}
+
+/// Encodes field definition in the form of `<type desc> <type name>(custom types only)
+/// <type size>(sized types only) <array levels>(arrays only) <key name>`.
+fn encode_field_definition(type_name: &str, key_name: &str) -> Result<Vec<u8>, LedgerError> {
+    let (root_type, array_levels) = type_name
+        .find('[')
+        .map(|idx| type_name.split_at(idx))
+        .unwrap_or((type_name, ""));
+
+    let array_levels = array_levels
+        .split_terminator(']')
+        .map(|level| match level.strip_prefix('[') {
+            Some("") => Ok(vec![ARRAY_LEVEL_DYNAMIC]),
+            Some(size) => size
+                .parse()
+                .map(|size| vec![ARRAY_LEVEL_FIXED, size])
+                .map_err(invalid_typed_data),
+            None => Err(invalid_typed_data(format!("invalid type {}", type_name))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (type_id, type_size) = encode_root_type(root_type);
+
+    let mut type_desc = type_id;
+    if !array_levels.is_empty() {
+        type_desc |= TYPE_ARRAY_FLAG;
+    }
+    if type_size.is_some() {
+        type_desc |= TYPE_SIZE_FLAG;
+    }
+
+    let mut data = vec![type_desc];
+    if type_id == TYPE_CUSTOM {
+        data.extend(length_prefixed(root_type.as_bytes())?);
+    }
+    data.extend(type_size);
+    if !array_levels.is_empty() {
+        data.push(array_levels.len() as u8);
+        data.extend(array_levels.concat());
+    }
+    data.extend(length_prefixed(key_name.as_bytes())?);
+
+    Ok(data)
+}
+
+/// Returns type id and size in bytes for sized types.
+fn encode_root_type(root_type: &str) -> (u8, Option<u8>) {
+    let size_in_bytes = |bits: &str| -> Option<u8> {
+        if bits.is_empty() {
+            Some(32)
+        } else {
+            bits.parse::<u16>().ok().map(|bits| (bits / 8) as u8)
+        }
+    };
+
+    match root_type {
+        "address" => return (TYPE_ADDRESS, None),
+        "bool" => return (TYPE_BOOL, None),
+        "string" => return (TYPE_STRING, None),
+        "bytes" => return (TYPE_DYNAMIC_BYTES, None),
+        _ => {}
+    }
+
+    if let Some(size) = root_type.strip_prefix("uint").and_then(size_in_bytes) {
+        (TYPE_UINT, Some(size))
+    } else if let Some(size) = root_type.strip_prefix("int").and_then(size_in_bytes) {
+        (TYPE_INT, Some(size))
+    } else if let Some(size) = root_type
+        .strip_prefix("bytes")
+        .and_then(|size| size.parse().ok())
+    {
+        (TYPE_FIXED_BYTES, Some(size))
+    } else {
+        (TYPE_CUSTOM, None)
+    }
+}
+
+fn send_struct_implementation(
+    transport: &dyn Transport,
+    value: &DynSolValue,
+) -> Result<(), LedgerError> {
+    let DynSolValue::CustomStruct { name, tuple, .. } = value else {
+        return Err(invalid_typed_data("root value is not a struct"));
+    };
+
+    apdu::eip712_root_struct(name)?.send(transport)?;
+
+    tuple
+        .iter()
+        .try_for_each(|field| send_field_value(transport, field))
+}
+
+/// Sends values of struct fields depth-first.
+fn send_field_value(transport: &dyn Transport, value: &DynSolValue) -> Result<(), LedgerError> {
+    match value {
+        DynSolValue::CustomStruct { tuple, .. } => tuple
+            .iter()
+            .try_for_each(|field| send_field_value(transport, field)),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            apdu::eip712_array(values.len())?.send(transport)?;
+
+            values
+                .iter()
+                .try_for_each(|value| send_field_value(transport, value))
+        }
+        value => {
+            let value = encode_field_value(value)?;
+            send_all(&apdu::eip712_field_value(&value)?, transport)?;
+
+            Ok(())
+        }
+    }
+}
+
+fn encode_field_value(value: &DynSolValue) -> Result<Vec<u8>, LedgerError> {
+    let encoded = match value {
+        DynSolValue::Bool(value) => vec![*value as u8],
+        DynSolValue::Uint(value, _) => {
+            let bytes = value.to_be_bytes_trimmed_vec();
+            if bytes.is_empty() { vec![0] } else { bytes }
+        }
+        DynSolValue::Int(value, bits) => value.to_be_bytes::<32>()[32 - bits / 8..].to_vec(),
+        DynSolValue::Address(address) => address.to_vec(),
+        DynSolValue::FixedBytes(word, size) => word[..*size].to_vec(),
+        DynSolValue::Bytes(bytes) => bytes.clone(),
+        DynSolValue::String(string) => string.as_bytes().to_vec(),
+        value => return Err(invalid_typed_data(format!("unsupported value {:?}", value))),
+    };
+
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_definition_encoding() {
+        assert_eq!(
+            encode_field_definition("address", "to").unwrap(),
+            [&[TYPE_ADDRESS, 2][..], b"to"].concat()
+        );
+        assert_eq!(
+            encode_field_definition("uint256", "amount").unwrap(),
+            [&[TYPE_UINT | TYPE_SIZE_FLAG, 32, 6][..], b"amount"].concat()
+        );
+        assert_eq!(
+            encode_field_definition("bytes4", "selector").unwrap(),
+            [&[TYPE_FIXED_BYTES | TYPE_SIZE_FLAG, 4, 8][..], b"selector"].concat()
+        );
+        assert_eq!(
+            encode_field_definition("Person[][2]", "people").unwrap(),
+            [
+                &[TYPE_CUSTOM | TYPE_ARRAY_FLAG, 6][..],
+                b"Person",
+                &[2, ARRAY_LEVEL_DYNAMIC, ARRAY_LEVEL_FIXED, 2, 6],
+                b"people"
+            ]
+            .concat()
+        );
+    }
+}
@@ -0,0 +1,559 @@
+use std::fmt;
+
+use crate::api::common_types::{Account, DerivationPath, Token};
+use alloy::{
+    dyn_abi::TypedData,
+    primitives::{Address, keccak256},
+};
+
+use super::{Device, LedgerError, apdu::send_all, transport::Transport};
+
+mod apdu;
+mod crypto_assets;
+mod eip712;
+
+use apdu::AddressResponse;
+use crypto_assets::TokenDescriptor;
+
+pub use crypto_assets::CRYPTO_ASSETS_URL;
+
+#[cfg(fuzzing)]
+pub mod fuzzing {
+    use super::{apdu, crypto_assets};
+
+    /// Decodes the data as every kind of device response and as crypto assets list.
+    pub fn decode(data: &[u8]) {
+        let _ = apdu::decode_address(data);
+        let _ = apdu::decode_signature(data);
+        let _ = crypto_assets::decode_token_descriptors(data);
+    }
+}
+
+const UNCOMPRESSED_PUBLIC_KEY_PREFIX: u8 = 0x04;
+
+/// Chain id of the Ethereum mainnet, device assumes it if chain id isn't provided.
+const MAINNET_CHAIN_ID: u64 = 1;
+
+/// Signature produced by ethereum app for messages and typed data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EthereumSignature {
+    pub v: u8,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+impl EthereumSignature {
+    /// Encodes signature in the form of `r || s || v` as it's expected by
+    /// `personal_sign` and `eth_signTypedData` consumers.
+    pub fn to_bytes(self) -> [u8; 65] {
+        let mut bytes = [0; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = self.v;
+        bytes
+    }
+}
+
+impl fmt::Display for EthereumSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.to_bytes()))
+    }
+}
+
+pub async fn get_account(
+    device: &Device,
+    path: &DerivationPath,
+    chain_id: u64,
+) -> Result<Account, LedgerError> {
+    log::info!("Deriving ethereum account {} on chain {}", path, chain_id);
+
+    let transport = device.open_transport()?;
+
+    let AddressResponse {
+        public_key,
+        address,
+    } = get_address(transport.as_ref(), path, false, chain_id)?;
+
+    log::info!(
+        "Derived ethereum account {} with public key = {}",
+        path,
+        address,
+    );
+
+    Ok(Account {
+        public_key: address,
+        derivation_path: Some(path.clone()),
+        xpub: None,
+        uncompressed_public_key: Some(hex::encode(public_key)),
+    })
+}
+
+/// Shows the address on the device screen and returns it once user confirms it.
+pub async fn display_address(
+    device: &Device,
+    path: &DerivationPath,
+    chain_id: u64,
+) -> Result<String, LedgerError> {
+    log::info!("Displaying ethereum address {} on device", path);
+
+    let transport = device.open_transport()?;
+
+    Ok(get_address(transport.as_ref(), path, true, chain_id)?.address)
+}
+
+/// Returns address and public key after checking that the address is derived from the key.
+/// Chain id isn't sent for mainnet, so app versions not supporting it work there as well.
+fn get_address(
+    transport: &dyn Transport,
+    path: &DerivationPath,
+    display: bool,
+    chain_id: u64,
+) -> Result<AddressResponse, LedgerError> {
+    let chain_id = (chain_id != MAINNET_CHAIN_ID).then_some(chain_id);
+    let response = apdu::get_address(path, display, chain_id).send(transport)?;
+    let response = apdu::decode_address(&response)?;
+
+    let expected_address = address_of_public_key(&response.public_key)?;
+    if response.address != expected_address {
+        log::error!(
+            "Device returned address {} for public key {}, expected {}",
+            response.address,
+            hex::encode(&response.public_key),
+            expected_address
+        );
+        return Err(LedgerError::AddressMismatch);
+    }
+
+    Ok(response)
+}
+
+/// Returns EIP-55 checksummed address of the uncompressed public key.
+pub fn address_of_public_key(public_key: &[u8]) -> Result<String, LedgerError> {
+    let Some((&UNCOMPRESSED_PUBLIC_KEY_PREFIX, coordinates)) = public_key.split_first() else {
+        log::error!("Invalid public key received from ledger device");
+        return Err(LedgerError::InvalidResponse);
+    };
+    if coordinates.len() != 64 {
+        log::error!("Invalid public key received from ledger device");
+        return Err(LedgerError::InvalidResponse);
+    }
+
+    let hash = keccak256(coordinates);
+
+    Ok(Address::from_slice(&hash[12..]).to_checksum(None))
+}
+
+pub async fn sign_message(
+    message: Vec<u8>,
+    device: &Device,
+    path: &DerivationPath,
+) -> Result<Vec<u8>, LedgerError> {
+    log::info!("Signing ethereum message 0x{}", hex::encode(&message));
+
+    let transport = device.open_transport()?;
+
+    let response = send_all(&apdu::sign_transaction(path, &message), transport.as_ref())?;
+
+    let EthereumSignature { v, r, s } = apdu::decode_signature(&response)?;
+
+    log::info!(
+        "Ethereum message signature: v={:#04x} r=0x{} s=0x{}",
+        v,
+        hex::encode(r),
+        hex::encode(s)
+    );
+
+    Ok(response)
+}
+
+/// Signs message according to EIP-191 (`personal_sign`).
+pub async fn sign_personal_message(
+    message: Vec<u8>,
+    device: &Device,
+    path: &DerivationPath,
+) -> Result<EthereumSignature, LedgerError> {
+    log::info!(
+        "Signing ethereum personal message 0x{}",
+        hex::encode(&message)
+    );
+
+    let transport = device.open_transport()?;
+
+    let response = send_all(
+        &apdu::sign_personal_message(path, &message),
+        transport.as_ref(),
+    )?;
+
+    let signature = apdu::decode_signature(&response)?;
+    log::info!("Ethereum personal message signature: {}", signature);
+
+    Ok(signature)
+}
+
+/// Signs EIP-712 typed data. Typed data is sent to the device in full to be displayed
+/// field by field if app supports it, otherwise only domain and message hashes are sent.
+pub async fn sign_typed_data(
+    typed_data: &TypedData,
+    device: &Device,
+    path: &DerivationPath,
+) -> Result<EthereumSignature, LedgerError> {
+    log::info!(
+        "Signing ethereum typed data of type {}",
+        typed_data.primary_type
+    );
+
+    let transport = device.open_transport()?;
+
+    let signature = match eip712::sign_full(typed_data, transport.as_ref(), path) {
+        Err(LedgerError::WrongApp) => {
+            log::info!("Full EIP-712 signing isn't supported by the app, signing hashes");
+            eip712::sign_hashed(typed_data, transport.as_ref(), path)
+        }
+        result => result,
+    }?;
+
+    log::info!("Ethereum typed data signature: {}", signature);
+
+    Ok(signature)
+}
+
+/// Provides token information signed by Ledger to the device, so it displays amount
+/// and ticker of the token transfer signed next instead of the raw contract call.
+/// Signature configured for the token is used if present, otherwise it's looked up
+/// in the Ledger crypto assets list at `crypto_assets_url`.
+pub async fn provide_token_info(
+    device: &Device,
+    token: &Token,
+    crypto_assets_url: &str,
+) -> Result<(), LedgerError> {
+    let contract = hex::decode(token.contract.trim_start_matches("0x"))
+        .ok()
+        .and_then(|contract| <[u8; 20]>::try_from(contract).ok())
+        .ok_or_else(|| {
+            log::error!("Invalid {} contract address", token.symbol);
+            LedgerError::InvalidData
+        })?;
+
+    let chain_id = token
+        .network
+        .chain_id()
+        .and_then(|chain_id| u32::try_from(chain_id).ok())
+        .ok_or_else(|| {
+            log::error!("{} token network has no valid chain id", token.symbol);
+            LedgerError::InvalidData
+        })?;
+
+    let descriptor = match &token.ledger_signature {
+        Some(signature) => TokenDescriptor {
+            ticker: token.symbol.clone(),
+            contract,
+            decimals: token.decimals as u32,
+            chain_id,
+            signature: hex::decode(signature.trim_start_matches("0x")).map_err(|_| {
+                log::error!("Invalid ledger signature of {} token", token.symbol);
+                LedgerError::InvalidData
+            })?,
+        },
+        None => {
+            match crypto_assets::fetch_token_descriptor(crypto_assets_url, chain_id, &contract)
+                .await
+            {
+                Some(descriptor) => descriptor,
+                None => {
+                    log::warn!(
+                        "No ledger signature for {} token, device will show the raw contract call",
+                        token.symbol
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    log::info!(
+        "Providing {} token information to device",
+        descriptor.ticker
+    );
+
+    let command = apdu::provide_erc20_token_info(&descriptor)?;
+
+    let transport = device.open_transport()?;
+    command.send(transport.as_ref())?;
+
+    Ok(())
+}
+
+/// Address of `m/44'/60'/0'/0/0` account of the mnemonic APDU fixtures are made with.
+#[cfg(test)]
+const FIXTURE_ADDRESS: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+
+#[tokio::test]
+async fn test_ethereum_get_account_replay() {
+    let (device, transport) = super::replay_device("ethereum_get_account");
+    let path = "m/44'/60'/0'/0/0".parse().unwrap();
+
+    let account = get_account(&device, &path, MAINNET_CHAIN_ID).await.unwrap();
+
+    assert_eq!(account.public_key, FIXTURE_ADDRESS);
+    assert_eq!(account.derivation_path, Some(path));
+    assert_eq!(
+        address_of_public_key(&hex::decode(account.uncompressed_public_key.unwrap()).unwrap()),
+        Ok(FIXTURE_ADDRESS.to_string())
+    );
+    transport.assert_finished();
+}
+
+#[test]
+fn test_address_mismatch() {
+    let path = "m/44'/60'/0'/0/0".parse().unwrap();
+
+    let fixture = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/fixtures/apdu/ethereum_get_account.json"
+    ))
+    .unwrap();
+    let fixture: serde_json::Value = serde_json::from_str(&fixture).unwrap();
+    let exchange = &fixture["exchanges"][0];
+    let command = exchange["command"].as_str().unwrap();
+    let response = exchange["response"].as_str().unwrap();
+
+    // Device returning the address of other key or the right one without checksum.
+    let tampered_addresses = [
+        "1111111111111111111111111111111111111111".to_string(),
+        FIXTURE_ADDRESS[2..].to_lowercase(),
+    ];
+    for address in tampered_addresses {
+        let response =
+            response.replace(&hex::encode(&FIXTURE_ADDRESS[2..]), &hex::encode(&address));
+        assert_ne!(response, exchange["response"]);
+
+        let fixture = serde_json::json!({
+            "exchanges": [{ "command": command, "response": response }]
+        });
+        let transport = super::transport::ReplayTransport::new(&fixture.to_string());
+
+        assert_eq!(
+            get_address(&transport, &path, false, MAINNET_CHAIN_ID).err(),
+            Some(LedgerError::AddressMismatch)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_ethereum_sign_message_replay() {
+    use alloy::primitives::{Address, Signature, U256, keccak256};
+
+    let (device, transport) = super::replay_device("ethereum_sign_message");
+    let path = "m/44'/60'/0'/0/0".parse().unwrap();
+
+    // EIP-1559 transaction with 608 bytes of call data, so it's sent in 3 chunks.
+    let tx: Vec<u8> = hex::decode(
+        "02f902a701808459682f00850c92a69c0083030d409400000000000000000000000000000000000000\
+         00880de0b6b3a7640000b90260",
+    )
+    .unwrap()
+    .into_iter()
+    .chain((0..608u32).map(|i| (i * 7) as u8))
+    .chain([0xC0])
+    .collect();
+
+    let signature = sign_message(tx.clone(), &device, &path).await.unwrap();
+    transport.assert_finished();
+
+    let signature = Signature::from_rs_and_parity(
+        U256::from_be_slice(&signature[1..33]),
+        U256::from_be_slice(&signature[33..65]),
+        signature[0] == 1,
+    )
+    .unwrap();
+    let signer = signature
+        .recover_address_from_prehash(&keccak256(&tx))
+        .unwrap();
+    assert_eq!(signer, FIXTURE_ADDRESS.parse::<Address>().unwrap());
+}
+
+#[tokio::test]
+async fn test_ethereum_sign_personal_message_replay() {
+    use alloy::primitives::{Address, Signature};
+
+    let (device, transport) = super::replay_device("ethereum_sign_personal_message");
+    let path = "m/44'/60'/0'/0/0".parse().unwrap();
+    let message = b"Hello from ledger-tui";
+
+    let signature = sign_personal_message(message.to_vec(), &device, &path)
+        .await
+        .unwrap();
+    transport.assert_finished();
+
+    let signer = Signature::try_from(&signature.to_bytes()[..])
+        .unwrap()
+        .recover_address_from_msg(message)
+        .unwrap();
+    assert_eq!(signer, FIXTURE_ADDRESS.parse::<Address>().unwrap());
+}
+
+#[tokio::test]
+async fn test_ethereum_sign_long_personal_message_replay() {
+    use alloy::primitives::{Address, Signature};
+
+    let (device, transport) = super::replay_device("ethereum_sign_long_personal_message");
+    let path = "m/44'/60'/0'/0/0".parse().unwrap();
+    // Message doesn't fit into the first chunk alongside with path and its length.
+    let message: Vec<u8> = (0..300u32).map(|i| b'a' + (i % 26) as u8).collect();
+
+    let signature = sign_personal_message(message.clone(), &device, &path)
+        .await
+        .unwrap();
+    transport.assert_finished();
+
+    let signer = Signature::try_from(&signature.to_bytes()[..])
+        .unwrap()
+        .recover_address_from_msg(&message)
+        .unwrap();
+    assert_eq!(signer, FIXTURE_ADDRESS.parse::<Address>().unwrap());
+}
+
+#[tokio::test]
+async fn test_ethereum_token_transfer_replay() {
+    use alloy::{
+        consensus::{SignableTransaction, TxEip1559},
+        primitives::{TxKind, U256},
+        sol_types::SolCall,
+    };
+    use std::sync::Arc;
+
+    use base64::Engine;
+    use ledger_apdu::APDUCommand;
+
+    use crate::api::{
+        common_types::Network,
+        ledger::{software_signer::SoftwareSigner, transport::ReplayTransport},
+        stand_in,
+    };
+
+    alloy::sol! {
+        function transfer(address to, uint256 amount) external returns (bool);
+    }
+
+    let path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+    let usdt = Token::builtin()
+        .into_iter()
+        .find(|token| token.symbol == "USDT")
+        .unwrap();
+
+    // Signature can't be verified by replay, so the one of the list is a placeholder.
+    let descriptor =
+        hex::decode("0455534454dac17f958d2ee523a2206206994597c13d831ec70000000600000001").unwrap();
+    let signature = [0x30; 71];
+    let descriptor = [&descriptor[..], &signature].concat();
+    let blob = [&(descriptor.len() as u32).to_be_bytes()[..], &descriptor].concat();
+    let blob = base64::engine::general_purpose::STANDARD.encode(blob);
+    let url = stand_in::serve(move |path, _| {
+        (path == "/evm/1/erc20-signatures.json").then(|| serde_json::json!(blob))
+    })
+    .await;
+
+    let tx = TxEip1559 {
+        chain_id: MAINNET_CHAIN_ID,
+        nonce: 0,
+        gas_limit: 60_000,
+        max_fee_per_gas: 30_000_000_000,
+        max_priority_fee_per_gas: 1_000_000_000,
+        to: TxKind::Call(usdt.contract.parse().unwrap()),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: transferCall {
+            to: Address::repeat_byte(0x11),
+            amount: U256::from(1_500_000),
+        }
+        .abi_encode()
+        .into(),
+    };
+    let mut payload = vec![];
+    tx.encode_for_signing(&mut payload);
+    let expected_signature = SoftwareSigner::new()
+        .sign_message(&payload, Network::ETHEREUM, &path)
+        .unwrap();
+
+    // Token information goes first, so device shows ticker and amount of the transfer.
+    let mut exchanges = vec![serde_json::json!({
+        "command": format!("e00a0000{:02x}{}", descriptor.len(), hex::encode(&descriptor)),
+        "response": "009000",
+    })];
+    let sign_commands = apdu::sign_transaction(&path, &payload);
+    for (idx, command) in sign_commands.iter().enumerate() {
+        let response = if idx + 1 == sign_commands.len() {
+            hex::encode(&expected_signature)
+        } else {
+            String::new()
+        };
+        let serialized = APDUCommand {
+            cla: command.cla,
+            ins: command.ins,
+            p1: command.p1,
+            p2: command.p2,
+            data: &command.data[..],
+        }
+        .serialize();
+        exchanges.push(serde_json::json!({
+            "command": hex::encode(serialized),
+            "response": format!("{}9000", response),
+        }));
+    }
+    let fixture = serde_json::json!({ "exchanges": exchanges });
+    let transport = Arc::new(ReplayTransport::new(&fixture.to_string()));
+    let device = Device::new_test(transport.clone());
+
+    provide_token_info(&device, &usdt, &url).await.unwrap();
+    let signature = sign_message(payload, &device, &path).await.unwrap();
+
+    assert_eq!(signature, expected_signature);
+    transport.assert_finished();
+}
+
+#[ignore = "manual test"]
+#[tokio::test]
+async fn test_ethereum_sign_message() {
+    use futures::StreamExt;
+
+    use crate::api::{
+        common_types::{DerivationScheme, Network},
+        ledger::{LedgerApi, LedgerApiT},
+    };
+
+    pretty_env_logger::formatted_timed_builder()
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let ledger_api = LedgerApi::new(Default::default()).await;
+    let device = &ledger_api.discover_devices().await[0];
+
+    log::info!("Open an ethereum app on the connected ledger device");
+
+    ledger_api
+        .open_app(device, Network::ETHEREUM, &Default::default())
+        .await
+        .expect("Failed to open ethereum app");
+
+    let scheme = &DerivationScheme::builtin(Network::ETHEREUM)[0];
+    let account = ledger_api
+        .discover_accounts(device, Network::ETHEREUM, scheme)
+        .await
+        .next()
+        .await
+        .expect("No accounts derived")
+        .expect("Failed to derive an account");
+
+    let tx = hex::decode(
+        "eb808509502f900082520894423163e58aabec5daa3dd1130b759d24bef0f6ea8711c37937e0800080018080",
+    )
+    .expect("Invalid hex string");
+
+    let signature = ledger_api
+        .sign_message(tx.clone(), device, Network::ETHEREUM, &account)
+        .await
+        .expect("Failed to sign message");
+
+    log::info!("tx: {}", hex::encode(&tx));
+    log::info!("signature: {}", hex::encode(&signature));
+}
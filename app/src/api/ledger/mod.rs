@@ -1,76 +1,211 @@
-use std::{hash::Hash, time::Duration};
+use std::{hash::Hash, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
+use alloy::dyn_abi::TypedData;
 use async_trait::async_trait;
-use ledger_apdu::{APDUCommand, APDUErrorCode};
+use futures::{
+    StreamExt,
+    stream::{self, BoxStream},
+};
 use ledger_transport_hid::{
     TransportNativeHID,
     hidapi::{DeviceInfo as LedgerDeviceInfo, HidApi},
 };
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
 
-use super::common_types::{Account, Network};
+use super::common_types::{Account, DerivationPath, DerivationScheme, Network, NetworkKind, Token};
 
+mod apdu;
 mod bitcoin_app;
+mod device_info;
+mod error;
 mod ethereum_app;
+mod software_signer;
+mod transport;
+
+pub use device_info::{AppInfo, DeviceInfo};
+pub use error::LedgerError;
+pub use ethereum_app::EthereumSignature;
+#[cfg(test)]
+pub use software_signer::SoftwareSigner;
+
+/// Entry points of the fuzz targets in `fuzz/`, which compile this module tree by path.
+#[cfg(fuzzing)]
+pub mod fuzzing {
+    pub use super::{bitcoin_app::fuzzing as bitcoin, ethereum_app::fuzzing as ethereum};
+}
 
-const DELAY_BEFORE_ACCOUNTS_DISCOVERY: Duration = Duration::from_secs(1);
+use device_info::{DASHBOARD_APP_NAME, get_dashboard_info, get_running_app, model_name};
+use transport::{ApduRecorder, RecordingTransport, SpeculosTransport, Transport, TransportError};
+
+const APP_STATE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_OPEN_APP_TIMEOUT_SECS: u64 = 30;
+/// BIP44 stops accounts discovery at the first unused account.
+const DEFAULT_ACCOUNT_DISCOVERY_GAP_LIMIT: usize = 1;
 
 #[async_trait]
 pub trait LedgerApiT: Send + Sync + 'static {
     async fn discover_devices(&self) -> Vec<Device>;
 
-    async fn get_device_info(&self, device: &Device) -> Option<DeviceInfo>;
-
-    async fn open_app(&self, device: &Device, network: Network) -> ();
-
-    // TODO: Return stream of accounts?
-    async fn discover_accounts(&self, device: &Device, network: Network) -> Vec<Account>;
-
-    // TODO: Accept `account: Account` argument here.
-    #[allow(dead_code)] // TODO: Remove.
-    async fn sign_message(&self, message: Vec<u8>, device: &Device, network: Network) -> Vec<u8>;
+    /// Queries model, firmware version and the running app from the device.
+    async fn get_device_info(&self, device: &Device) -> Result<DeviceInfo, LedgerError>;
+
+    /// Opens the app of the network and waits until device reports it as running,
+    /// so it's ready to process requests once this call returns. Waiting stops with
+    /// an error when timeout configured elapses or `cancel` is triggered.
+    async fn open_app(
+        &self,
+        device: &Device,
+        network: Network,
+        cancel: &CancellationToken,
+    ) -> Result<(), LedgerError>;
+
+    /// Lazily derives accounts of the derivation scheme with increasing indices starting
+    /// from 0. Stream ends if scheme has no more accounts or right after device fails
+    /// to derive one.
+    async fn discover_accounts(
+        &self,
+        device: &Device,
+        network: Network,
+        scheme: &DerivationScheme,
+    ) -> BoxStream<'static, Result<Account, LedgerError>>;
+
+    async fn sign_message(
+        &self,
+        message: Vec<u8>,
+        device: &Device,
+        network: Network,
+        account: &Account,
+    ) -> Result<Vec<u8>, LedgerError>;
+
+    /// Shows receive address of the account on the device screen and returns it
+    /// once user confirms it.
+    async fn display_address(
+        &self,
+        device: &Device,
+        network: Network,
+        account: &Account,
+    ) -> Result<String, LedgerError>;
+
+    /// Signs message according to EIP-191 (`personal_sign`) with ethereum account.
+    async fn sign_personal_message(
+        &self,
+        message: Vec<u8>,
+        device: &Device,
+        account: &Account,
+    ) -> Result<EthereumSignature, LedgerError>;
+
+    /// Signs EIP-712 typed data with ethereum account.
+    async fn sign_typed_data(
+        &self,
+        typed_data: &TypedData,
+        device: &Device,
+        account: &Account,
+    ) -> Result<EthereumSignature, LedgerError>;
+
+    /// Makes the device clear-sign transfer of the token that is signed next.
+    /// Should be called right before `sign_message`.
+    async fn provide_token_info(&self, device: &Device, token: &Token) -> Result<(), LedgerError>;
 }
 
-#[derive(Clone, Debug)]
-pub struct Device(DeviceInner);
+/// Connected ledger device. Exchanges with it are recorded if recorder is present.
+#[derive(Clone)]
+pub struct Device(DeviceInner, Option<Arc<ApduRecorder>>);
 
 impl Device {
     fn new(info: LedgerDeviceInfo) -> Self {
-        Self(DeviceInner::Device(info))
+        Self(DeviceInner::Hid(info), None)
+    }
+
+    fn new_speculos(config: SpeculosConfig) -> Self {
+        Self(DeviceInner::Speculos(config), None)
     }
 
     fn new_mock(id: usize) -> Self {
-        Self(DeviceInner::Mock(id))
+        Self(DeviceInner::Mock(id), None)
     }
 
-    fn get_info(&self) -> Option<&LedgerDeviceInfo> {
-        match &self.0 {
-            DeviceInner::Mock(_) => None,
-            DeviceInner::Device(info) => Some(info),
+    /// Device communicating over the given transport, e.g. replaying APDU fixture.
+    #[cfg(test)]
+    fn new_test(transport: Arc<dyn Transport>) -> Self {
+        Self(DeviceInner::Test(transport), None)
+    }
+
+    fn with_recorder(self, recorder: Option<Arc<ApduRecorder>>) -> Self {
+        Self(self.0, recorder)
+    }
+
+    fn open_transport(&self) -> Result<Box<dyn Transport>, TransportError> {
+        let transport: Box<dyn Transport> = match &self.0 {
+            DeviceInner::Mock(_) => panic!("Expected non-mock device"),
+            DeviceInner::Hid(info) => {
+                let hid_api = HidApi::new().map_err(|e| TransportError::Hid(e.into()))?;
+                Box::new(TransportNativeHID::open_device(&hid_api, info)?)
+            }
+            DeviceInner::Speculos(config) => Box::new(SpeculosTransport::open(config.address)?),
+            #[cfg(test)]
+            DeviceInner::Test(transport) => Box::new(transport.clone()),
+        };
+
+        match &self.1 {
+            Some(recorder) => Ok(Box::new(RecordingTransport::new(
+                transport,
+                recorder.clone(),
+            ))),
+            None => Ok(transport),
         }
     }
 
     fn get_mock_id(&self) -> Option<usize> {
         match &self.0 {
             DeviceInner::Mock(id) => Some(*id),
-            DeviceInner::Device(_) => None,
+            _ => None,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+impl std::fmt::Debug for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Clone)]
 enum DeviceInner {
     Mock(usize),
-    Device(LedgerDeviceInfo),
+    Hid(LedgerDeviceInfo),
+    Speculos(SpeculosConfig),
+    #[cfg(test)]
+    Test(Arc<dyn Transport>),
+}
+
+impl std::fmt::Debug for DeviceInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mock(id) => f.debug_tuple("Mock").field(id).finish(),
+            Self::Hid(info) => f.debug_tuple("Hid").field(info).finish(),
+            Self::Speculos(config) => f.debug_tuple("Speculos").field(config).finish(),
+            #[cfg(test)]
+            Self::Test(_) => f.debug_tuple("Test").finish(),
+        }
+    }
 }
 
 impl PartialEq for Device {
     fn eq(&self, other: &Self) -> bool {
         match (&self.0, &other.0) {
             (DeviceInner::Mock(self_id), DeviceInner::Mock(other_id)) => self_id == other_id,
-            (DeviceInner::Device(self_info), DeviceInner::Device(other_info)) => {
+            (DeviceInner::Hid(self_info), DeviceInner::Hid(other_info)) => {
                 self_info.path() == other_info.path()
                     && self_info.serial_number() == other_info.serial_number()
             }
+            (DeviceInner::Speculos(self_config), DeviceInner::Speculos(other_config)) => {
+                self_config.address == other_config.address
+            }
+            #[cfg(test)]
+            (DeviceInner::Test(self_transport), DeviceInner::Test(other_transport)) => {
+                Arc::ptr_eq(self_transport, other_transport)
+            }
             _ => false,
         }
     }
@@ -82,24 +217,98 @@ impl Hash for Device {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match &self.0 {
             DeviceInner::Mock(id) => id.hash(state),
-            DeviceInner::Device(info) => {
+            DeviceInner::Hid(info) => {
                 info.path().hash(state);
                 info.serial_number().hash(state);
             }
+            DeviceInner::Speculos(config) => config.address.hash(state),
+            #[cfg(test)]
+            DeviceInner::Test(transport) => Arc::as_ptr(transport).hash(state),
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct DeviceInfo {
-    pub model: String,
+pub struct LedgerApi {
+    config: Config,
+    recorder: Option<Arc<ApduRecorder>>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Config {
+    /// Emulated devices that will be listed alongside with the ones connected over HID.
+    #[serde(default)]
+    pub speculos: Vec<SpeculosConfig>,
+    /// Number of unused accounts in a row after which accounts discovery stops.
+    #[serde(default = "default_account_discovery_gap_limit")]
+    pub account_discovery_gap_limit: usize,
+    /// Derivation schemes that are available in addition to the builtin ones.
+    #[serde(default)]
+    pub derivation_schemes: Vec<CustomDerivationScheme>,
+    /// Tokens that are tracked in addition to the builtin ones. Builtin token with
+    /// the same contract is replaced.
+    #[serde(default)]
+    pub tokens: Vec<Token>,
+    /// Base URL of the Ledger crypto assets list signed token information is fetched from.
+    #[serde(default = "default_crypto_assets_url")]
+    pub crypto_assets_url: String,
+    /// Time given to the user to confirm opening an app on the device.
+    #[serde(default = "default_open_app_timeout_secs")]
+    pub open_app_timeout_secs: u64,
+    /// File APDU exchanges with devices are recorded to. Recorded file can be
+    /// used as a test fixture.
+    #[serde(default)]
+    pub record_apdu_to: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            speculos: vec![],
+            account_discovery_gap_limit: default_account_discovery_gap_limit(),
+            derivation_schemes: vec![],
+            tokens: vec![],
+            crypto_assets_url: default_crypto_assets_url(),
+            open_app_timeout_secs: default_open_app_timeout_secs(),
+            record_apdu_to: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CustomDerivationScheme {
+    pub network: Network,
+    #[serde(flatten)]
+    pub scheme: DerivationScheme,
+}
+
+fn default_account_discovery_gap_limit() -> usize {
+    DEFAULT_ACCOUNT_DISCOVERY_GAP_LIMIT
 }
 
-pub struct LedgerApi {}
+fn default_crypto_assets_url() -> String {
+    ethereum_app::CRYPTO_ASSETS_URL.to_string()
+}
+
+fn default_open_app_timeout_secs() -> u64 {
+    DEFAULT_OPEN_APP_TIMEOUT_SECS
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpeculosConfig {
+    /// Address of the emulator APDU server(`--apdu-port` option of speculos).
+    pub address: SocketAddr,
+    /// Emulated device model, e.g. `Nano S+` or `Nano X`.
+    pub model: String,
+}
 
 impl LedgerApi {
-    pub async fn new() -> Self {
-        Self {}
+    pub async fn new(config: Config) -> Self {
+        let recorder = config.record_apdu_to.clone().map(|path| {
+            log::info!("Recording APDU exchanges to {}", path.display());
+            Arc::new(ApduRecorder::new(path, "Recorded from ledger device"))
+        });
+
+        Self { config, recorder }
     }
 }
 
@@ -108,130 +317,301 @@ impl LedgerApiT for LedgerApi {
     async fn discover_devices(&self) -> Vec<Device> {
         log::info!("Discovering connected ledger devices...");
 
-        let hid_api = HidApi::new().unwrap();
-        let devices = TransportNativeHID::list_ledgers(&hid_api);
-        let devices: Vec<_> = devices.cloned().map(Device::new).collect();
+        let mut devices = match HidApi::new() {
+            Ok(hid_api) => TransportNativeHID::list_ledgers(&hid_api)
+                .cloned()
+                .map(Device::new)
+                .collect(),
+            Err(e) => {
+                log::error!("Failed to initialize HID api: {}", e);
+                vec![]
+            }
+        };
+
+        let emulated_devices = self
+            .config
+            .speculos
+            .iter()
+            .filter(|config| SpeculosTransport::is_reachable(config.address))
+            .cloned()
+            .map(Device::new_speculos);
+        devices.extend(emulated_devices);
+
+        let devices: Vec<_> = devices
+            .into_iter()
+            .map(|device| device.with_recorder(self.recorder.clone()))
+            .collect();
 
         log::info!("Discovered {} connected ledger devices", devices.len());
 
         devices
     }
 
-    async fn get_device_info(&self, device: &Device) -> Option<DeviceInfo> {
-        let model = device
-            .get_info()
-            .expect("Expected non-mock device")
-            .product_string()
-            .map(|s| s.to_string())
-            .unwrap_or_default();
+    async fn get_device_info(&self, device: &Device) -> Result<DeviceInfo, LedgerError> {
+        let reported_model = match &device.0 {
+            DeviceInner::Mock(_) => panic!("Expected non-mock device"),
+            DeviceInner::Hid(info) => info
+                .product_string()
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+            DeviceInner::Speculos(config) => config.model.clone(),
+            #[cfg(test)]
+            DeviceInner::Test(_) => "Test".to_string(),
+        };
 
-        Some(DeviceInfo { model })
-    }
+        let transport = device.open_transport()?;
 
-    async fn open_app(&self, device: &Device, network: Network) -> () {
-        let device_info = device.get_info().expect("Expected non-mock device");
-        let hid_api = HidApi::new().unwrap();
-        let transport = TransportNativeHID::open_device(&hid_api, device_info).unwrap();
+        let running_app = get_running_app(transport.as_ref())?;
 
-        let data = match network {
-            Network::Bitcoin => "Bitcoin",
-            Network::Ethereum => "Ethereum",
-        }
-        .as_bytes();
-
-        let command = APDUCommand {
-            cla: 0xE0,
-            ins: 0xD8,
-            p1: 0x00,
-            p2: 0x00,
-            data,
+        // Target id and firmware version can be queried only from the dashboard.
+        let (target_id, firmware_version, running_app) = if running_app.name == DASHBOARD_APP_NAME {
+            let (target_id, firmware_version) = get_dashboard_info(transport.as_ref())?;
+            (Some(target_id), Some(firmware_version), None)
+        } else {
+            (None, None, Some(running_app))
         };
 
-        let response = transport.exchange(&command).unwrap();
+        let model = target_id
+            .and_then(model_name)
+            .map(|name| name.to_string())
+            .unwrap_or(reported_model);
+        let model = match &device.0 {
+            DeviceInner::Speculos(_) => format!("{} (Speculos)", model),
+            _ => model,
+        };
 
-        match response.error_code() {
-            Err(e) => {
-                log::error!("Error received from ledegr device: {:#x}", e);
+        Ok(DeviceInfo {
+            model,
+            target_id,
+            firmware_version,
+            running_app,
+        })
+    }
+
+    async fn open_app(
+        &self,
+        device: &Device,
+        network: Network,
+        cancel: &CancellationToken,
+    ) -> Result<(), LedgerError> {
+        let app_name = network.get_info().ledger_app;
+
+        let timeout = Duration::from_secs(self.config.open_app_timeout_secs);
+
+        tokio::select! {
+            result = tokio::time::timeout(timeout, open_app(device, app_name)) => {
+                result.map_err(|_| {
+                    log::error!("Timed out waiting for {} app to open", app_name);
+                    LedgerError::Timeout
+                })?
             }
-            Ok(APDUErrorCode::NoError) => {}
-            Ok(e) => {
-                log::error!("Error received from ledegr device: {}", e);
+            _ = cancel.cancelled() => {
+                log::info!("Opening of {} app cancelled", app_name);
+                Err(LedgerError::Cancelled)
             }
         }
+    }
 
-        ()
+    async fn discover_accounts(
+        &self,
+        device: &Device,
+        network: Network,
+        scheme: &DerivationScheme,
+    ) -> BoxStream<'static, Result<Account, LedgerError>> {
+        let device = device.clone();
+        let scheme = scheme.clone();
+        stream::unfold(Some(0), move |index| {
+            let device = device.clone();
+            let path = index.and_then(|index| Some((index, scheme.path(index)?)));
+            async move {
+                let (index, path) = path?;
+                let account = match network.kind() {
+                    NetworkKind::Bitcoin => bitcoin_app::get_account(&device, &path).await,
+                    NetworkKind::Ethereum => {
+                        ethereum_app::get_account(&device, &path, evm_chain_id(network)).await
+                    }
+                };
+
+                // Subsequent accounts will most likely fail to derive the same way.
+                let next_index = account.is_ok().then_some(index + 1);
+
+                Some((account, next_index))
+            }
+        })
+        .boxed()
     }
 
-    async fn discover_accounts(&self, device: &Device, network: Network) -> Vec<Account> {
-        // TODO: It's a workaround of a problem that ledger disconnects after `open_app` request
-        // and so maintainionhg connection to it is impossible, so we try to reconnect to it
-        // after some delay.
-        tokio::time::sleep(DELAY_BEFORE_ACCOUNTS_DISCOVERY).await;
+    async fn sign_message(
+        &self,
+        message: Vec<u8>,
+        device: &Device,
+        network: Network,
+        account: &Account,
+    ) -> Result<Vec<u8>, LedgerError> {
+        let path = account_derivation_path(account, network);
+
+        match network.kind() {
+            NetworkKind::Bitcoin => bitcoin_app::sign_message(message, device, &path).await,
+            NetworkKind::Ethereum => ethereum_app::sign_message(message, device, &path).await,
+        }
+    }
 
-        match network {
-            Network::Bitcoin => bitcoin_app::discover_accounts(device).await,
-            Network::Ethereum => ethereum_app::discover_accounts(device).await,
+    async fn display_address(
+        &self,
+        device: &Device,
+        network: Network,
+        account: &Account,
+    ) -> Result<String, LedgerError> {
+        let path = account_derivation_path(account, network);
+
+        match network.kind() {
+            NetworkKind::Bitcoin => bitcoin_app::display_address(device, &path).await,
+            NetworkKind::Ethereum => {
+                ethereum_app::display_address(device, &path, evm_chain_id(network)).await
+            }
         }
     }
 
-    async fn sign_message(&self, message: Vec<u8>, device: &Device, network: Network) -> Vec<u8> {
-        // TODO: It's a workaround of a problem that ledger disconnects after `open_app` request
-        // and so maintainionhg connection to it is impossible, so we try to reconnect to it
-        // after some delay.
-        tokio::time::sleep(DELAY_BEFORE_ACCOUNTS_DISCOVERY).await;
+    async fn sign_personal_message(
+        &self,
+        message: Vec<u8>,
+        device: &Device,
+        account: &Account,
+    ) -> Result<EthereumSignature, LedgerError> {
+        let path = account_derivation_path(account, Network::ETHEREUM);
+
+        ethereum_app::sign_personal_message(message, device, &path).await
+    }
+
+    async fn sign_typed_data(
+        &self,
+        typed_data: &TypedData,
+        device: &Device,
+        account: &Account,
+    ) -> Result<EthereumSignature, LedgerError> {
+        let path = account_derivation_path(account, Network::ETHEREUM);
+
+        ethereum_app::sign_typed_data(typed_data, device, &path).await
+    }
+
+    async fn provide_token_info(&self, device: &Device, token: &Token) -> Result<(), LedgerError> {
+        ethereum_app::provide_token_info(device, token, &self.config.crypto_assets_url).await
+    }
+}
+
+/// Switches device to the app and waits until it's running. Device re-enumerates
+/// when switching apps, so it's reconnected to for every request.
+async fn open_app(device: &Device, app_name: &str) -> Result<(), LedgerError> {
+    let running_app = wait_for_app(device, |_| true).await?;
+    if running_app == app_name {
+        return Ok(());
+    }
+
+    // Device rejects request to open an app if some other app is already open.
+    if running_app != DASHBOARD_APP_NAME {
+        log::info!("Closing {} app", running_app);
+
+        apdu::quit_app().send(device.open_transport()?.as_ref())?;
 
-        match network {
-            Network::Bitcoin => bitcoin_app::sign_message(message, device).await,
-            Network::Ethereum => ethereum_app::sign_message(message, device).await,
+        wait_for_app(device, |name| name == DASHBOARD_APP_NAME).await?;
+    }
+
+    log::info!("Opening {} app", app_name);
+
+    apdu::open_app(app_name).send(device.open_transport()?.as_ref())?;
+
+    wait_for_app(device, |name| name == app_name).await?;
+
+    Ok(())
+}
+
+/// Polls device until the running app satisfies `is_expected` and returns its name.
+/// Device is unreachable for a while when switching apps, so transport errors are
+/// treated as app not being ready yet.
+async fn wait_for_app(
+    device: &Device,
+    is_expected: impl Fn(&str) -> bool,
+) -> Result<String, LedgerError> {
+    loop {
+        let running_app = device
+            .open_transport()
+            .map_err(|e| LedgerError::Transport(e.to_string()))
+            .and_then(|transport| get_running_app(transport.as_ref()));
+
+        match running_app {
+            Ok(app) if is_expected(&app.name) => return Ok(app.name),
+            Ok(_) | Err(LedgerError::Transport(_)) => {}
+            Err(e) => return Err(e),
         }
+
+        tokio::time::sleep(APP_STATE_POLL_INTERVAL).await;
+    }
+}
+
+/// Device replaying APDU fixture `app/fixtures/apdu/<name>.json`. If `RECORD_APDU_FIXTURES`
+/// is set to the address of Speculos APDU server, fixture is recorded from the emulator
+/// instead and the returned transport has nothing to replay.
+#[cfg(test)]
+fn replay_device(name: &str) -> (Device, Arc<transport::ReplayTransport>) {
+    let path = format!("{}/fixtures/apdu/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+
+    if let Ok(address) = std::env::var("RECORD_APDU_FIXTURES") {
+        let recorder = ApduRecorder::new(
+            path.into(),
+            "Recorded from Speculos for BIP39 test mnemonic `abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon about`",
+        );
+        let device = Device::new_speculos(SpeculosConfig {
+            address: address.parse().expect("Invalid Speculos address"),
+            model: "Speculos".to_string(),
+        })
+        .with_recorder(Some(Arc::new(recorder)));
+        let transport = transport::ReplayTransport::new(r#"{"exchanges": []}"#);
+
+        return (device, Arc::new(transport));
     }
+
+    let fixture = std::fs::read_to_string(&path).expect("APDU fixture is not found");
+    let transport = Arc::new(transport::ReplayTransport::new(&fixture));
+
+    (Device::new_test(transport.clone()), transport)
+}
+
+fn account_derivation_path(account: &Account, network: Network) -> DerivationPath {
+    // Accounts stored before derivation paths were introduced have no path,
+    // but all of them were derived with the default one.
+    account
+        .derivation_path
+        .clone()
+        .unwrap_or_else(|| DerivationScheme::default_path(network))
+}
+
+fn evm_chain_id(network: Network) -> u64 {
+    network
+        .chain_id()
+        .expect("Networks of ethereum kind should have chain id")
 }
 
 pub mod mock {
-    use std::{collections::HashMap, sync::Mutex};
+    use std::sync::Mutex;
 
-    use super::*;
+    use super::{software_signer::SoftwareSigner, *};
 
+    /// Ledger api backed by software signer with keys of the test mnemonic, so accounts
+    /// and signatures it returns are real.
     pub struct LedgerApiMock {
         devices: Vec<Device>,
-        accounts: HashMap<Network, Vec<Account>>,
+        account_count: usize,
+        signer: SoftwareSigner,
         open_app: Mutex<Option<Network>>,
     }
 
     impl LedgerApiMock {
         pub fn new(device_count: usize, account_count: usize) -> Self {
-            let repeat_accounts = |pattern: Vec<&str>| -> Vec<Account> {
-                std::iter::repeat(pattern)
-                    .flatten()
-                    .take(account_count)
-                    .map(|acc| Account {
-                        public_key: acc.into(),
-                    })
-                    .collect()
-            };
-
-            let mut accounts = HashMap::new();
-
-            let btc_accounts = repeat_accounts(vec![
-                "0x0123456789012345678901234567890101234567890123456789012345678901",
-                "0x9876543210987654321098765432109876543210987654321098765432109876",
-                "0x0000001111111112222222223333333333344444444445555555555666666666",
-                "0x1230000000000000000000000000000000000000000000000000000000000321",
-                "0x1111000000000000000000000000000000000000000000000000000000001111",
-            ]);
-            accounts.insert(Network::Bitcoin, btc_accounts);
-
-            let eth_accounts = repeat_accounts(vec![
-                "0x1234567891011121123456789101112112345678",
-                "0x1111111111111111111111111111111111111111",
-                "0x3333333333333333333333333333333333333333",
-                "0x5555555555555555555555555555555555555555",
-            ]);
-            accounts.insert(Network::Ethereum, eth_accounts);
-
             Self {
                 devices: (0..device_count).map(Device::new_mock).collect(),
-                accounts,
+                account_count,
+                signer: SoftwareSigner::new(),
                 open_app: Mutex::new(None),
             }
         }
@@ -243,47 +623,121 @@ pub mod mock {
             self.devices.clone()
         }
 
-        async fn get_device_info(&self, device: &Device) -> Option<DeviceInfo> {
+        async fn get_device_info(&self, device: &Device) -> Result<DeviceInfo, LedgerError> {
             let id = device.get_mock_id().expect("Expected mock device");
 
             let info = match id % 3 {
                 0 => DeviceInfo {
                     model: "Nano S".to_string(),
+                    target_id: Some(0x31100004),
+                    firmware_version: Some("2.1.0".to_string()),
+                    running_app: None,
                 },
                 1 => DeviceInfo {
                     model: "Nano S+".to_string(),
+                    target_id: None,
+                    firmware_version: None,
+                    running_app: Some(AppInfo {
+                        name: "Ethereum".to_string(),
+                        version: "1.10.4".to_string(),
+                    }),
                 },
                 2 => DeviceInfo {
                     model: "Nano X".to_string(),
+                    target_id: None,
+                    firmware_version: None,
+                    running_app: Some(AppInfo {
+                        name: "Bitcoin".to_string(),
+                        version: "2.0.6".to_string(),
+                    }),
                 },
                 _ => unreachable!(),
             };
 
-            Some(info)
+            Ok(info)
         }
 
-        async fn open_app(&self, _device: &Device, network: Network) {
+        async fn open_app(
+            &self,
+            _device: &Device,
+            network: Network,
+            _cancel: &CancellationToken,
+        ) -> Result<(), LedgerError> {
             *self.open_app.lock().unwrap() = Some(network);
+
+            Ok(())
         }
 
-        async fn discover_accounts(&self, _device: &Device, network: Network) -> Vec<Account> {
+        async fn discover_accounts(
+            &self,
+            _device: &Device,
+            network: Network,
+            scheme: &DerivationScheme,
+        ) -> BoxStream<'static, Result<Account, LedgerError>> {
             assert_eq!(*self.open_app.lock().unwrap(), Some(network));
 
-            self.accounts
-                .get(&network)
-                .cloned()
-                .into_iter()
-                .flatten()
-                .collect()
+            let accounts: Vec<_> = (0..)
+                .map_while(|index| scheme.path(index))
+                .take(self.account_count)
+                .map(|path| self.signer.get_account(network, &path))
+                .collect();
+
+            stream::iter(accounts).boxed()
         }
 
         async fn sign_message(
             &self,
-            _message: Vec<u8>,
+            message: Vec<u8>,
+            _device: &Device,
+            network: Network,
+            account: &Account,
+        ) -> Result<Vec<u8>, LedgerError> {
+            self.signer.sign_message(
+                &message,
+                network,
+                &account_derivation_path(account, network),
+            )
+        }
+
+        async fn display_address(
+            &self,
+            _device: &Device,
+            network: Network,
+            account: &Account,
+        ) -> Result<String, LedgerError> {
+            Ok(account.receive_address(network))
+        }
+
+        async fn sign_personal_message(
+            &self,
+            message: Vec<u8>,
+            _device: &Device,
+            account: &Account,
+        ) -> Result<EthereumSignature, LedgerError> {
+            self.signer.sign_personal_message(
+                &message,
+                &account_derivation_path(account, Network::ETHEREUM),
+            )
+        }
+
+        async fn sign_typed_data(
+            &self,
+            typed_data: &TypedData,
+            _device: &Device,
+            account: &Account,
+        ) -> Result<EthereumSignature, LedgerError> {
+            self.signer.sign_typed_data(
+                typed_data,
+                &account_derivation_path(account, Network::ETHEREUM),
+            )
+        }
+
+        async fn provide_token_info(
+            &self,
             _device: &Device,
-            _network: Network,
-        ) -> Vec<u8> {
-            todo!()
+            _token: &Token,
+        ) -> Result<(), LedgerError> {
+            Ok(())
         }
     }
 }
@@ -0,0 +1,441 @@
+//! Signer holding keys derived from a fixed seed in memory. It derives accounts and signs
+//! the same data ledger apps do, so the mock api produces real addresses and signatures.
+
+use alloy::{
+    dyn_abi::TypedData,
+    primitives::B256,
+    signers::{Signature, SignerSync, local::PrivateKeySigner},
+};
+use bitcoin::{
+    PublicKey, TxOut,
+    bip32::{ChildNumber, ExtendedPrivKey, ExtendedPubKey, Fingerprint, KeySource},
+    ecdsa,
+    key::{KeyPair, TapTweak},
+    psbt::Psbt,
+    secp256k1::{All, Message, Secp256k1, SecretKey},
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    taproot,
+};
+
+use crate::api::common_types::{Account, DerivationPath, Network, NetworkKind};
+
+use super::{EthereumSignature, LedgerError, bitcoin_app, ethereum_app};
+
+/// Seed of BIP39 test mnemonic `abandon abandon abandon abandon abandon abandon abandon
+/// abandon abandon abandon abandon about`, APDU fixtures are made with it as well.
+const TEST_SEED: &str = "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4";
+
+/// Offset ethereum app adds to the y-parity of message signatures.
+const MESSAGE_SIGNATURE_V_OFFSET: u8 = 27;
+
+pub struct SoftwareSigner {
+    master_key: ExtendedPrivKey,
+    secp: Secp256k1<All>,
+}
+
+impl SoftwareSigner {
+    pub fn new() -> Self {
+        let seed = hex::decode(TEST_SEED).expect("Test seed is valid hex");
+
+        Self {
+            master_key: ExtendedPrivKey::new_master(bitcoin::Network::Bitcoin, &seed)
+                .expect("Test seed is valid"),
+            secp: Secp256k1::new(),
+        }
+    }
+
+    pub fn get_account(
+        &self,
+        network: Network,
+        path: &DerivationPath,
+    ) -> Result<Account, LedgerError> {
+        match network.kind() {
+            NetworkKind::Bitcoin => {
+                let account_key = ExtendedPrivKey {
+                    network: network.bitcoin_network(),
+                    ..self.derive(&bitcoin_app::account_path(path))?
+                };
+                let xpub = ExtendedPubKey::from_priv(&self.secp, &account_key);
+
+                bitcoin_app::account_from_xpub(&xpub, path)
+            }
+            NetworkKind::Ethereum => {
+                let public_key = self
+                    .derive(path)?
+                    .private_key
+                    .public_key(&self.secp)
+                    .serialize_uncompressed();
+
+                Ok(Account {
+                    public_key: ethereum_app::address_of_public_key(&public_key)?,
+                    derivation_path: Some(path.clone()),
+                    xpub: None,
+                    uncompressed_public_key: Some(hex::encode(public_key)),
+                })
+            }
+        }
+    }
+
+    /// Signs RLP encoded ethereum transaction or bitcoin PSBT, result has the same
+    /// encoding as the one returned by the device.
+    pub fn sign_message(
+        &self,
+        message: &[u8],
+        network: Network,
+        path: &DerivationPath,
+    ) -> Result<Vec<u8>, LedgerError> {
+        match network.kind() {
+            NetworkKind::Bitcoin => self.sign_psbt(message, path),
+            NetworkKind::Ethereum => {
+                let signature = self
+                    .ethereum_signer(path)?
+                    .sign_hash_sync(&alloy::primitives::keccak256(message))
+                    .map_err(signing_failed)?;
+
+                Ok([
+                    &[signature.v().y_parity_byte()][..],
+                    &signature.r().to_be_bytes::<32>(),
+                    &signature.s().to_be_bytes::<32>(),
+                ]
+                .concat())
+            }
+        }
+    }
+
+    pub fn sign_personal_message(
+        &self,
+        message: &[u8],
+        path: &DerivationPath,
+    ) -> Result<EthereumSignature, LedgerError> {
+        let signature = self
+            .ethereum_signer(path)?
+            .sign_message_sync(message)
+            .map_err(signing_failed)?;
+
+        Ok(message_signature(signature))
+    }
+
+    pub fn sign_typed_data(
+        &self,
+        typed_data: &TypedData,
+        path: &DerivationPath,
+    ) -> Result<EthereumSignature, LedgerError> {
+        let hash: B256 = typed_data.eip712_signing_hash().map_err(|e| {
+            log::error!("Invalid EIP-712 typed data: {}", e);
+            LedgerError::InvalidData
+        })?;
+        let signature = self
+            .ethereum_signer(path)?
+            .sign_hash_sync(&hash)
+            .map_err(signing_failed)?;
+
+        Ok(message_signature(signature))
+    }
+
+    /// Signs inputs with key origins under the account of the path and returns the PSBT
+    /// with signatures added. Origins with zero fingerprint are treated as the ones of
+    /// the master key, the same way the device does.
+    fn sign_psbt(&self, psbt: &[u8], path: &DerivationPath) -> Result<Vec<u8>, LedgerError> {
+        let mut psbt = Psbt::deserialize(psbt).map_err(|e| {
+            log::error!("Failed to deserialize PSBT: {}", e);
+            LedgerError::InvalidData
+        })?;
+
+        let fingerprint = self.master_key.fingerprint(&self.secp);
+        let account_path = bitcoin_app::account_path(path);
+        let account_children: Vec<_> = account_path
+            .components()
+            .iter()
+            .copied()
+            .map(ChildNumber::from)
+            .collect();
+        // Key of the account input is derived with, if it's under the account.
+        let key = |(source_fingerprint, key_path): &KeySource| -> Result<_, LedgerError> {
+            let own_fingerprint =
+                *source_fingerprint == fingerprint || *source_fingerprint == Fingerprint::default();
+            if !own_fingerprint || !key_path.as_ref().starts_with(&account_children) {
+                return Ok(None);
+            }
+
+            Ok(Some(self.derive_children(key_path.as_ref())?.private_key))
+        };
+
+        let tx = psbt.unsigned_tx.clone();
+        let mut cache = SighashCache::new(&tx);
+        let prevouts: Vec<TxOut> = (0..psbt.inputs.len())
+            .map(|index| psbt.spend_utxo(index).cloned())
+            .collect::<Result<_, _>>()
+            .map_err(|e| {
+                log::error!("PSBT input has no UTXO: {}", e);
+                LedgerError::InvalidData
+            })?;
+
+        for (index, prevout) in prevouts.iter().enumerate() {
+            let input = &psbt.inputs[index];
+
+            if prevout.script_pubkey.is_v1_p2tr() {
+                let Some((internal_key, (_, source))) = input.tap_key_origins.iter().next() else {
+                    continue;
+                };
+                let Some(private_key) = key(source)? else {
+                    continue;
+                };
+                if private_key.x_only_public_key(&self.secp).0 != *internal_key {
+                    log::error!("Key origin of input #{} doesn't match its key", index);
+                    return Err(LedgerError::InvalidData);
+                }
+
+                let sighash = cache
+                    .taproot_key_spend_signature_hash(
+                        index,
+                        &Prevouts::All(&prevouts),
+                        TapSighashType::Default,
+                    )
+                    .map_err(invalid_psbt)?;
+                let key_pair = KeyPair::from_secret_key(&self.secp, &private_key)
+                    .tap_tweak(&self.secp, None)
+                    .to_inner();
+
+                psbt.inputs[index].tap_key_sig = Some(taproot::Signature {
+                    sig: self
+                        .secp
+                        .sign_schnorr_no_aux_rand(&Message::from(sighash), &key_pair),
+                    hash_ty: TapSighashType::Default,
+                });
+            } else {
+                let Some((public_key, source)) = input.bip32_derivation.iter().next() else {
+                    continue;
+                };
+                let public_key = *public_key;
+                let Some(private_key) = key(source)? else {
+                    continue;
+                };
+                if private_key.public_key(&self.secp) != public_key {
+                    log::error!("Key origin of input #{} doesn't match its key", index);
+                    return Err(LedgerError::InvalidData);
+                }
+
+                let (message, hash_ty) = psbt
+                    .sighash_ecdsa(index, &mut cache)
+                    .map_err(invalid_psbt)?;
+
+                psbt.inputs[index].partial_sigs.insert(
+                    PublicKey::new(public_key),
+                    ecdsa::Signature {
+                        sig: self.secp.sign_ecdsa(&message, &private_key),
+                        hash_ty,
+                    },
+                );
+            }
+        }
+
+        Ok(psbt.serialize())
+    }
+
+    fn derive(&self, path: &DerivationPath) -> Result<ExtendedPrivKey, LedgerError> {
+        let path: Vec<_> = path
+            .components()
+            .iter()
+            .copied()
+            .map(ChildNumber::from)
+            .collect();
+
+        self.derive_children(&path)
+    }
+
+    fn derive_children(&self, path: &[ChildNumber]) -> Result<ExtendedPrivKey, LedgerError> {
+        self.master_key
+            .derive_priv(&self.secp, &path)
+            .map_err(|_| LedgerError::UnsupportedDerivationPath)
+    }
+
+    fn ethereum_signer(&self, path: &DerivationPath) -> Result<PrivateKeySigner, LedgerError> {
+        let private_key: SecretKey = self.derive(path)?.private_key;
+
+        PrivateKeySigner::from_slice(&private_key.secret_bytes()).map_err(signing_failed)
+    }
+}
+
+/// Converts signature to the form ethereum app returns for messages and typed data.
+fn message_signature(signature: Signature) -> EthereumSignature {
+    EthereumSignature {
+        v: MESSAGE_SIGNATURE_V_OFFSET + signature.v().y_parity_byte(),
+        r: signature.r().to_be_bytes(),
+        s: signature.s().to_be_bytes(),
+    }
+}
+
+fn signing_failed(error: impl std::fmt::Display) -> LedgerError {
+    log::error!("Failed to sign with software signer: {}", error);
+    LedgerError::InvalidData
+}
+
+fn invalid_psbt(error: impl std::fmt::Display) -> LedgerError {
+    log::error!("Failed to compute PSBT input sighash: {}", error);
+    LedgerError::InvalidData
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use alloy::primitives::Address as EthereumAddress;
+
+    use super::*;
+
+    #[test]
+    fn test_software_signer_accounts_and_signatures() {
+        let signer = SoftwareSigner::new();
+
+        let path = "m/84'/0'/0'/0/0".parse().unwrap();
+        let account = signer.get_account(Network::BITCOIN, &path).unwrap();
+        assert_eq!(
+            account.receive_address(Network::BITCOIN),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+
+        let path = "m/84'/1'/0'/0/0".parse().unwrap();
+        let testnet = "bitcoin-testnet".parse().unwrap();
+        let account = signer.get_account(testnet, &path).unwrap();
+        assert_eq!(
+            account.receive_address(testnet),
+            "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl"
+        );
+        assert!(account.xpub.unwrap().starts_with("tpub"));
+
+        let path = "m/44'/60'/0'/0/0".parse().unwrap();
+        let account = signer.get_account(Network::ETHEREUM, &path).unwrap();
+        assert_eq!(
+            account.public_key,
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        let address: EthereumAddress = account.public_key.parse().unwrap();
+
+        let message = b"Hello from ledger-tui";
+        let signature = signer.sign_personal_message(message, &path).unwrap();
+        let recovered = Signature::try_from(&signature.to_bytes()[..])
+            .unwrap()
+            .recover_address_from_msg(message)
+            .unwrap();
+        assert_eq!(recovered, address);
+    }
+
+    #[tokio::test]
+    async fn test_software_signer_eip1559() {
+        use alloy::{
+            consensus::{SignableTransaction, TxEip1559},
+            primitives::{TxKind, U256},
+            rlp::Decodable,
+        };
+
+        use crate::api::{
+            blockchain_monitoring::{BlockchainMonitoringApiT, mock::BlockchainMonitoringApiMock},
+            common_types::Token,
+        };
+
+        let signer = SoftwareSigner::new();
+        let path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+        let from = signer.get_account(Network::ETHEREUM, &path).unwrap();
+        let to = signer
+            .get_account(Network::ETHEREUM, &"m/44'/60'/1'/0/0".parse().unwrap())
+            .unwrap();
+        let usdt = Token::builtin().remove(0);
+
+        let monitoring = BlockchainMonitoringApiMock::new(0);
+        let transfer = monitoring
+            .prepare_transfer(Network::ETHEREUM, &from, &to, "0.5".parse().unwrap())
+            .await
+            .unwrap();
+        let token_transfer = monitoring
+            .prepare_token_transfer(Network::ETHEREUM, &usdt, &from, &to, "1.5".parse().unwrap())
+            .await
+            .unwrap();
+
+        let to_address: EthereumAddress = to.public_key.parse().unwrap();
+        for (unsigned, receiver, value) in [
+            (transfer, to_address, U256::from(500_000_000_000_000_000u64)),
+            (token_transfer, usdt.contract.parse().unwrap(), U256::ZERO),
+        ] {
+            let tx = TxEip1559::decode(&mut &unsigned.payload[1..]).unwrap();
+            assert_eq!(tx.chain_id, 1);
+            assert_eq!(tx.to, TxKind::Call(receiver));
+            assert_eq!(tx.value, value);
+
+            let signature = signer
+                .sign_message(&unsigned.payload, Network::ETHEREUM, &path)
+                .unwrap();
+            let signature =
+                Signature::try_from(&[&signature[1..], &signature[..1]].concat()[..]).unwrap();
+            let recovered = tx.into_signed(signature).recover_signer().unwrap();
+            assert_eq!(
+                recovered,
+                from.public_key.parse::<EthereumAddress>().unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_software_signer_psbt() {
+        use crate::api::blockchain_monitoring::{
+            BlockchainMonitoringApiT, mock::BlockchainMonitoringApiMock,
+        };
+
+        let signer = SoftwareSigner::new();
+        let secp = Secp256k1::verification_only();
+        let to = signer
+            .get_account(Network::BITCOIN, &"m/84'/0'/1'/0/0".parse().unwrap())
+            .unwrap();
+
+        for path in [
+            "m/84'/0'/0'/0/0",
+            "m/49'/0'/0'/0/0",
+            "m/44'/0'/0'/0/0",
+            "m/86'/0'/0'/0/0",
+        ] {
+            let path: DerivationPath = path.parse().unwrap();
+            let from = signer.get_account(Network::BITCOIN, &path).unwrap();
+
+            let unsigned = BlockchainMonitoringApiMock::new(0)
+                .prepare_transfer(Network::BITCOIN, &from, &to, "0.001".parse().unwrap())
+                .await
+                .unwrap();
+            let signed = signer
+                .sign_message(&unsigned.payload, Network::BITCOIN, &path)
+                .unwrap();
+
+            let psbt = Psbt::deserialize(&signed).unwrap();
+            let mut cache = SighashCache::new(&psbt.unsigned_tx);
+            let input = &psbt.inputs[0];
+            let public_key = PublicKey::from_str(&from.public_key).unwrap();
+
+            if let Some(signature) = input.tap_key_sig {
+                let prevouts = [input.witness_utxo.clone().unwrap()];
+                let sighash = cache
+                    .taproot_key_spend_signature_hash(
+                        0,
+                        &Prevouts::All(&prevouts),
+                        signature.hash_ty,
+                    )
+                    .unwrap();
+                let (output_key, _) = public_key
+                    .inner
+                    .x_only_public_key()
+                    .0
+                    .tap_tweak(&secp, None);
+
+                secp.verify_schnorr(
+                    &signature.sig,
+                    &Message::from(sighash),
+                    &output_key.to_inner(),
+                )
+                .unwrap();
+            } else {
+                let (message, _) = psbt.sighash_ecdsa(0, &mut cache).unwrap();
+                let signature = input.partial_sigs[&public_key];
+
+                secp.verify_ecdsa(&message, &signature.sig, &public_key.inner)
+                    .unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use ledger_apdu::{APDUAnswer, APDUCommand};
+use ledger_transport_hid::TransportNativeHID;
+
+use super::{Transport, TransportError};
+
+impl Transport for TransportNativeHID {
+    fn exchange(
+        &self,
+        command: &APDUCommand<&[u8]>,
+    ) -> Result<APDUAnswer<Vec<u8>>, TransportError> {
+        Ok(TransportNativeHID::exchange(self, command)?)
+    }
+}
@@ -0,0 +1,61 @@
+use std::{fmt, io, sync::Arc};
+
+use ledger_apdu::{APDUAnswer, APDUCommand};
+use ledger_transport_hid::LedgerHIDError;
+
+mod hid;
+mod record;
+mod speculos;
+
+#[cfg(test)]
+pub use record::ReplayTransport;
+pub use record::{ApduRecorder, RecordingTransport};
+pub use speculos::SpeculosTransport;
+
+/// Channel able to deliver APDU commands to a ledger device (either physical or emulated)
+/// and receive responses back.
+pub trait Transport: Send + Sync {
+    fn exchange(&self, command: &APDUCommand<&[u8]>)
+    -> Result<APDUAnswer<Vec<u8>>, TransportError>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn exchange(
+        &self,
+        command: &APDUCommand<&[u8]>,
+    ) -> Result<APDUAnswer<Vec<u8>>, TransportError> {
+        self.as_ref().exchange(command)
+    }
+}
+
+#[derive(Debug)]
+pub enum TransportError {
+    Hid(LedgerHIDError),
+    Tcp(io::Error),
+    ResponseTooShort,
+    /// Peer sent a frame that can't be a response to the command.
+    InvalidResponse(String),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hid(e) => write!(f, "HID transport error: {}", e),
+            Self::Tcp(e) => write!(f, "TCP transport error: {}", e),
+            Self::ResponseTooShort => write!(f, "APDU response is too short"),
+            Self::InvalidResponse(e) => write!(f, "Invalid APDU response: {}", e),
+        }
+    }
+}
+
+impl From<LedgerHIDError> for TransportError {
+    fn from(value: LedgerHIDError) -> Self {
+        Self::Hid(value)
+    }
+}
+
+impl From<io::Error> for TransportError {
+    fn from(value: io::Error) -> Self {
+        Self::Tcp(value)
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use ledger_apdu::{APDUAnswer, APDUCommand};
+use serde::{Deserialize, Serialize};
+
+use super::{Transport, TransportError};
+#[cfg(test)]
+use crate::api::ledger::apdu::MAX_DATA_LENGTH;
+
+/// Length of the command header: CLA, INS, P1, P2 and length of data.
+#[cfg(test)]
+const COMMAND_HEADER_LENGTH: usize = 5;
+
+/// APDU exchanges made with a device during a session, stored as JSON.
+#[derive(Default, Serialize, Deserialize)]
+pub struct ApduFixture {
+    /// How the exchanges were obtained.
+    #[serde(default)]
+    pub description: String,
+    pub exchanges: Vec<ApduExchange>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ApduExchange {
+    /// Hex encoded serialized command.
+    pub command: String,
+    /// Hex encoded response data followed by status word.
+    pub response: String,
+}
+
+/// Collects exchanges made over all the transports of a device and writes them
+/// to the fixture file after every exchange, so the session can be stopped at any time.
+pub struct ApduRecorder {
+    path: PathBuf,
+    fixture: Mutex<ApduFixture>,
+}
+
+impl ApduRecorder {
+    pub fn new(path: PathBuf, description: &str) -> Self {
+        Self {
+            path,
+            fixture: Mutex::new(ApduFixture {
+                description: description.to_string(),
+                exchanges: vec![],
+            }),
+        }
+    }
+
+    fn record(&self, command: &APDUCommand<&[u8]>, answer: &APDUAnswer<Vec<u8>>) {
+        let mut fixture = self.fixture.lock().unwrap();
+        fixture.exchanges.push(ApduExchange {
+            command: hex::encode(command.serialize()),
+            response: hex::encode([answer.data(), &answer.retcode().to_be_bytes()].concat()),
+        });
+
+        let fixture = serde_json::to_string_pretty(&*fixture).expect("Fixture is serializable");
+        if let Err(e) = fs::write(&self.path, fixture) {
+            log::error!(
+                "Failed to write APDU fixture to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Transport passing commands to the inner one and recording every exchange.
+pub struct RecordingTransport {
+    inner: Box<dyn Transport>,
+    recorder: Arc<ApduRecorder>,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Box<dyn Transport>, recorder: Arc<ApduRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn exchange(
+        &self,
+        command: &APDUCommand<&[u8]>,
+    ) -> Result<APDUAnswer<Vec<u8>>, TransportError> {
+        let answer = self.inner.exchange(command)?;
+        self.recorder.record(command, &answer);
+
+        Ok(answer)
+    }
+}
+
+/// Transport serving responses of the fixture in order. Panics if command differs
+/// from the recorded one, so any change in the protocol is caught by tests. Commands
+/// whose data doesn't fit into the length byte are rejected the same way device does it.
+#[cfg(test)]
+pub struct ReplayTransport {
+    exchanges: Mutex<std::collections::VecDeque<ApduExchange>>,
+}
+
+#[cfg(test)]
+impl ReplayTransport {
+    pub fn new(fixture: &str) -> Self {
+        let fixture: ApduFixture = serde_json::from_str(fixture).expect("Invalid APDU fixture");
+
+        for exchange in &fixture.exchanges {
+            let command = hex::decode(&exchange.command).expect("Invalid command in fixture");
+            assert!(
+                command.len() >= COMMAND_HEADER_LENGTH
+                    && command[COMMAND_HEADER_LENGTH - 1] as usize
+                        == command.len() - COMMAND_HEADER_LENGTH,
+                "Length byte of APDU command {} in fixture doesn't match its data",
+                exchange.command
+            );
+        }
+
+        Self {
+            exchanges: Mutex::new(fixture.exchanges.into()),
+        }
+    }
+
+    /// Panics if some of the recorded exchanges weren't replayed.
+    pub fn assert_finished(&self) {
+        let exchanges = self.exchanges.lock().unwrap();
+        if let Some(exchange) = exchanges.front() {
+            panic!(
+                "{} recorded APDU exchanges left, next command: {}",
+                exchanges.len(),
+                exchange.command
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+impl Transport for ReplayTransport {
+    fn exchange(
+        &self,
+        command: &APDUCommand<&[u8]>,
+    ) -> Result<APDUAnswer<Vec<u8>>, TransportError> {
+        assert!(
+            command.data.len() <= MAX_DATA_LENGTH,
+            "APDU command data of {} bytes doesn't fit into its length byte",
+            command.data.len()
+        );
+
+        let command = hex::encode(command.serialize());
+        let exchange = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| panic!("Unexpected APDU command: {}", command));
+
+        assert_eq!(
+            command, exchange.command,
+            "APDU command differs from fixture"
+        );
+
+        let response = hex::decode(&exchange.response).expect("Invalid response in fixture");
+
+        APDUAnswer::from_answer(response).map_err(|_| TransportError::ResponseTooShort)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoTransport;
+
+    impl Transport for EchoTransport {
+        fn exchange(
+            &self,
+            command: &APDUCommand<&[u8]>,
+        ) -> Result<APDUAnswer<Vec<u8>>, TransportError> {
+            let answer = [command.data, &[0x90, 0x00]].concat();
+            APDUAnswer::from_answer(answer).map_err(|_| TransportError::ResponseTooShort)
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join("ledger-tui-test-record-and-replay.json");
+        let recorder = Arc::new(ApduRecorder::new(path.clone(), "Echo"));
+        let transport = RecordingTransport::new(Box::new(EchoTransport), recorder);
+
+        let command = APDUCommand {
+            cla: 0xE0,
+            ins: 0x02,
+            p1: 0x00,
+            p2: 0x00,
+            data: &[0x01, 0x02][..],
+        };
+        transport.exchange(&command).unwrap();
+
+        let replay = ReplayTransport::new(&fs::read_to_string(&path).unwrap());
+        let answer = replay.exchange(&command).unwrap();
+
+        assert_eq!(answer.data(), &[0x01, 0x02]);
+        assert_eq!(answer.retcode(), 0x9000);
+        replay.assert_finished();
+
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+use ledger_apdu::{APDUAnswer, APDUCommand};
+
+use super::{Transport, TransportError};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Emulator responds to requests requiring confirmation only after user confirms them.
+const READ_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const STATUS_WORD_LENGTH: usize = 2;
+/// Responses of ledger apps fit into a single APDU, so anything longer is a broken frame.
+const MAX_RESPONSE_LENGTH: usize = 4096;
+
+/// Transport talking to the APDU server of [Speculos](https://github.com/LedgerHQ/speculos) emulator.
+///
+/// Every command is sent as a 4-byte big endian length followed by serialized APDU. Response is
+/// a 4-byte big endian length of response data followed by data and 2-byte status word.
+pub struct SpeculosTransport {
+    stream: TcpStream,
+}
+
+impl SpeculosTransport {
+    pub fn open(address: SocketAddr) -> Result<Self, TransportError> {
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+        Ok(Self { stream })
+    }
+
+    pub fn is_reachable(address: SocketAddr) -> bool {
+        TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).is_ok()
+    }
+}
+
+impl Transport for SpeculosTransport {
+    fn exchange(
+        &self,
+        command: &APDUCommand<&[u8]>,
+    ) -> Result<APDUAnswer<Vec<u8>>, TransportError> {
+        let mut stream = &self.stream;
+
+        let apdu = command.serialize();
+        let request = [&(apdu.len() as u32).to_be_bytes()[..], &apdu[..]].concat();
+        stream.write_all(&request)?;
+
+        let mut length = [0u8; 4];
+        stream.read_exact(&mut length)?;
+        let length = u32::from_be_bytes(length) as usize;
+        if length > MAX_RESPONSE_LENGTH {
+            return Err(TransportError::InvalidResponse(format!(
+                "response length {} exceeds {}",
+                length, MAX_RESPONSE_LENGTH
+            )));
+        }
+
+        let mut answer = vec![0u8; length + STATUS_WORD_LENGTH];
+        stream.read_exact(&mut answer)?;
+
+        APDUAnswer::from_answer(answer).map_err(|_| TransportError::ResponseTooShort)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+    use crate::api::ledger::LedgerError;
+
+    #[test]
+    fn test_speculos_transport_framing() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            let mut length = [0u8; 4];
+            stream.read_exact(&mut length).unwrap();
+            let mut apdu = vec![0u8; u32::from_be_bytes(length) as usize];
+            stream.read_exact(&mut apdu).unwrap();
+
+            stream
+                .write_all(&[0, 0, 0, 3, 0xAA, 0xBB, 0xCC, 0x90, 0x00])
+                .unwrap();
+
+            apdu
+        });
+
+        let transport = SpeculosTransport::open(address).unwrap();
+        let command = APDUCommand {
+            cla: 0xE0,
+            ins: 0x02,
+            p1: 0x00,
+            p2: 0x00,
+            data: &[0x01, 0x02][..],
+        };
+        let answer = transport.exchange(&command).unwrap();
+
+        assert_eq!(answer.data(), &[0xAA, 0xBB, 0xCC]);
+        assert_eq!(answer.retcode(), 0x9000);
+        assert_eq!(
+            server.join().unwrap(),
+            vec![0xE0, 0x02, 0x00, 0x00, 0x02, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_speculos_transport_rejects_oversized_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(&u32::MAX.to_be_bytes()).unwrap();
+            stream
+        });
+
+        let transport = SpeculosTransport::open(address).unwrap();
+        let command = APDUCommand {
+            cla: 0xE0,
+            ins: 0x02,
+            p1: 0x00,
+            p2: 0x00,
+            data: &[][..],
+        };
+        let result = transport.exchange(&command);
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(TransportError::InvalidResponse(_))));
+        assert_eq!(
+            LedgerError::from(result.unwrap_err()),
+            LedgerError::InvalidResponse
+        );
+    }
+}
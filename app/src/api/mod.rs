@@ -3,4 +3,6 @@ pub mod cache_utils;
 pub mod coin_price;
 pub mod common_types;
 pub mod ledger;
+#[cfg(test)]
+pub mod stand_in;
 pub mod storage;
@@ -0,0 +1,76 @@
+//! Local HTTP server serving canned JSON, APIs talking to HTTP services are tested against it.
+
+use std::sync::Arc;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+/// Starts the server and returns its base URL. `handler` maps path of the request(with query)
+/// and its body to the response body, `None` is answered with 404. Body is usually JSON `Value`,
+/// `String` is served as plain text.
+pub async fn serve<T: ToString>(
+    handler: impl Fn(&str, &str) -> Option<T> + Send + Sync + 'static,
+) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let (reader, mut writer) = stream.into_split();
+                let mut reader = BufReader::new(reader);
+
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    if reader.read_line(&mut header).await.unwrap_or(0) == 0 {
+                        return;
+                    }
+
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                if reader.read_exact(&mut body).await.is_err() {
+                    return;
+                }
+
+                let path = request_line.split(' ').nth(1).unwrap_or_default();
+                let (status, body) = match handler(path, &String::from_utf8_lossy(&body)) {
+                    Some(body) => ("200 OK", body.to_string()),
+                    None => ("404 Not Found", String::new()),
+                };
+
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = writer.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    url
+}
@@ -1,7 +1,13 @@
 use std::{
-    collections::HashMap, fs::read_to_string, io::stdout, marker::PhantomData, time::Duration,
+    collections::HashMap,
+    fs::read_to_string,
+    io::{ErrorKind, stdout},
+    marker::PhantomData,
+    str::FromStr,
+    time::Duration,
 };
 
+use alloy::primitives::Address;
 use ratatui::{
     Terminal,
     backend::{Backend, CrosstermBackend},
@@ -23,8 +29,11 @@ use crate::{
         coin_price::{
             CoinPriceApi, CoinPriceApiT, cache::Cache as CoinPriceApiCache, mock::CoinPriceApiMock,
         },
-        common_types::{Account, Network},
-        ledger::{Device, DeviceInfo, LedgerApi, LedgerApiT, mock::LedgerApiMock},
+        common_types::{Account, DerivationScheme, Network, NetworkKind, Token},
+        ledger::{
+            Config as LedgerApiConfig, Device, DeviceInfo, LedgerApi, LedgerApiT,
+            mock::LedgerApiMock,
+        },
         storage::{StorageApi, StorageApiT, mock::StorageApiMock},
     },
     screen::{OutgoingMessage, Screen, ScreenName, resources::Resources},
@@ -41,6 +50,9 @@ pub(crate) struct StateRegistry {
     pub active_device: Option<(Device, DeviceInfo)>,
     pub device_accounts: Option<DeviceAccountsList>,
     pub selected_account: Option<(Network, Account)>,
+    pub account_discovery_gap_limit: usize,
+    pub derivation_schemes: HashMap<Network, Vec<DerivationScheme>>,
+    pub tokens: Vec<Token>,
     _phantom: PhantomData<()>,
 }
 
@@ -59,11 +71,57 @@ where
 }
 
 impl StateRegistry {
-    fn new() -> StateRegistry {
+    pub(crate) fn new(ledger_api_config: &LedgerApiConfig) -> StateRegistry {
+        let mut derivation_schemes: HashMap<_, _> = Network::all()
+            .map(|network| (network, DerivationScheme::builtin(network)))
+            .collect();
+
+        for custom in &ledger_api_config.derivation_schemes {
+            derivation_schemes
+                .entry(custom.network)
+                .or_default()
+                .push(custom.scheme.clone());
+        }
+
+        let mut tokens = Token::builtin();
+        for custom in &ledger_api_config.tokens {
+            tokens.retain(|token| {
+                token.network != custom.network || token.contract != custom.contract
+            });
+            tokens.push(custom.clone());
+        }
+
         StateRegistry {
             active_device: None,
             device_accounts: None,
             selected_account: None,
+            account_discovery_gap_limit: ledger_api_config.account_discovery_gap_limit,
+            derivation_schemes,
+            tokens,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+impl<L, C, M, S> ApiRegistry<L, C, M, S>
+where
+    L: LedgerApiT,
+    C: CoinPriceApiT,
+    M: BlockchainMonitoringApiT,
+    S: StorageApiT,
+{
+    pub fn new(
+        ledger_api: L,
+        coin_price_api: C,
+        blockchain_monitoring_api: M,
+        storage_api: S,
+    ) -> Self {
+        Self {
+            ledger_api: Some(ledger_api),
+            coin_price_api: Some(coin_price_api),
+            blockchain_monitoring_api: Some(blockchain_monitoring_api),
+            storage_api: Some(storage_api),
             _phantom: PhantomData,
         }
     }
@@ -89,11 +147,13 @@ impl App {
     }
 
     async fn main_loop<B: Backend>(&mut self, mut terminal: Terminal<B>) {
-        let mut state = StateRegistry::new();
+        let ledger_api_config = load_ledger_api_config();
+
+        let mut state = StateRegistry::new(&ledger_api_config);
 
         let mut api_registry = {
             let ledger_api = LedgerApiMock::new(4, 4);
-            let _ledger_api = LedgerApi::new().await;
+            let _ledger_api = LedgerApi::new(ledger_api_config).await;
 
             let coin_price_api = CoinPriceApiMock::new();
             let _coin_price_api = CoinPriceApi::new("https://data-api.binance.vision");
@@ -107,13 +167,23 @@ impl App {
                 .await;
 
             let config = load_blockchain_monitoring_api_config();
-            let _blockchain_monitoring_api = BlockchainMonitoringApi::new(config).await;
+            let _blockchain_monitoring_api =
+                BlockchainMonitoringApi::new(config, StorageApi::new("./data".into())).await;
             let blockchain_monitoring_api = BlockchainMonitoringApiMock::new(4);
             let mut blockchain_monitoring_api =
                 BlockchainMonitoringApiCache::new(blockchain_monitoring_api).await;
             blockchain_monitoring_api
                 .set_all_modes(ModePlan::TimedOut(Duration::from_secs(3)))
                 .await;
+            blockchain_monitoring_api
+                .set_prepare_transfer_mode(ModePlan::Transparent)
+                .await;
+            blockchain_monitoring_api
+                .set_prepare_token_transfer_mode(ModePlan::Transparent)
+                .await;
+            blockchain_monitoring_api
+                .set_send_transaction_mode(ModePlan::Transparent)
+                .await;
 
             let _storage_api = StorageApi::new("./data".into());
             let storage_api = StorageApiMock::new();
@@ -190,6 +260,35 @@ impl App {
     }
 }
 
+fn load_ledger_api_config() -> LedgerApiConfig {
+    let config = match read_to_string("LedgerApiConfig.toml") {
+        Ok(config) => config,
+        Err(e) if e.kind() == ErrorKind::NotFound => return LedgerApiConfig::default(),
+        Err(e) => panic!("Failed to read LedgerApiConfig.toml: {}", e),
+    };
+
+    let config: LedgerApiConfig =
+        toml::from_str(&config).expect("Wrong LedgerApiConfig.toml format");
+
+    // Discovery with zero gap limit would stop before the first account.
+    assert!(
+        config.account_discovery_gap_limit > 0,
+        "account_discovery_gap_limit in LedgerApiConfig.toml should be at least 1"
+    );
+
+    for token in &config.tokens {
+        assert!(
+            token.network.kind() != NetworkKind::Ethereum
+                || Address::from_str(&token.contract).is_ok(),
+            "Invalid contract address of {} token in LedgerApiConfig.toml: {}",
+            token.symbol,
+            token.contract
+        );
+    }
+
+    config
+}
+
 fn load_blockchain_monitoring_api_config() -> BlockchainMonitoringApiConfig {
     let config =
         read_to_string("NetworkApiConfig.toml").expect("Network api config file is not found");
@@ -199,11 +298,9 @@ fn load_blockchain_monitoring_api_config() -> BlockchainMonitoringApiConfig {
 
     let mut network_configs = HashMap::new();
     for (network, network_config) in config {
-        let network = match network.as_str() {
-            "ethereum" => Network::Ethereum,
-            "bitcoin" => Network::Bitcoin,
-            str => panic!("Invalid network name found: {}", str),
-        };
+        let network: Network = network
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid network found in NetworkApiConfig.toml: {}", e));
 
         let config = network_config
             .as_table()
@@ -31,6 +31,10 @@ pub enum InputEvent {
     #[description = "Open send screen"]
     OpenSendScreen,
 
+    #[key = 'g']
+    #[description = "Open message signing screen"]
+    OpenMessageSigningScreen,
+
     SelectTimeInterval(SelectTimeInterval),
 }
 
@@ -72,6 +76,9 @@ pub(super) fn process_input<C: CoinPriceApiT, M: BlockchainMonitoringApiT>(
         InputEvent::Back => Some(OutgoingMessage::Back),
         InputEvent::OpenDepositScreen => Some(OutgoingMessage::SwitchScreen(ScreenName::Deposit)),
         InputEvent::OpenSendScreen => Some(OutgoingMessage::SwitchScreen(ScreenName::Send)),
+        InputEvent::OpenMessageSigningScreen => {
+            Some(OutgoingMessage::SwitchScreen(ScreenName::MessageSigning))
+        }
         InputEvent::SelectTimeInterval(event) => {
             model.selected_time_period = match event {
                 SelectTimeInterval::Day => TimePeriod::Day,
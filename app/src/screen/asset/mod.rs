@@ -7,7 +7,6 @@ use crate::{
     api::{
         blockchain_monitoring::{BlockchainMonitoringApiT, TransactionInfo, TransactionUid},
         coin_price::{Coin, CoinPriceApiT, TimePeriod as ApiTimePeriod},
-        common_types::Network,
         ledger::LedgerApiT,
         storage::StorageApiT,
     },
@@ -77,10 +76,7 @@ impl<C: CoinPriceApiT, M: BlockchainMonitoringApiT> Model<C, M> {
             .expect("Selected account should be present in state")
             .clone(); // TODO: Enforce this rule at `app` level?
 
-        let coin = match selected_network {
-            Network::Bitcoin => Coin::BTC,
-            Network::Ethereum => Coin::ETH,
-        };
+        let coin = selected_network.get_info().coin;
 
         let time_period = match self.selected_time_period {
             TimePeriod::Day => ApiTimePeriod::Day,
@@ -92,9 +88,14 @@ impl<C: CoinPriceApiT, M: BlockchainMonitoringApiT> Model<C, M> {
 
         let spawn_price_history_task = |coin_price_api: C| {
             tokio::task::spawn(async move {
-                let result = coin_price_api
-                    .get_price_history(coin, Coin::USDT, time_period)
-                    .await;
+                let result = match coin {
+                    Some(coin) => {
+                        coin_price_api
+                            .get_price_history(coin, Coin::USDT, time_period)
+                            .await
+                    }
+                    None => None,
+                };
 
                 (coin_price_api, result)
             })
@@ -120,7 +121,9 @@ impl<C: CoinPriceApiT, M: BlockchainMonitoringApiT> Model<C, M> {
                         .get_transaction_info(selected_network, &tx)
                         .await;
 
-                    txs.push((tx, tx_info));
+                    if let Some(tx_info) = tx_info {
+                        txs.push((tx, tx_info));
+                    }
                 }
 
                 (blockchain_monitoring_api, txs)
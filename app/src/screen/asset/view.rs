@@ -40,6 +40,12 @@ pub(super) fn render<C: CoinPriceApiT, M: BlockchainMonitoringApiT>(
 
     frame.render_widget(BackgroundWidget::new(resources.background_color), area);
 
+    let (network, _) = model
+        .state
+        .selected_account
+        .as_ref()
+        .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+
     let [price_chart_area, txs_list_area] = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Fill(1); 2])
@@ -63,8 +69,15 @@ pub(super) fn render<C: CoinPriceApiT, M: BlockchainMonitoringApiT>(
             resources,
         );
     } else {
+        let text = if network.get_info().coin.is_none() {
+            "Test coins have no price"
+        } else {
+            "Price is loading..."
+        };
+
         render_price_chart_placeholder(
             model.selected_time_period,
+            text,
             frame,
             inner_price_chart_area,
             resources,
@@ -104,6 +117,8 @@ pub(super) fn render<C: CoinPriceApiT, M: BlockchainMonitoringApiT>(
         }
     }
 
+    common::render_testnet_marker(*network, frame, resources);
+
     if model.show_navigation_help {
         let mapping = super::controller::InputEvent::get_mapping();
         common::render_navigation_help(mapping, frame, resources);
@@ -152,6 +167,7 @@ fn render_price_chart(
 
 fn render_price_chart_placeholder(
     selected_time_period: TimePeriod,
+    text: &str,
     frame: &mut Frame<'_>,
     area: Rect,
     resources: &Resources,
@@ -166,8 +182,7 @@ fn render_price_chart_placeholder(
 
     frame.render_widget(chart, area);
 
-    let text = Text::raw("Price is loading...");
-    render_centered_text(frame, area, text);
+    render_centered_text(frame, area, Text::raw(text));
 }
 
 fn render_chart_legend(selected_time_period: TimePeriod, resources: &Resources) -> Line<'static> {
@@ -207,6 +222,11 @@ fn render_tx_list(
         .iter()
         .map(|(uid, tx)| {
             let uid = format_address(&uid.uid, TX_UID_MAX_LEN);
+
+            let symbol = match &tx.token {
+                Some(token) => format!(" {}", token),
+                None => network_icon.clone(),
+            };
             let uid = Text::raw(uid).alignment(Alignment::Center);
 
             let time = format!("{}", tx.timestamp.format("%Y-%m-%d %H:%M UTC%:z"));
@@ -221,7 +241,7 @@ fn render_tx_list(
                         Span::raw(from),
                         Span::raw(" -> "),
                         Span::raw(to).fg(resources.accent_color),
-                        Span::raw(format!(" for {}{}", amount, network_icon)),
+                        Span::raw(format!(" for {}{}", amount, symbol)),
                     ]
                 }
                 TransactionType::Withdraw { to, amount } => {
@@ -232,7 +252,7 @@ fn render_tx_list(
                         Span::raw(from).fg(resources.accent_color),
                         Span::raw(" -> "),
                         Span::raw(to),
-                        Span::raw(format!(" for {}{}", amount, network_icon)),
+                        Span::raw(format!(" for {}{}", amount, symbol)),
                     ]
                 }
             };
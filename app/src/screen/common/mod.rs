@@ -20,11 +20,27 @@ pub mod api_task;
 use super::resources::Resources;
 
 pub fn network_symbol(network: Network) -> String {
-    match network {
-        Network::Bitcoin => "₿",
-        Network::Ethereum => "⟠",
+    network.get_info().icon.to_string()
+}
+
+/// Marks screens showing accounts of a test network, so its coins aren't mistaken
+/// for the real ones.
+pub fn render_testnet_marker(network: Network, frame: &mut Frame, resources: &Resources) {
+    if !network.is_testnet() {
+        return;
     }
-    .to_string()
+
+    let text = Text::from(format!(" {} TESTNET ", network.get_info().name))
+        .bold()
+        .fg(resources.background_color)
+        .bg(resources.accent_color);
+
+    let [area] = Layout::horizontal([Constraint::Length(text.width() as u16)])
+        .flex(Flex::End)
+        .areas(frame.area());
+    let [area] = Layout::vertical([Constraint::Length(1)]).areas(area);
+
+    frame.render_widget(text, area);
 }
 
 pub fn render_centered_text(frame: &mut Frame, area: Rect, text: Text) {
@@ -6,7 +6,10 @@ use input_mapping_derive::InputMapping;
 use ratatui::crossterm::event::Event;
 
 use super::Model;
-use crate::screen::OutgoingMessage;
+use crate::{
+    api::{ledger::LedgerApiT, storage::StorageApiT},
+    screen::OutgoingMessage,
+};
 
 #[derive(InputMapping)]
 pub enum InputEvent {
@@ -25,9 +28,20 @@ pub enum InputEvent {
     #[key = 'c']
     #[description = "Copy address to a clipboard"]
     CopyAddress,
+
+    #[key = 'v']
+    #[description = "Verify address on the device"]
+    VerifyAddress,
+
+    #[key = 'n']
+    #[description = "Switch to a new receive address"]
+    NextAddress,
 }
 
-pub(super) fn process_input(event: &Event, model: &mut Model) -> Option<OutgoingMessage> {
+pub(super) async fn process_input<L: LedgerApiT, S: StorageApiT>(
+    event: &Event,
+    model: &mut Model<L, S>,
+) -> Option<OutgoingMessage> {
     let event = InputEvent::map_event(event.clone())?;
 
     match event {
@@ -40,20 +54,23 @@ pub(super) fn process_input(event: &Event, model: &mut Model) -> Option<Outgoing
         InputEvent::CopyAddress => {
             model.last_address_copy = Some(Instant::now());
 
-            let pubkey = model
-                .state
-                .selected_account
-                .as_ref()
-                .expect("Selected account should be present in state") // TODO: Enforce this rule at `app` level?
-                .1
-                .get_info()
-                .public_key;
+            let address = model.receive_address();
 
             let mut ctx = ClipboardContext::new().unwrap();
-            ctx.set_contents(pubkey).unwrap();
+            ctx.set_contents(address).unwrap();
             // It's a bug in `copypasta`. Without calling `get_contents` after `set_contents` clipboard will contain nothing.
             ctx.get_contents().unwrap();
 
+            None
+        }
+        InputEvent::VerifyAddress => {
+            model.verify_address().await;
+
+            None
+        }
+        InputEvent::NextAddress => {
+            model.next_receive_address().await;
+
             None
         }
     }
@@ -1,12 +1,17 @@
-use std::time::Instant;
+use std::{collections::HashMap, time::Instant};
 
+use futures::executor::block_on;
 use ratatui::{Frame, crossterm::event::Event};
+use tokio_util::sync::CancellationToken;
 
-use super::{OutgoingMessage, ScreenT, resources::Resources};
+use super::{OutgoingMessage, ScreenT, common::api_task::ApiTask, resources::Resources};
 use crate::{
     api::{
-        blockchain_monitoring::BlockchainMonitoringApiT, coin_price::CoinPriceApiT,
-        ledger::LedgerApiT, storage::StorageApiT,
+        blockchain_monitoring::BlockchainMonitoringApiT,
+        coin_price::CoinPriceApiT,
+        common_types::{Account, AddressChain, Network},
+        ledger::{LedgerApiT, LedgerError},
+        storage::StorageApiT,
     },
     app::{ApiRegistry, StateRegistry},
 };
@@ -14,53 +19,238 @@ use crate::{
 mod controller;
 mod view;
 
-pub struct Model {
+const RECEIVE_INDICES_STORAGE_NAME: &str = "receive_indices.json";
+
+pub struct Model<L: LedgerApiT, S: StorageApiT> {
     last_address_copy: Option<Instant>,
+    address_verification: Option<AddressVerification>,
     show_navigation_help: bool,
+    /// Index of the current receive address of accounts with xpub, keyed by xpub.
+    receive_indices: HashMap<String, u32>,
 
     state: StateRegistry,
+
+    display_address_task: ApiTask<L, Result<String, LedgerError>>,
+    store_receive_indices_task: ApiTask<S, ()>,
+    cancel_ledger_requests: CancellationToken,
+}
+
+enum AddressVerification {
+    InProgress,
+    Matched,
+    Mismatched { device_address: String },
+    Failed(LedgerError),
 }
 
-impl Model {
-    pub fn construct<
-        L: LedgerApiT,
-        C: CoinPriceApiT,
-        M: BlockchainMonitoringApiT,
-        S: StorageApiT,
-    >(
+impl<L: LedgerApiT, S: StorageApiT> Model<L, S> {
+    pub fn construct<C: CoinPriceApiT, M: BlockchainMonitoringApiT>(
         state: StateRegistry,
-        api_registry: ApiRegistry<L, C, M, S>,
+        mut api_registry: ApiRegistry<L, C, M, S>,
     ) -> (Self, ApiRegistry<L, C, M, S>) {
+        let receive_indices = block_on(
+            api_registry
+                .storage_api
+                .as_mut()
+                .unwrap()
+                .load(RECEIVE_INDICES_STORAGE_NAME),
+        );
+        let receive_indices = match receive_indices {
+            Some(indices) => serde_json::from_str(&indices).unwrap_or_else(|e| {
+                log::error!("Failed to parse receive indices: {}", e);
+                Default::default()
+            }),
+            None => Default::default(),
+        };
+
+        let display_address_task = ApiTask::new(api_registry.ledger_api.take().unwrap());
+        let store_receive_indices_task = ApiTask::new(api_registry.storage_api.take().unwrap());
+
         (
             Self {
                 last_address_copy: None,
+                address_verification: None,
                 show_navigation_help: false,
+                receive_indices,
 
                 state,
+
+                display_address_task,
+                store_receive_indices_task,
+                cancel_ledger_requests: CancellationToken::new(),
             },
             api_registry,
         )
     }
 
-    pub async fn deconstruct<
-        L: LedgerApiT,
-        C: CoinPriceApiT,
-        M: BlockchainMonitoringApiT,
-        S: StorageApiT,
-    >(
+    /// Account of the current receive address. Accounts without xpub have the only
+    /// address, so the account itself is returned for them.
+    fn address_account(&self) -> (Network, Account) {
+        let (network, account) = self
+            .state
+            .selected_account
+            .clone()
+            .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+
+        let address_account = self
+            .receive_index(&account)
+            .and_then(|index| account.derive_address_account(AddressChain::Receive, index));
+
+        (network, address_account.unwrap_or(account))
+    }
+
+    fn receive_index(&self, account: &Account) -> Option<u32> {
+        let xpub = account.xpub.as_ref()?;
+        let index = self
+            .receive_indices
+            .get(xpub)
+            .copied()
+            .unwrap_or_else(|| account.address_index().unwrap_or(0));
+
+        Some(index)
+    }
+
+    fn receive_address(&self) -> String {
+        let (network, account) = self.address_account();
+
+        account.receive_address(network)
+    }
+
+    /// Switches to the next receive address, so the previous ones aren't reused.
+    async fn next_receive_address(&mut self) {
+        let (_, account) = self
+            .state
+            .selected_account
+            .as_ref()
+            .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+
+        let (Some(xpub), Some(index)) = (account.xpub.clone(), self.receive_index(account)) else {
+            return;
+        };
+
+        self.receive_indices.insert(xpub, index + 1);
+        self.address_verification = None;
+
+        let receive_indices = self.receive_indices.clone();
+        let spawn_store_task = |mut storage_api: S| {
+            tokio::task::spawn(async move {
+                let data = serde_json::to_string(&receive_indices).unwrap();
+                storage_api.save(RECEIVE_INDICES_STORAGE_NAME, data).await;
+
+                (storage_api, ())
+            })
+        };
+
+        self.store_receive_indices_task.run(spawn_store_task).await;
+    }
+
+    async fn tick_logic(&mut self) {
+        if let Some(device_address) = self.display_address_task.try_fetch_value().await {
+            let verification = match device_address {
+                // Ethereum addresses may differ in checksum casing.
+                Ok(device_address)
+                    if device_address.eq_ignore_ascii_case(&self.receive_address()) =>
+                {
+                    AddressVerification::Matched
+                }
+                Ok(device_address) => AddressVerification::Mismatched { device_address },
+                Err(error) => AddressVerification::Failed(error),
+            };
+
+            self.address_verification = Some(verification);
+        }
+    }
+
+    async fn verify_address(&mut self) {
+        if matches!(
+            self.address_verification,
+            Some(AddressVerification::InProgress)
+        ) {
+            return;
+        }
+
+        let (network, account) = self.address_account();
+        let device = self
+            .state
+            .active_device
+            .clone()
+            .expect("Active device should be present in state") // TODO: Enforce this rule at `app` level?
+            .0;
+
+        let cancel = self.cancel_ledger_requests.clone();
+        let spawn_task = |ledger_api: L| {
+            tokio::task::spawn(async move {
+                let device_address = async {
+                    ledger_api.open_app(&device, network, &cancel).await?;
+                    ledger_api.display_address(&device, network, &account).await
+                }
+                .await;
+
+                (ledger_api, device_address)
+            })
+        };
+
+        self.display_address_task.run(spawn_task).await;
+        self.address_verification = Some(AddressVerification::InProgress);
+    }
+
+    pub async fn deconstruct<C: CoinPriceApiT, M: BlockchainMonitoringApiT>(
         self,
-        api_registry: ApiRegistry<L, C, M, S>,
+        mut api_registry: ApiRegistry<L, C, M, S>,
     ) -> (StateRegistry, ApiRegistry<L, C, M, S>) {
+        self.cancel_ledger_requests.cancel();
+        api_registry.ledger_api = Some(self.display_address_task.abort().await);
+        api_registry.storage_api = Some(self.store_receive_indices_task.abort().await);
+
         (self.state, api_registry)
     }
 }
 
-impl ScreenT for Model {
+impl<L: LedgerApiT, S: StorageApiT> ScreenT for Model<L, S> {
     fn render(&self, frame: &mut Frame<'_>, resources: &Resources) {
         view::render(self, frame, resources);
     }
 
     async fn tick(&mut self, event: Option<Event>) -> Option<OutgoingMessage> {
-        controller::process_input(event.as_ref()?, self)
+        self.tick_logic().await;
+
+        controller::process_input(event.as_ref()?, self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{
+        blockchain_monitoring::mock::BlockchainMonitoringApiMock,
+        coin_price::mock::CoinPriceApiMock,
+        common_types::bip84_test_account,
+        ledger::{Config as LedgerApiConfig, mock::LedgerApiMock},
+        storage::mock::StorageApiMock,
+    };
+
+    #[tokio::test]
+    async fn test_corrupted_receive_indices_are_ignored() {
+        let mut storage_api = StorageApiMock::new();
+        storage_api
+            .save(RECEIVE_INDICES_STORAGE_NAME, "not json".to_string())
+            .await;
+
+        let account = bip84_test_account(0);
+        let mut state = StateRegistry::new(&LedgerApiConfig::default());
+        state.selected_account = Some((Network::BITCOIN, account.clone()));
+        let api_registry = ApiRegistry::new(
+            LedgerApiMock::new(1, 1),
+            CoinPriceApiMock::new(),
+            BlockchainMonitoringApiMock::new(0),
+            storage_api,
+        );
+
+        let (model, _) = Model::construct(state, api_registry);
+
+        assert!(model.receive_indices.is_empty());
+        assert_eq!(
+            model.receive_address(),
+            account.receive_address(Network::BITCOIN)
+        );
     }
 }
@@ -11,30 +11,30 @@ use ratatui::{
     widgets::{Block, BorderType, Borders, Padding, Widget},
 };
 
-use crate::screen::{
-    common::{self, BackgroundWidget},
-    resources::Resources,
+use crate::{
+    api::{ledger::LedgerApiT, storage::StorageApiT},
+    screen::{
+        common::{self, BackgroundWidget},
+        resources::Resources,
+    },
 };
 
-use super::Model;
+use super::{AddressVerification, Model};
 
 const DISPLAY_COPIED_TEXT_FOR: Duration = Duration::from_secs(2);
 
-pub(super) fn render(model: &Model, frame: &mut Frame<'_>, resources: &Resources) {
+pub(super) fn render<L: LedgerApiT, S: StorageApiT>(
+    model: &Model<L, S>,
+    frame: &mut Frame<'_>,
+    resources: &Resources,
+) {
     let area = frame.area();
 
     frame.render_widget(BackgroundWidget::new(resources.background_color), area);
 
-    let pubkey = model
-        .state
-        .selected_account
-        .as_ref()
-        .expect("Selected account should be present in state") // TODO: Enforce this rule at `app` level?
-        .1
-        .get_info()
-        .public_key;
+    let address = model.receive_address();
 
-    let address_text = Text::raw(&pubkey)
+    let address_text = Text::raw(&address)
         .alignment(Alignment::Center)
         .fg(resources.main_color);
 
@@ -51,24 +51,68 @@ pub(super) fn render(model: &Model, frame: &mut Frame<'_>, resources: &Resources
     }
     .alignment(Alignment::Center);
 
+    let (network, account) = model
+        .state
+        .selected_account
+        .as_ref()
+        .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+    let address_index_text = match model.receive_index(account) {
+        Some(index) => Text::raw(format!(
+            "receive address #{}, press `n` for a new one",
+            index
+        )),
+        None => Text::raw(""),
+    }
+    .alignment(Alignment::Center)
+    .fg(resources.main_color);
+
+    let verification_text = match &model.address_verification {
+        None => Text::raw("press `v` to verify on device").fg(resources.main_color),
+        Some(AddressVerification::InProgress) => {
+            Text::raw("confirm address on the device").fg(resources.accent_color)
+        }
+        Some(AddressVerification::Matched) => {
+            Text::raw("address matches the one on the device").fg(resources.main_color)
+        }
+        Some(AddressVerification::Mismatched { device_address }) => {
+            Text::raw(format!("ADDRESS MISMATCH, device shows {}", device_address))
+                .fg(resources.accent_color)
+        }
+        Some(AddressVerification::Failed(error)) => {
+            Text::raw(error.to_string()).fg(resources.accent_color)
+        }
+    }
+    .alignment(Alignment::Center);
+
     let [qr_code_area, address_with_description_area] =
         Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
 
-    let [address_area, description_area] = Layout::vertical([
+    let [
+        address_area,
+        address_index_area,
+        description_area,
+        verification_area,
+    ] = Layout::vertical([
         Constraint::Length(address_text.height() as u16),
+        Constraint::Length(address_index_text.height() as u16),
         Constraint::Length(description_text.height() as u16),
+        Constraint::Length(verification_text.height() as u16),
     ])
     .flex(Flex::Center)
     .areas(address_with_description_area);
 
-    let qr_code = QrCodeWidget::new(pubkey.clone())
+    let qr_code = QrCodeWidget::new(address.clone())
         .size(QrCodeSize::Small)
         .dark_color(resources.qr_code_dark_color)
         .light_color(resources.qr_code_light_color);
 
     frame.render_widget(qr_code, qr_code_area);
     frame.render_widget(address_text, address_area);
+    frame.render_widget(address_index_text, address_index_area);
     frame.render_widget(description_text, description_area);
+    frame.render_widget(verification_text, verification_area);
+
+    common::render_testnet_marker(*network, frame, resources);
 
     if model.show_navigation_help {
         let mapping = super::controller::InputEvent::get_mapping();
@@ -68,13 +68,14 @@ pub(super) async fn process_input<L: LedgerApiT>(
             None
         }
         InputEvent::Select => {
-            if let Some(device_idx) = model.selected_device {
-                model.state.active_device = Some(model.devices[device_idx].clone());
+            let device_idx = model.selected_device?;
+            let (device, Ok(info)) = &model.devices[device_idx] else {
+                return None;
+            };
 
-                Some(OutgoingMessage::Back)
-            } else {
-                None
-            }
+            model.state.active_device = Some((device.clone(), info.clone()));
+
+            Some(OutgoingMessage::Back)
         }
         InputEvent::Refresh => {
             model.refresh_device_list().await;
@@ -5,7 +5,7 @@ use crate::{
     api::{
         blockchain_monitoring::BlockchainMonitoringApiT,
         coin_price::CoinPriceApiT,
-        ledger::{Device, DeviceInfo, LedgerApiT},
+        ledger::{Device, DeviceInfo, LedgerApiT, LedgerError},
         storage::StorageApiT,
     },
     app::{ApiRegistry, StateRegistry},
@@ -14,14 +14,17 @@ use crate::{
 mod controller;
 mod view;
 
+type DeviceList = Vec<(Device, Result<DeviceInfo, LedgerError>)>;
+
 pub struct Model<L: LedgerApiT> {
-    devices: Vec<(Device, DeviceInfo)>,
+    /// Devices that failed to report info are listed too, so user knows what's wrong with them.
+    devices: DeviceList,
     selected_device: Option<usize>,
     show_navigation_help: bool,
 
     state: StateRegistry,
 
-    device_list_refresh_task: ApiTask<L, Vec<(Device, DeviceInfo)>>,
+    device_list_refresh_task: ApiTask<L, DeviceList>,
 }
 
 impl<L: LedgerApiT> Model<L> {
@@ -71,9 +74,7 @@ impl<L: LedgerApiT> Model<L> {
 
                 for device in devices {
                     let info = ledger_api.get_device_info(&device).await;
-                    if let Some(info) = info {
-                        devices_with_info.push((device, info));
-                    }
+                    devices_with_info.push((device, info));
                 }
 
                 log::info!("Discovered {} ledger devices", devices_with_info.len());
@@ -2,14 +2,14 @@ use input_mapping_common::InputMappingT;
 use ratatui::{
     Frame,
     layout::{Alignment, Margin, Rect},
-    style::Stylize,
-    text::Text,
+    style::{Color, Stylize},
+    text::{Line, Text},
     widgets::{Block, BorderType, Borders, List, Padding},
 };
 
 use super::{Model, controller};
 use crate::{
-    api::ledger::{Device, DeviceInfo, LedgerApiT},
+    api::ledger::{Device, DeviceInfo, LedgerApiT, LedgerError},
     screen::{
         common::{self, BackgroundWidget, render_centered_text},
         resources::Resources,
@@ -62,7 +62,7 @@ fn render_device_list_placeholder(frame: &mut Frame<'_>, area: Rect) {
 }
 
 fn render_device_list(
-    devices: &[(Device, DeviceInfo)],
+    devices: &[(Device, Result<DeviceInfo, LedgerError>)],
     selected_device: Option<usize>,
     frame: &mut Frame<'_>,
     area: Rect,
@@ -70,11 +70,25 @@ fn render_device_list(
 ) {
     let mut list_height = 0;
     let list = List::new(devices.iter().enumerate().map(|(idx, (_, info))| {
-        let label = format!("Ledger {}", info.model);
+        let is_selected = Some(idx) == selected_device;
 
-        let item = Text::centered(label.into());
+        // Selected item is highlighted with accent color, so warnings can't use it.
+        let warning_color = if is_selected {
+            resources.background_color
+        } else {
+            resources.accent_color
+        };
+
+        let item = match info {
+            Ok(info) => device_description(info, warning_color),
+            Err(error) => Text::from(vec![
+                Line::from("Ledger device"),
+                Line::from(error.to_string()).fg(warning_color),
+            ]),
+        }
+        .centered();
 
-        let item = if Some(idx) == selected_device {
+        let item = if is_selected {
             item.bold()
                 .bg(resources.accent_color)
                 .fg(resources.background_color)
@@ -92,3 +106,27 @@ fn render_device_list(
 
     frame.render_widget(list, area);
 }
+
+fn device_description<'a>(info: &DeviceInfo, warning_color: Color) -> Text<'a> {
+    let mut lines = vec![Line::from(format!("Ledger {}", info.model))];
+
+    if let (Some(firmware_version), Some(target_id)) = (&info.firmware_version, info.target_id) {
+        lines.push(Line::from(format!(
+            "firmware {} (target {:#010x})",
+            firmware_version, target_id
+        )));
+    }
+
+    if let Some(app) = &info.running_app {
+        lines.push(Line::from(format!("{} app {}", app.name, app.version)));
+
+        if let Some(min_version) = app.outdated_by() {
+            lines.push(
+                Line::from(format!("app is outdated, update it to {}+", min_version))
+                    .fg(warning_color),
+            );
+        }
+    }
+
+    Text::from(lines)
+}
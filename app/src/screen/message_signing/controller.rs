@@ -0,0 +1,79 @@
+use std::time::Instant;
+
+use copypasta::{ClipboardContext, ClipboardProvider};
+use input_mapping_common::InputMappingT;
+use input_mapping_derive::InputMapping;
+use ratatui::crossterm::event::{Event, KeyCode};
+
+use super::{Model, SignStatus};
+use crate::{api::ledger::LedgerApiT, screen::OutgoingMessage};
+
+#[derive(InputMapping)]
+pub enum InputEvent {
+    #[key = 'q']
+    #[description = "Quit application"]
+    Quit,
+
+    #[key = 'h']
+    #[description = "Open/close navigation help"]
+    NavigationHelp,
+
+    #[key = 'b']
+    #[description = "Return one screen back"]
+    Back,
+
+    #[key = 'p']
+    #[description = "Paste a path to message or typed data JSON file"]
+    PasteFilePath,
+
+    #[key = "KeyCode::Enter"]
+    #[description = "Sign the file contents"]
+    Sign,
+
+    #[key = 'c']
+    #[description = "Copy signature to a clipboard"]
+    CopySignature,
+}
+
+pub(super) async fn process_input<L: LedgerApiT>(
+    event: &Event,
+    model: &mut Model<L>,
+) -> Option<OutgoingMessage> {
+    let event = InputEvent::map_event(event.clone())?;
+
+    match event {
+        InputEvent::Quit => Some(OutgoingMessage::Exit),
+        InputEvent::NavigationHelp => {
+            model.show_navigation_help ^= true;
+            None
+        }
+        InputEvent::Back => Some(OutgoingMessage::Back),
+        InputEvent::PasteFilePath => {
+            let mut ctx = ClipboardContext::new().unwrap();
+            let file_path = ctx.get_contents().unwrap();
+
+            model.file_path = Some(file_path.trim().to_string());
+
+            None
+        }
+        InputEvent::Sign => {
+            model.sign().await;
+
+            None
+        }
+        InputEvent::CopySignature => {
+            let Some(SignStatus::Signed(signature)) = &model.sign_status else {
+                return None;
+            };
+
+            model.last_signature_copy = Some(Instant::now());
+
+            let mut ctx = ClipboardContext::new().unwrap();
+            ctx.set_contents(signature.to_string()).unwrap();
+            // It's a bug in `copypasta`. Without calling `get_contents` after `set_contents` clipboard will contain nothing.
+            ctx.get_contents().unwrap();
+
+            None
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::Instant;
+
+use alloy::dyn_abi::TypedData;
+use ratatui::{Frame, crossterm::event::Event};
+use tokio_util::sync::CancellationToken;
+
+use super::{OutgoingMessage, ScreenT, common::api_task::ApiTask, resources::Resources};
+use crate::{
+    api::{
+        blockchain_monitoring::BlockchainMonitoringApiT,
+        coin_price::CoinPriceApiT,
+        common_types::NetworkKind,
+        ledger::{EthereumSignature, LedgerApiT, LedgerError},
+        storage::StorageApiT,
+    },
+    app::{ApiRegistry, StateRegistry},
+};
+
+mod controller;
+mod view;
+
+pub struct Model<L: LedgerApiT> {
+    file_path: Option<String>,
+    sign_status: Option<SignStatus>,
+    last_signature_copy: Option<Instant>,
+    show_navigation_help: bool,
+
+    state: StateRegistry,
+
+    sign_task: ApiTask<L, Result<EthereumSignature, SignError>>,
+    cancel_ledger_requests: CancellationToken,
+}
+
+enum SignStatus {
+    InProgress,
+    Signed(EthereumSignature),
+    Failed(SignError),
+}
+
+enum SignError {
+    UnsupportedNetwork,
+    ReadFile(String),
+    Ledger(LedgerError),
+}
+
+/// File is signed as EIP-712 typed data if it contains one in JSON
+/// and as `personal_sign` message otherwise.
+enum Payload {
+    PersonalMessage(Vec<u8>),
+    TypedData(Box<TypedData>),
+}
+
+impl Payload {
+    fn parse(content: Vec<u8>) -> Self {
+        match serde_json::from_slice(&content) {
+            Ok(typed_data) => Self::TypedData(Box::new(typed_data)),
+            Err(_) => Self::PersonalMessage(content),
+        }
+    }
+}
+
+impl<L: LedgerApiT> Model<L> {
+    pub fn construct<C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT>(
+        state: StateRegistry,
+        mut api_registry: ApiRegistry<L, C, M, S>,
+    ) -> (Self, ApiRegistry<L, C, M, S>) {
+        let sign_task = ApiTask::new(api_registry.ledger_api.take().unwrap());
+
+        (
+            Self {
+                file_path: None,
+                sign_status: None,
+                last_signature_copy: None,
+                show_navigation_help: false,
+
+                state,
+
+                sign_task,
+                cancel_ledger_requests: CancellationToken::new(),
+            },
+            api_registry,
+        )
+    }
+
+    async fn tick_logic(&mut self) {
+        if let Some(signature) = self.sign_task.try_fetch_value().await {
+            self.sign_status = Some(match signature {
+                Ok(signature) => SignStatus::Signed(signature),
+                Err(error) => SignStatus::Failed(error),
+            });
+        }
+    }
+
+    pub async fn sign(&mut self) {
+        if matches!(self.sign_status, Some(SignStatus::InProgress)) {
+            return;
+        }
+
+        let Some(file_path) = self.file_path.clone() else {
+            return;
+        };
+
+        let (network, account) = self
+            .state
+            .selected_account
+            .clone()
+            .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+        let device = self
+            .state
+            .active_device
+            .clone()
+            .expect("Active device should be present in state") // TODO: Enforce this rule at `app` level?
+            .0;
+
+        if network.kind() != NetworkKind::Ethereum {
+            self.sign_status = Some(SignStatus::Failed(SignError::UnsupportedNetwork));
+            return;
+        }
+
+        let cancel = self.cancel_ledger_requests.clone();
+        let spawn_task = |ledger_api: L| {
+            tokio::task::spawn(async move {
+                let signature = async {
+                    let content = tokio::fs::read(&file_path)
+                        .await
+                        .map_err(|e| SignError::ReadFile(e.to_string()))?;
+
+                    ledger_api
+                        .open_app(&device, network, &cancel)
+                        .await
+                        .map_err(SignError::Ledger)?;
+
+                    match Payload::parse(content) {
+                        Payload::PersonalMessage(message) => {
+                            ledger_api
+                                .sign_personal_message(message, &device, &account)
+                                .await
+                        }
+                        Payload::TypedData(typed_data) => {
+                            ledger_api
+                                .sign_typed_data(&typed_data, &device, &account)
+                                .await
+                        }
+                    }
+                    .map_err(SignError::Ledger)
+                }
+                .await;
+
+                (ledger_api, signature)
+            })
+        };
+
+        self.sign_task.run(spawn_task).await;
+        self.sign_status = Some(SignStatus::InProgress);
+    }
+
+    pub async fn deconstruct<C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT>(
+        self,
+        mut api_registry: ApiRegistry<L, C, M, S>,
+    ) -> (StateRegistry, ApiRegistry<L, C, M, S>) {
+        self.cancel_ledger_requests.cancel();
+        api_registry.ledger_api = Some(self.sign_task.abort().await);
+
+        (self.state, api_registry)
+    }
+}
+
+impl<L: LedgerApiT> ScreenT for Model<L> {
+    fn render(&self, frame: &mut Frame<'_>, resources: &Resources) {
+        view::render(self, frame, resources);
+    }
+
+    async fn tick(&mut self, event: Option<Event>) -> Option<OutgoingMessage> {
+        self.tick_logic().await;
+
+        controller::process_input(event.as_ref()?, self).await
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use input_mapping_common::InputMappingT;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout},
+    style::Stylize,
+    text::Text,
+};
+
+use crate::{
+    api::ledger::LedgerApiT,
+    screen::{
+        common::{self, BackgroundWidget},
+        resources::Resources,
+    },
+};
+
+use super::{Model, SignError, SignStatus};
+
+const DISPLAY_COPIED_TEXT_FOR: Duration = Duration::from_secs(2);
+
+pub(super) fn render<L: LedgerApiT>(
+    model: &Model<L>,
+    frame: &mut Frame<'_>,
+    resources: &Resources,
+) {
+    let area = frame.area();
+
+    frame.render_widget(BackgroundWidget::new(resources.background_color), area);
+
+    let file_path = if let Some(file_path) = &model.file_path {
+        Text::from(&**file_path).fg(resources.main_color)
+    } else {
+        Text::from("paste path to a message or typed data JSON file [p]").fg(resources.accent_color)
+    };
+    let file_path_label = Text::from("file:").fg(resources.main_color);
+
+    let display_copied_text = if let Some(last_copy) = model.last_signature_copy {
+        last_copy.elapsed() <= DISPLAY_COPIED_TEXT_FOR
+    } else {
+        false
+    };
+
+    let (sign_status, sign_description) = match &model.sign_status {
+        None => (
+            Text::from(""),
+            Text::from("press `enter` to sign").fg(resources.main_color),
+        ),
+        Some(SignStatus::InProgress) => (
+            Text::from("confirm signing on the device").fg(resources.accent_color),
+            Text::from(""),
+        ),
+        Some(SignStatus::Signed(signature)) => (
+            Text::from(signature.to_string()).fg(resources.main_color),
+            if display_copied_text {
+                Text::from("copied!").fg(resources.accent_color)
+            } else {
+                Text::from("press `c` to copy").fg(resources.main_color)
+            },
+        ),
+        Some(SignStatus::Failed(error)) => {
+            let error = match error {
+                SignError::UnsupportedNetwork => {
+                    "only ethereum accounts can sign messages".to_string()
+                }
+                SignError::ReadFile(error) => format!("failed to read file: {}", error),
+                SignError::Ledger(error) => error.to_string(),
+            };
+
+            (
+                Text::from(error).fg(resources.accent_color),
+                Text::from("press `enter` to retry").fg(resources.main_color),
+            )
+        }
+    };
+
+    let [
+        file_path_label_area,
+        file_path_area,
+        _,
+        sign_status_area,
+        sign_description_area,
+    ] = Layout::vertical([
+        Constraint::Length(file_path_label.height() as u16),
+        Constraint::Length(file_path.height() as u16),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .flex(Flex::Center)
+    .areas(area);
+
+    frame.render_widget(file_path_label.centered(), file_path_label_area);
+    frame.render_widget(file_path.centered(), file_path_area);
+    frame.render_widget(sign_status.centered(), sign_status_area);
+    frame.render_widget(sign_description.centered(), sign_description_area);
+
+    if let Some((network, _)) = &model.state.selected_account {
+        common::render_testnet_marker(*network, frame, resources);
+    }
+
+    if model.show_navigation_help {
+        let mapping = super::controller::InputEvent::get_mapping();
+        common::render_navigation_help(mapping, frame, resources);
+    }
+}
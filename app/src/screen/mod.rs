@@ -13,6 +13,7 @@ pub mod asset;
 mod common;
 pub mod deposit;
 pub mod device_selection;
+pub mod message_signing;
 pub mod portfolio;
 pub mod resources;
 pub mod send;
@@ -25,10 +26,11 @@ pub struct Screen<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT,
 #[allow(clippy::large_enum_variant)]
 enum ScreenModel<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT> {
     Asset(asset::Model<C, M>),
-    Deposit(deposit::Model),
+    Deposit(deposit::Model<L, S>),
     DeviceSelection(device_selection::Model<L>),
+    MessageSigning(message_signing::Model<L>),
     Portfolio(portfolio::Model<L, C, M, S>),
-    Send(send::Model<L>),
+    Send(send::Model<L, M>),
 }
 
 impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT>
@@ -63,6 +65,14 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
                     model: ScreenModel::DeviceSelection(model),
                 }
             }
+            ScreenName::MessageSigning => {
+                let (model, remaining_apis) =
+                    message_signing::Model::construct(state_registry, api_registry);
+                Self {
+                    remaining_apis,
+                    model: ScreenModel::MessageSigning(model),
+                }
+            }
             ScreenName::Portfolio => {
                 let (model, remaining_apis) =
                     portfolio::Model::construct(state_registry, api_registry);
@@ -86,6 +96,7 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
             ScreenModel::Asset(screen) => screen.render(frame, resources),
             ScreenModel::Deposit(screen) => screen.render(frame, resources),
             ScreenModel::DeviceSelection(screen) => screen.render(frame, resources),
+            ScreenModel::MessageSigning(screen) => screen.render(frame, resources),
             ScreenModel::Portfolio(screen) => screen.render(frame, resources),
             ScreenModel::Send(screen) => screen.render(frame, resources),
         }
@@ -96,6 +107,7 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
             ScreenModel::Asset(screen) => screen.tick(event).await,
             ScreenModel::Deposit(screen) => screen.tick(event).await,
             ScreenModel::DeviceSelection(screen) => screen.tick(event).await,
+            ScreenModel::MessageSigning(screen) => screen.tick(event).await,
             ScreenModel::Portfolio(screen) => screen.tick(event).await,
             ScreenModel::Send(screen) => screen.tick(event).await,
         }
@@ -106,6 +118,7 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
             ScreenModel::Asset(model) => model.deconstruct(self.remaining_apis).await,
             ScreenModel::Deposit(model) => model.deconstruct(self.remaining_apis).await,
             ScreenModel::DeviceSelection(model) => model.deconstruct(self.remaining_apis).await,
+            ScreenModel::MessageSigning(model) => model.deconstruct(self.remaining_apis).await,
             ScreenModel::Portfolio(model) => model.deconstruct(self.remaining_apis).await,
             ScreenModel::Send(model) => model.deconstruct(self.remaining_apis).await,
         }
@@ -131,4 +144,5 @@ pub enum ScreenName {
     Asset,
     Deposit,
     Send,
+    MessageSigning,
 }
@@ -38,6 +38,10 @@ pub enum InputEvent {
     #[key = "KeyCode::Enter"]
     #[description = "Select account"]
     Select,
+
+    #[key = 's']
+    #[description = "Switch derivation scheme used for accounts discovery"]
+    SwitchDerivationScheme,
 }
 
 pub(super) async fn process_input<
@@ -69,6 +73,16 @@ pub(super) async fn process_input<
         .as_ref()
         .expect("TODO: Enforce this rule at app level?");
 
+    if matches!(event, InputEvent::SwitchDerivationScheme) {
+        if let Some(network_idx) = model.selected_network {
+            if let Some(&(network, _)) = accounts.get(network_idx) {
+                model.switch_derivation_scheme(network);
+            }
+        }
+
+        return None;
+    }
+
     if matches!(event, InputEvent::Select) {
         if let (Some(network_idx), Some(account_idx)) =
             (model.selected_network, model.selected_account)
@@ -1,18 +1,20 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use bigdecimal::BigDecimal;
-use futures::{executor::block_on, future::join_all};
+use futures::{StreamExt, executor::block_on, future::join_all, stream::BoxStream};
 use itertools::Itertools;
 use ratatui::{Frame, crossterm::event::Event};
 use rust_decimal::Decimal;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio_util::sync::CancellationToken;
 
 use super::{OutgoingMessage, ScreenT, common::api_task::ApiTask, resources::Resources};
 use crate::{
     api::{
         blockchain_monitoring::BlockchainMonitoringApiT,
         coin_price::{Coin, CoinPriceApiT},
-        common_types::{Account, Network},
-        ledger::LedgerApiT,
+        common_types::{Account, DerivationScheme, Network, Token},
+        ledger::{LedgerApiT, LedgerError},
         storage::StorageApiT,
     },
     app::{ApiRegistry, StateRegistry},
@@ -24,20 +26,32 @@ mod view;
 const ACCOUNT_STORAGE_NAME: &str = "accounts.json";
 
 type AccountList = Vec<(Network, Vec<Account>)>;
+type AccountsDiscoveryResult = Result<(), (Network, LedgerError)>;
+type Balances = HashMap<(Network, Account), BigDecimal>;
+type TokenBalances = HashMap<(Account, Token), BigDecimal>;
 
 pub struct Model<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT> {
     selected_network: Option<NetworkIdx>,
     selected_account: Option<AccountIdx>,
     coin_prices: HashMap<Network, Option<Decimal>>,
-    balances: HashMap<(Network, Account), BigDecimal>,
+    balances: Balances,
+    token_balances: TokenBalances,
+    /// Index of derivation scheme in `StateRegistry::derivation_schemes` used for accounts
+    /// discovery. The first scheme is used if network is missing.
+    derivation_schemes: HashMap<Network, usize>,
+    /// Reason the last accounts discovery failed.
+    accounts_discovery_error: Option<(Network, LedgerError)>,
     show_navigation_help: bool,
 
     state: StateRegistry,
 
+    discovered_accounts: Option<UnboundedReceiver<(Network, Account)>>,
+
     coin_price_task: ApiTask<C, HashMap<Network, Option<Decimal>>>,
-    account_balances_task: ApiTask<M, HashMap<(Network, Account), BigDecimal>>,
-    fetch_accounts_task: ApiTask<L, (Network, Vec<Account>)>,
+    account_balances_task: ApiTask<Arc<M>, (Balances, TokenBalances)>,
+    fetch_accounts_task: ApiTask<(L, Arc<M>), AccountsDiscoveryResult>,
     store_accounts_task: ApiTask<S, ()>,
+    cancel_ledger_requests: CancellationToken,
 }
 
 type AccountIdx = usize;
@@ -65,16 +79,18 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
             state.device_accounts = Some(accounts);
         } else {
             // TODO: Make it empty and add networks only on user request.
-            state.device_accounts = Some(vec![
-                (Network::Bitcoin, vec![]),
-                (Network::Ethereum, vec![]),
-            ]);
+            state.device_accounts = Some(Network::all().map(|network| (network, vec![])).collect());
         }
 
+        let blockchain_monitoring_api =
+            Arc::new(api_registry.blockchain_monitoring_api.take().unwrap());
+
         let coin_price_task = ApiTask::new(api_registry.coin_price_api.take().unwrap());
-        let account_balances_task =
-            ApiTask::new(api_registry.blockchain_monitoring_api.take().unwrap());
-        let fetch_accounts_task = ApiTask::new(api_registry.ledger_api.take().unwrap());
+        let account_balances_task = ApiTask::new(blockchain_monitoring_api.clone());
+        let fetch_accounts_task = ApiTask::new((
+            api_registry.ledger_api.take().unwrap(),
+            blockchain_monitoring_api,
+        ));
         let store_accounts_task = ApiTask::new(api_registry.storage_api.take().unwrap());
 
         (
@@ -83,30 +99,60 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
                 selected_account: None,
                 coin_prices: HashMap::new(),
                 balances: HashMap::new(),
+                token_balances: HashMap::new(),
+                derivation_schemes: HashMap::new(),
+                accounts_discovery_error: None,
                 show_navigation_help: false,
 
                 state,
 
+                discovered_accounts: None,
+
                 coin_price_task,
                 account_balances_task,
                 fetch_accounts_task,
                 store_accounts_task,
+                cancel_ledger_requests: CancellationToken::new(),
             },
             api_registry,
         )
     }
 
     async fn tick_logic(&mut self) {
-        if let Some((network, accounts)) = self.fetch_accounts_task.try_fetch_value().await {
+        let mut new_accounts_discovered = false;
+        while let Some(Ok((network, account))) = self
+            .discovered_accounts
+            .as_mut()
+            .map(|receiver| receiver.try_recv())
+        {
             let device_accounts = self.state.device_accounts.as_mut().unwrap();
-            let idx = device_accounts.iter().position(|(nw, _)| *nw == network);
-            if let Some(idx) = idx {
-                device_accounts[idx] = (network, accounts);
-            } else {
-                device_accounts.push((network, accounts));
+            // Account is listed in every network it's valid in to show balances per network.
+            for network in network.sharing_accounts() {
+                let idx = device_accounts.iter().position(|(nw, _)| *nw == network);
+                if let Some(idx) = idx {
+                    let accounts = &mut device_accounts[idx].1;
+                    if accounts
+                        .iter()
+                        .any(|acc| acc.public_key == account.public_key)
+                    {
+                        continue;
+                    }
+
+                    accounts.push(account.clone());
+                } else {
+                    device_accounts.push((network, vec![account.clone()]));
+                }
+
+                new_accounts_discovered = true;
             }
+        }
+
+        if let Some(result) = self.fetch_accounts_task.try_fetch_value().await {
+            self.accounts_discovery_error = result.err();
+        }
 
-            let device_accounts = device_accounts.clone();
+        if new_accounts_discovered {
+            let device_accounts = self.state.device_accounts.clone().unwrap();
             let spawn_store_task = |mut storage_api: S| {
                 tokio::task::spawn(async move {
                     let data = serde_json::to_string(&device_accounts).unwrap();
@@ -121,10 +167,14 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
 
         let spawn_coin_price_task = |coin_price_api: C| {
             tokio::task::spawn(async move {
-                let prices =
-                    [Coin::BTC, Coin::ETH].map(|coin| coin_price_api.get_price(coin, Coin::USDT));
+                let networks: Vec<_> = Network::all().collect();
+                let prices = networks.iter().map(|network| async {
+                    match network.get_info().coin {
+                        Some(coin) => coin_price_api.get_price(coin, Coin::USDT).await,
+                        None => None,
+                    }
+                });
                 let prices = join_all(prices).await;
-                let networks = [Network::Bitcoin, Network::Ethereum];
 
                 let coin_prices = networks.into_iter().zip_eq(prices.into_iter()).collect();
 
@@ -141,7 +191,8 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
         }
 
         let accounts = self.state.device_accounts.clone();
-        let spawn_account_balances_task = |blockchain_monitoring_api: M| {
+        let tokens = self.state.tokens.clone();
+        let spawn_account_balances_task = |blockchain_monitoring_api: Arc<M>| {
             tokio::task::spawn(async move {
                 let accounts: Vec<_> = accounts
                     .into_iter()
@@ -156,19 +207,36 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
                 });
                 let balances = join_all(balances).await;
 
+                let account_tokens: Vec<_> = accounts
+                    .iter()
+                    .flat_map(|(network, account)| {
+                        tokens
+                            .iter()
+                            .filter(|token| token.network == *network)
+                            .map(|token| (account.clone(), token.clone()))
+                    })
+                    .collect();
+
+                let token_balances = account_tokens.iter().map(|(account, token)| {
+                    blockchain_monitoring_api.get_token_balance(token.network, account, token)
+                });
+                let token_balances = join_all(token_balances).await;
+
                 let balances = accounts.into_iter().zip_eq(balances).collect();
+                let token_balances = account_tokens.into_iter().zip_eq(token_balances).collect();
 
-                (blockchain_monitoring_api, balances)
+                (blockchain_monitoring_api, (balances, token_balances))
             })
         };
 
         // TODO: Request balances only when user updates the screen.
-        if let Some(balances) = self
+        if let Some((balances, token_balances)) = self
             .account_balances_task
             .try_fetch_value_and_rerun(spawn_account_balances_task)
             .await
         {
             self.balances = balances;
+            self.token_balances = token_balances;
         }
     }
 
@@ -180,17 +248,56 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
             .expect("TODO: Enforce this rule at app level?")
             .0;
 
-        let spawn_task = |ledger_api: L| {
-            tokio::task::spawn(async move {
-                ledger_api.open_app(&active_device, network).await;
-
-                let accounts = ledger_api.discover_accounts(&active_device, network).await;
+        let scheme = self.derivation_scheme(network).clone();
+        let gap_limit = self.state.account_discovery_gap_limit;
+        // Accounts from the previous discovery are dropped together with its receiver.
+        let (discovered_accounts_sender, discovered_accounts) = mpsc::unbounded_channel();
+        self.discovered_accounts = Some(discovered_accounts);
 
-                (ledger_api, (network, accounts))
+        let cancel = self.cancel_ledger_requests.clone();
+        let spawn_task = |(ledger_api, blockchain_monitoring_api): (L, Arc<M>)| {
+            tokio::task::spawn(async move {
+                let result = async {
+                    ledger_api
+                        .open_app(&active_device, network, &cancel)
+                        .await?;
+
+                    let accounts = ledger_api
+                        .discover_accounts(&active_device, network, &scheme)
+                        .await;
+
+                    discover_used_accounts(
+                        accounts,
+                        blockchain_monitoring_api.as_ref(),
+                        network,
+                        gap_limit,
+                        |account| {
+                            let _ = discovered_accounts_sender.send((network, account));
+                        },
+                    )
+                    .await
+                }
+                .await;
+
+                let result = result.map_err(|error| (network, error));
+
+                ((ledger_api, blockchain_monitoring_api), result)
             })
         };
 
         self.fetch_accounts_task.run(spawn_task).await;
+        self.accounts_discovery_error = None;
+    }
+
+    fn derivation_scheme(&self, network: Network) -> &DerivationScheme {
+        let idx = self.derivation_schemes.get(&network).copied().unwrap_or(0);
+        &self.state.derivation_schemes[&network][idx]
+    }
+
+    fn switch_derivation_scheme(&mut self, network: Network) {
+        let scheme_count = self.state.derivation_schemes[&network].len();
+        let idx = self.derivation_schemes.entry(network).or_default();
+        *idx = (*idx + 1) % scheme_count;
     }
 
     pub async fn deconstruct(
@@ -198,14 +305,59 @@ impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApi
         mut api_registry: ApiRegistry<L, C, M, S>,
     ) -> (StateRegistry, ApiRegistry<L, C, M, S>) {
         api_registry.coin_price_api = Some(self.coin_price_task.abort().await);
-        api_registry.blockchain_monitoring_api = Some(self.account_balances_task.abort().await);
-        api_registry.ledger_api = Some(self.fetch_accounts_task.abort().await);
+        let _ = self.account_balances_task.abort().await;
+        self.cancel_ledger_requests.cancel();
+        let (ledger_api, blockchain_monitoring_api) = self.fetch_accounts_task.abort().await;
+        api_registry.ledger_api = Some(ledger_api);
+        api_registry.blockchain_monitoring_api = Some(
+            Arc::into_inner(blockchain_monitoring_api)
+                .expect("All the tasks using blockchain monitoring api should be finished"),
+        );
         api_registry.storage_api = Some(self.store_accounts_task.abort().await);
 
         (self.state, api_registry)
     }
 }
 
+/// Walks through discovered accounts until `gap_limit` unused accounts in a row are found.
+/// Reports all the used accounts, unused ones between them and the first unused account
+/// after the last used one, so there's always a fresh account to receive funds to.
+/// Accounts discovered before device failure are reported as well.
+async fn discover_used_accounts<M: BlockchainMonitoringApiT>(
+    mut accounts: BoxStream<'static, Result<Account, LedgerError>>,
+    blockchain_monitoring_api: &M,
+    network: Network,
+    gap_limit: usize,
+    mut report_account: impl FnMut(Account),
+) -> Result<(), LedgerError> {
+    let mut unused_in_row = 0;
+    let mut unreported_unused = vec![];
+
+    while unused_in_row < gap_limit {
+        let Some(account) = accounts.next().await.transpose()? else {
+            break;
+        };
+
+        if blockchain_monitoring_api
+            .is_account_used(network, &account)
+            .await
+        {
+            unused_in_row = 0;
+            unreported_unused.drain(..).for_each(&mut report_account);
+            report_account(account);
+        } else {
+            unused_in_row += 1;
+            if unused_in_row == 1 {
+                report_account(account);
+            } else {
+                unreported_unused.push(account);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl<L: LedgerApiT, C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT> ScreenT
     for Model<L, C, M, S>
 {
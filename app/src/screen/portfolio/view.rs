@@ -2,7 +2,7 @@ use input_mapping_common::InputMappingT;
 use ratatui::{
     Frame,
     style::{Style, Stylize},
-    text::Text,
+    text::{Line, Span, Text},
 };
 use tui_tree_widget::{Tree, TreeItem, TreeState};
 
@@ -66,7 +66,7 @@ fn render_account_table<
     let mut tree_items: Vec<_> = accounts
         .iter()
         .map(|(network, accounts)| {
-            let network_name = network.get_info().name;
+            let network_name = network.get_info().name.to_string();
 
             let mut leafs: Vec<_> = accounts
                 .iter()
@@ -76,18 +76,50 @@ fn render_account_table<
                     // TODO: Pretty formatting.
                     let text = Text::from(pk[..8].to_string().clone()).fg(resources.main_color);
 
-                    TreeItem::new_leaf(pk, text)
+                    let token_leafs: Vec<_> = model
+                        .state
+                        .tokens
+                        .iter()
+                        .filter(|token| token.network == *network)
+                        .map(|token| {
+                            let balance = model
+                                .token_balances
+                                .get(&(account.clone(), token.clone()))
+                                .map(|balance| balance.to_string())
+                                .unwrap_or_else(|| "-".to_string());
+                            let text = Text::from(format!("{} {}", balance, token.symbol))
+                                .fg(resources.main_color);
+
+                            TreeItem::new_leaf(token.contract.clone(), text)
+                        })
+                        .collect();
+
+                    TreeItem::new(pk, text, token_leafs).expect("Duplicate tokens found")
                 })
                 .collect();
 
-            let add_account_tree_item = TreeItem::new_leaf(
-                "AddAccount".to_string(),
-                Text::from("+ discover accounts").fg(resources.main_color),
-            );
+            let scheme = model.derivation_scheme(*network);
+            let add_account_text = match &model.accounts_discovery_error {
+                Some((error_network, error)) if error_network == network => Text::from(format!(
+                    "+ discover accounts [{}: {}] - {}",
+                    scheme.name, scheme.template, error
+                ))
+                .fg(resources.accent_color),
+                _ => Text::from(format!(
+                    "+ discover accounts [{}: {}]",
+                    scheme.name, scheme.template
+                ))
+                .fg(resources.main_color),
+            };
+            let add_account_tree_item =
+                TreeItem::new_leaf("AddAccount".to_string(), add_account_text);
 
             leafs.push(add_account_tree_item);
 
-            let text = Text::from(network_name.clone()).fg(resources.main_color);
+            let mut text = Line::from(network_name.clone()).fg(resources.main_color);
+            if network.is_testnet() {
+                text.push_span(Span::from(" TESTNET").bold().fg(resources.accent_color));
+            }
             TreeItem::new(network_name, text, leafs).expect("Duplicate networks found")
         })
         .collect();
@@ -105,8 +137,13 @@ fn render_account_table<
 
     let mut tree_state = TreeState::default();
 
-    for &(network, _) in accounts {
-        tree_state.open(vec![network.get_info().name]);
+    for (network, accounts) in accounts {
+        let network_name = network.get_info().name.to_string();
+        tree_state.open(vec![network_name.clone()]);
+
+        for account in accounts {
+            tree_state.open(vec![network_name.clone(), account.get_info().public_key]);
+        }
     }
 
     if let Some(network_idx) = model.selected_network {
@@ -114,14 +151,13 @@ fn render_account_table<
             tree_state.select(vec!["AddNetwork".to_string()]);
         } else {
             let (network, accounts) = &accounts[network_idx];
-            let mut path = vec![network.get_info().name];
+            let mut path = vec![network.get_info().name.to_string()];
 
             if let Some(account_idx) = model.selected_account {
                 if account_idx == accounts.len() {
                     path.push("AddAccount".to_string());
                 } else {
-                    let account = accounts[account_idx].get_info().public_key[..8].to_string();
-                    path.push(account);
+                    path.push(accounts[account_idx].get_info().public_key);
                 }
             }
 
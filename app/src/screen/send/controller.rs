@@ -4,7 +4,10 @@ use input_mapping_derive::InputMapping;
 use ratatui::crossterm::event::{Event, KeyCode, KeyEvent};
 
 use super::Model;
-use crate::{api::ledger::LedgerApiT, screen::OutgoingMessage};
+use crate::{
+    api::{blockchain_monitoring::BlockchainMonitoringApiT, ledger::LedgerApiT},
+    screen::OutgoingMessage,
+};
 
 #[derive(InputMapping)]
 pub enum InputEvent {
@@ -24,6 +27,10 @@ pub enum InputEvent {
     #[description = "Paste a receiver address"]
     PasteAddress,
 
+    #[key = 't']
+    #[description = "Switch between coin and tokens to send"]
+    SwitchAsset,
+
     #[key = "KeyCode::Backspace"]
     #[description = "Erase a symbol from amount"]
     EraseSymbol,
@@ -33,9 +40,9 @@ pub enum InputEvent {
     SignAndSend,
 }
 
-pub(super) async fn process_input<L: LedgerApiT>(
+pub(super) async fn process_input<L: LedgerApiT, M: BlockchainMonitoringApiT>(
     event: &Event,
-    model: &mut Model<L>,
+    model: &mut Model<L, M>,
 ) -> Option<OutgoingMessage> {
     let input_event = InputEvent::map_event(event.clone());
 
@@ -59,6 +66,11 @@ pub(super) async fn process_input<L: LedgerApiT>(
 
                 return None;
             }
+            InputEvent::SwitchAsset => {
+                model.switch_asset();
+
+                return None;
+            }
             InputEvent::EraseSymbol => {
                 let _ = model.send_amount.pop();
 
@@ -2,12 +2,16 @@ use std::str::FromStr;
 
 use bigdecimal::BigDecimal;
 use ratatui::{Frame, crossterm::event::Event};
+use tokio_util::sync::CancellationToken;
 
 use super::{OutgoingMessage, ScreenT, common::api_task::ApiTask, resources::Resources};
 use crate::{
     api::{
-        blockchain_monitoring::BlockchainMonitoringApiT, coin_price::CoinPriceApiT,
-        ledger::LedgerApiT, storage::StorageApiT,
+        blockchain_monitoring::{BlockchainMonitoringApiT, SignedTransaction, TransactionUid},
+        coin_price::CoinPriceApiT,
+        common_types::{Account, Token},
+        ledger::{LedgerApiT, LedgerError},
+        storage::StorageApiT,
     },
     app::{ApiRegistry, StateRegistry},
 };
@@ -15,63 +19,372 @@ use crate::{
 mod controller;
 mod view;
 
-type SignedTx = Vec<u8>;
-
-pub struct Model<L: LedgerApiT> {
+pub struct Model<L: LedgerApiT, M: BlockchainMonitoringApiT> {
     show_navigation_help: bool,
     receiver_address: Option<String>,
     send_amount: String,
+    /// Token to transfer, native coin is transferred if it's `None`.
+    token: Option<Token>,
+    tx_status: Option<TxStatus>,
 
     state: StateRegistry,
 
-    sign_tx_task: ApiTask<L, SignedTx>,
+    send_tx_task: ApiTask<(L, M), Result<TransactionUid, SendTxError>>,
+    cancel_ledger_requests: CancellationToken,
+}
+
+enum TxStatus {
+    InProgress,
+    Sent(TransactionUid),
+    Failed(SendTxError),
 }
 
-impl<L: LedgerApiT> Model<L> {
-    pub fn construct<C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT>(
+enum SendTxError {
+    Prepare,
+    Ledger(LedgerError),
+    Broadcast,
+}
+
+impl<L: LedgerApiT, M: BlockchainMonitoringApiT> Model<L, M> {
+    pub fn construct<C: CoinPriceApiT, S: StorageApiT>(
         state: StateRegistry,
         mut api_registry: ApiRegistry<L, C, M, S>,
     ) -> (Self, ApiRegistry<L, C, M, S>) {
-        let sign_tx_task = ApiTask::new(api_registry.ledger_api.take().unwrap());
+        let send_tx_task = ApiTask::new((
+            api_registry.ledger_api.take().unwrap(),
+            api_registry.blockchain_monitoring_api.take().unwrap(),
+        ));
 
         (
             Self {
                 show_navigation_help: false,
                 receiver_address: None,
                 send_amount: "".to_string(),
+                token: None,
+                tx_status: None,
 
                 state,
 
-                sign_tx_task,
+                send_tx_task,
+                cancel_ledger_requests: CancellationToken::new(),
             },
             api_registry,
         )
     }
 
+    async fn tick_logic(&mut self) {
+        if let Some(tx_uid) = self.send_tx_task.try_fetch_value().await {
+            self.tx_status = Some(match tx_uid {
+                Ok(tx_uid) => TxStatus::Sent(tx_uid),
+                Err(error) => TxStatus::Failed(error),
+            });
+        }
+    }
+
     fn is_amount_valid(&self) -> bool {
         BigDecimal::from_str(&self.send_amount).is_ok()
     }
 
+    /// Switches to the next token of the network, then back to the native coin.
+    pub fn switch_asset(&mut self) {
+        let network = self
+            .state
+            .selected_account
+            .as_ref()
+            .expect("Selected account should be present in state") // TODO: Enforce this rule at `app` level?
+            .0;
+
+        let mut tokens = self
+            .state
+            .tokens
+            .iter()
+            .filter(|token| token.network == network);
+
+        self.token = match &self.token {
+            None => tokens.next().cloned(),
+            Some(current) => tokens.skip_while(|token| *token != current).nth(1).cloned(),
+        };
+    }
+
     pub async fn sign_and_send_tx(&mut self) {
-        // TODO.
+        if matches!(self.tx_status, Some(TxStatus::InProgress)) {
+            return;
+        }
+
+        let Some(receiver) = self.receiver_address.clone() else {
+            return;
+        };
+        let Ok(amount) = BigDecimal::from_str(&self.send_amount) else {
+            return;
+        };
+
+        let (network, sender) = self
+            .state
+            .selected_account
+            .clone()
+            .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+        let device = self
+            .state
+            .active_device
+            .clone()
+            .expect("Active device should be present in state") // TODO: Enforce this rule at `app` level?
+            .0;
+
+        let token = self.token.clone();
+        let cancel = self.cancel_ledger_requests.clone();
+        let spawn_task = |(ledger_api, blockchain_monitoring_api): (L, M)| {
+            tokio::task::spawn(async move {
+                let receiver = Account::external(receiver);
+
+                let tx_uid = async {
+                    let unsigned = match &token {
+                        Some(token) => {
+                            blockchain_monitoring_api
+                                .prepare_token_transfer(network, token, &sender, &receiver, amount)
+                                .await
+                        }
+                        None => {
+                            blockchain_monitoring_api
+                                .prepare_transfer(network, &sender, &receiver, amount)
+                                .await
+                        }
+                    }
+                    .ok_or(SendTxError::Prepare)?;
+
+                    ledger_api
+                        .open_app(&device, network, &cancel)
+                        .await
+                        .map_err(SendTxError::Ledger)?;
+
+                    if let Some(token) = &token {
+                        ledger_api
+                            .provide_token_info(&device, token)
+                            .await
+                            .map_err(SendTxError::Ledger)?;
+                    }
+
+                    let signature = ledger_api
+                        .sign_message(unsigned.payload.clone(), &device, network, &sender)
+                        .await
+                        .map_err(SendTxError::Ledger)?;
+
+                    blockchain_monitoring_api
+                        .send_transaction(
+                            network,
+                            &SignedTransaction {
+                                unsigned,
+                                signature,
+                            },
+                        )
+                        .await
+                        .ok_or(SendTxError::Broadcast)
+                }
+                .await;
+
+                ((ledger_api, blockchain_monitoring_api), tx_uid)
+            })
+        };
+
+        self.send_tx_task.run(spawn_task).await;
+        self.tx_status = Some(TxStatus::InProgress);
     }
 
-    pub async fn deconstruct<C: CoinPriceApiT, M: BlockchainMonitoringApiT, S: StorageApiT>(
+    pub async fn deconstruct<C: CoinPriceApiT, S: StorageApiT>(
         self,
         mut api_registry: ApiRegistry<L, C, M, S>,
     ) -> (StateRegistry, ApiRegistry<L, C, M, S>) {
-        api_registry.ledger_api = Some(self.sign_tx_task.abort().await);
+        self.cancel_ledger_requests.cancel();
+        let (ledger_api, blockchain_monitoring_api) = self.send_tx_task.abort().await;
+        api_registry.ledger_api = Some(ledger_api);
+        api_registry.blockchain_monitoring_api = Some(blockchain_monitoring_api);
 
         (self.state, api_registry)
     }
 }
 
-impl<L: LedgerApiT> ScreenT for Model<L> {
+impl<L: LedgerApiT, M: BlockchainMonitoringApiT> ScreenT for Model<L, M> {
     fn render(&self, frame: &mut Frame<'_>, resources: &Resources) {
         view::render(self, frame, resources);
     }
 
     async fn tick(&mut self, event: Option<Event>) -> Option<OutgoingMessage> {
+        self.tick_logic().await;
+
         controller::process_input(event.as_ref()?, self).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy::{
+        consensus::{SignableTransaction, TxEip1559},
+        primitives::{Address, Signature, TxKind, U256},
+        rlp::Decodable,
+    };
+    use futures::StreamExt;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+    use super::*;
+    use crate::api::{
+        blockchain_monitoring::mock::BlockchainMonitoringApiMock,
+        coin_price::mock::CoinPriceApiMock,
+        common_types::{DerivationScheme, Network},
+        ledger::{Config as LedgerApiConfig, mock::LedgerApiMock},
+        storage::mock::StorageApiMock,
+    };
+
+    fn key(code: KeyCode) -> Option<Event> {
+        Some(Event::Key(KeyEvent::from(code)))
+    }
+
+    #[tokio::test]
+    async fn test_send_ethereum_transfer() {
+        let ledger_api = LedgerApiMock::new(1, 2);
+        let device = ledger_api.discover_devices().await.remove(0);
+        let device_info = ledger_api.get_device_info(&device).await.unwrap();
+        let scheme = &DerivationScheme::builtin(Network::ETHEREUM)[0];
+        ledger_api
+            .open_app(&device, Network::ETHEREUM, &CancellationToken::new())
+            .await
+            .unwrap();
+        let accounts: Vec<_> = ledger_api
+            .discover_accounts(&device, Network::ETHEREUM, scheme)
+            .await
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let (sender, receiver) = (accounts[0].clone(), accounts[1].clone());
+
+        let mut state = StateRegistry::new(&LedgerApiConfig::default());
+        state.active_device = Some((device, device_info));
+        state.selected_account = Some((Network::ETHEREUM, sender.clone()));
+        let api_registry = ApiRegistry::new(
+            ledger_api,
+            CoinPriceApiMock::new(),
+            BlockchainMonitoringApiMock::new(0),
+            StorageApiMock::new(),
+        );
+        let (mut model, api_registry) = Model::construct(state, api_registry);
+
+        // Address is pasted from the clipboard, which isn't available in tests.
+        model.receiver_address = Some(receiver.public_key.clone());
+        for char in ['0', '.', '2', '5'] {
+            model.tick(key(KeyCode::Char(char))).await;
+        }
+        model.tick(key(KeyCode::Enter)).await;
+
+        for _ in 0..100 {
+            if !matches!(model.tx_status, Some(TxStatus::InProgress)) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            model.tick(None).await;
+        }
+        let Some(TxStatus::Sent(tx_uid)) = &model.tx_status else {
+            panic!("Transaction should be sent");
+        };
+        assert_eq!(tx_uid.uid, "MOCK_SENT_TX_HASH");
+
+        let (_, mut api_registry) = model.deconstruct(api_registry).await;
+        let sent = api_registry
+            .blockchain_monitoring_api
+            .take()
+            .unwrap()
+            .sent_transactions();
+        assert_eq!(sent.len(), 1);
+
+        let tx = TxEip1559::decode(&mut &sent[0].unsigned.payload[1..]).unwrap();
+        assert_eq!(tx.to, TxKind::Call(receiver.public_key.parse().unwrap()));
+        assert_eq!(tx.value, U256::from(250_000_000_000_000_000u64));
+
+        let signature = &sent[0].signature;
+        let signature = Signature::try_from(&[&signature[1..], &signature[..1]].concat()[..]);
+        let signer = tx.into_signed(signature.unwrap()).recover_signer().unwrap();
+        assert_eq!(signer, sender.public_key.parse::<Address>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_send_bitcoin_transfer() {
+        use bitcoin::{PublicKey, psbt::Psbt, secp256k1::Secp256k1, sighash::SighashCache};
+
+        let ledger_api = LedgerApiMock::new(1, 2);
+        let device = ledger_api.discover_devices().await.remove(0);
+        let device_info = ledger_api.get_device_info(&device).await.unwrap();
+        let scheme = &DerivationScheme::builtin(Network::BITCOIN)[0];
+        ledger_api
+            .open_app(&device, Network::BITCOIN, &CancellationToken::new())
+            .await
+            .unwrap();
+        let accounts: Vec<_> = ledger_api
+            .discover_accounts(&device, Network::BITCOIN, scheme)
+            .await
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let (sender, receiver) = (accounts[0].clone(), accounts[1].clone());
+
+        let mut state = StateRegistry::new(&LedgerApiConfig::default());
+        state.active_device = Some((device, device_info));
+        state.selected_account = Some((Network::BITCOIN, sender.clone()));
+        let api_registry = ApiRegistry::new(
+            ledger_api,
+            CoinPriceApiMock::new(),
+            BlockchainMonitoringApiMock::new(0),
+            StorageApiMock::new(),
+        );
+        let (mut model, api_registry) = Model::construct(state, api_registry);
+
+        let receiver_address = receiver.receive_address(Network::BITCOIN);
+        model.receiver_address = Some(receiver_address.clone());
+        for char in ['0', '.', '0', '0', '1'] {
+            model.tick(key(KeyCode::Char(char))).await;
+        }
+        model.tick(key(KeyCode::Enter)).await;
+
+        for _ in 0..100 {
+            if !matches!(model.tx_status, Some(TxStatus::InProgress)) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            model.tick(None).await;
+        }
+        let Some(TxStatus::Sent(tx_uid)) = &model.tx_status else {
+            panic!("Transaction should be sent");
+        };
+        let tx_uid = tx_uid.clone();
+
+        let (_, mut api_registry) = model.deconstruct(api_registry).await;
+        let sent = api_registry
+            .blockchain_monitoring_api
+            .take()
+            .unwrap()
+            .sent_transactions();
+        assert_eq!(sent.len(), 1);
+
+        let psbt = Psbt::deserialize(&sent[0].signature).unwrap();
+        assert_eq!(tx_uid.uid, psbt.unsigned_tx.txid().to_string());
+
+        let output = &psbt.unsigned_tx.output[0];
+        assert_eq!(output.value, 100_000);
+        assert_eq!(
+            bitcoin::Address::from_script(&output.script_pubkey, bitcoin::Network::Bitcoin)
+                .unwrap()
+                .to_string(),
+            receiver_address
+        );
+
+        let public_key: PublicKey = sender.public_key.parse().unwrap();
+        let (message, _) = psbt
+            .sighash_ecdsa(0, &mut SighashCache::new(&psbt.unsigned_tx))
+            .unwrap();
+        Secp256k1::verification_only()
+            .verify_ecdsa(
+                &message,
+                &psbt.inputs[0].partial_sigs[&public_key].sig,
+                &public_key.inner,
+            )
+            .unwrap();
+    }
+}
@@ -7,17 +7,17 @@ use ratatui::{
 };
 
 use crate::{
-    api::ledger::LedgerApiT,
+    api::{blockchain_monitoring::BlockchainMonitoringApiT, ledger::LedgerApiT},
     screen::{
         common::{self, BackgroundWidget},
         resources::Resources,
     },
 };
 
-use super::Model;
+use super::{Model, SendTxError, TxStatus};
 
-pub(super) fn render<L: LedgerApiT>(
-    model: &Model<L>,
+pub(super) fn render<L: LedgerApiT, M: BlockchainMonitoringApiT>(
+    model: &Model<L, M>,
     frame: &mut Frame<'_>,
     resources: &Resources,
 ) {
@@ -25,14 +25,12 @@ pub(super) fn render<L: LedgerApiT>(
 
     frame.render_widget(BackgroundWidget::new(resources.background_color), area);
 
-    let sender = model
+    let (network, sender) = model
         .state
         .selected_account
         .as_ref()
-        .expect("Selected account should be present in state") // TODO: Enforce this rule at `app` level?
-        .1
-        .get_info()
-        .public_key;
+        .expect("Selected account should be present in state"); // TODO: Enforce this rule at `app` level?
+    let sender = sender.get_info().public_key;
     let sender = Text::from(sender).fg(resources.main_color);
     let sender_label = Text::from("sender:").fg(resources.main_color);
 
@@ -43,6 +41,13 @@ pub(super) fn render<L: LedgerApiT>(
     };
     let receiver_label = Text::from("receiver:").fg(resources.main_color);
 
+    let asset = match &model.token {
+        Some(token) => token.symbol.clone(),
+        None => network.get_info().symbol.to_string(),
+    };
+    let asset = Text::from(format!("{} [t]", asset)).fg(resources.main_color);
+    let asset_label = Text::from("asset:").fg(resources.main_color);
+
     let amount = if model.send_amount.is_empty() {
         Text::from("start typing amount").fg(resources.accent_color)
     } else {
@@ -51,6 +56,25 @@ pub(super) fn render<L: LedgerApiT>(
     let amount_label = Text::from("amount:").fg(resources.main_color);
     let invalid_amount_label = Text::from("invalid amount").fg(resources.accent_color);
 
+    let tx_status = match &model.tx_status {
+        None => Text::from(""),
+        Some(TxStatus::InProgress) => {
+            Text::from("confirm transaction on the device").fg(resources.accent_color)
+        }
+        Some(TxStatus::Sent(tx_uid)) => {
+            Text::from(format!("sent: {}", tx_uid.uid)).fg(resources.main_color)
+        }
+        Some(TxStatus::Failed(SendTxError::Prepare)) => {
+            Text::from("failed to prepare transaction").fg(resources.accent_color)
+        }
+        Some(TxStatus::Failed(SendTxError::Ledger(error))) => {
+            Text::from(error.to_string()).fg(resources.accent_color)
+        }
+        Some(TxStatus::Failed(SendTxError::Broadcast)) => {
+            Text::from("failed to broadcast transaction").fg(resources.accent_color)
+        }
+    };
+
     let [
         sender_label_area,
         sender_area,
@@ -58,9 +82,14 @@ pub(super) fn render<L: LedgerApiT>(
         receiver_label_area,
         receiver_area,
         _,
+        asset_label_area,
+        asset_area,
+        _,
         amount_label_area,
         amount_area,
         invalid_amount_label_area,
+        _,
+        tx_status_area,
     ] = Layout::vertical([
         Constraint::Length(sender_label.height() as u16),
         Constraint::Length(sender.height() as u16),
@@ -68,9 +97,14 @@ pub(super) fn render<L: LedgerApiT>(
         Constraint::Length(receiver_label.height() as u16),
         Constraint::Length(receiver.height() as u16),
         Constraint::Length(1),
+        Constraint::Length(asset_label.height() as u16),
+        Constraint::Length(asset.height() as u16),
+        Constraint::Length(1),
         Constraint::Length(amount_label.height() as u16),
         Constraint::Length(amount.height() as u16),
         Constraint::Length(invalid_amount_label.height() as u16),
+        Constraint::Length(1),
+        Constraint::Length(1),
     ])
     .flex(Flex::Center)
     .areas(area);
@@ -79,6 +113,8 @@ pub(super) fn render<L: LedgerApiT>(
     frame.render_widget(sender.centered(), sender_area);
     frame.render_widget(receiver_label.centered(), receiver_label_area);
     frame.render_widget(receiver.centered(), receiver_area);
+    frame.render_widget(asset_label.centered(), asset_label_area);
+    frame.render_widget(asset.centered(), asset_area);
     frame.render_widget(amount_label.centered(), amount_label_area);
     frame.render_widget(amount.centered(), amount_area);
 
@@ -86,6 +122,10 @@ pub(super) fn render<L: LedgerApiT>(
         frame.render_widget(invalid_amount_label.centered(), invalid_amount_label_area);
     }
 
+    frame.render_widget(tx_status.centered(), tx_status_area);
+
+    common::render_testnet_marker(*network, frame, resources);
+
     if model.show_navigation_help {
         let mapping = super::controller::InputEvent::get_mapping();
         common::render_navigation_help(mapping, frame, resources);
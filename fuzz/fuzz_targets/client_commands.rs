@@ -0,0 +1,9 @@
+#![no_main]
+
+use ledger_tui_fuzz::api::ledger::fuzzing;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (Vec<Vec<u8>>, Vec<(u8, bool, Vec<u8>)>)| {
+    let (elements, requests) = input;
+    fuzzing::bitcoin::execute_client_commands(&elements, &requests);
+});
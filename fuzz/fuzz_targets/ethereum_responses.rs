@@ -0,0 +1,6 @@
+#![no_main]
+
+use ledger_tui_fuzz::api::ledger::fuzzing;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| fuzzing::ethereum::decode(data));
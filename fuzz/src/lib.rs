@@ -0,0 +1,7 @@
+//! Fuzz targets of the APDU response decoders and of the client commands interpreter
+//! of the bitcoin app. Targets reach them through `api::ledger::fuzzing`, which exists
+//! only when built by cargo-fuzz.
+
+#[allow(dead_code, unused_imports)]
+#[path = "../../app/src/api/mod.rs"]
+pub mod api;